@@ -0,0 +1,211 @@
+//! Retained capacity that follows recent usage.
+//!
+//! A reusable container keeps the largest allocation it has ever needed. A
+//! [`LoadFactorShrinkPolicy`](crate::LoadFactorShrinkPolicy) or a
+//! [maximum retained capacity](crate::ReusableVec::set_max_retained_capacity)
+//! bounds that, but both need a number picked up front. An
+//! [`AdaptiveCapacityPolicy`] picks it from the container's own history
+//! instead: it remembers how many elements the last few cycles used and
+//! shrinks the allocation once it is far larger than a high percentile of
+//! them, so a long-running server sheds the memory of an old spike without
+//! any tuning.
+//!
+//! [`ReusableVec`](crate::ReusableVec), [`ReusableString`](crate::ReusableString)
+//! and [`ReusableHashMap`](crate::ReusableHashMap) accept a policy through
+//! `set_adaptive_capacity`.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{AdaptiveCapacityPolicy, ReusableVec};
+//!
+//! let mut batch = ReusableVec::<u64>::default();
+//! batch.set_adaptive_capacity(Some(AdaptiveCapacityPolicy::new(0.95, 8)));
+//!
+//! // A spike grows the allocation...
+//! batch.recycle_same().extend(0..100_000);
+//! assert!(batch.capacity() >= 100_000);
+//!
+//! // ...and once a full window of ordinary batches has been seen, it
+//! // converges to what they need.
+//! for _ in 0..8 {
+//!     batch.recycle_same().extend(0..100);
+//! }
+//! assert!(batch.capacity() < 1000);
+//! ```
+
+/// A policy that shrinks a container's allocation toward a percentile of
+/// the lengths it held over its last few cycles.
+///
+/// Every time a guard is dropped, the length it reached is recorded in a
+/// window of the last [`window`](Self::window) cycles. Once the window is
+/// full, the target is the [`percentile`](Self::percentile) of those
+/// lengths. If the retained capacity is more than twice the target, the
+/// allocation is shrunk to the target; the factor of two keeps a container
+/// that is merely a little oversized from reallocating every cycle.
+///
+/// Since the target comes from a full window, a single large cycle never
+/// causes a shrink, and a container that is only briefly underused keeps
+/// its allocation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveCapacityPolicy {
+    percentile: f64,
+    window: usize,
+}
+
+impl AdaptiveCapacityPolicy {
+    /// Creates a policy that converges on the `percentile` of the lengths
+    /// seen over the last `window` cycles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not in `0.0..=1.0` or if `window` is zero.
+    pub fn new(percentile: f64, window: usize) -> Self {
+        match Self::try_new(percentile, window) {
+            Ok(policy) => policy,
+            Err(msg) => panic!("{}", msg),
+        }
+    }
+
+    fn try_new(percentile: f64, window: usize) -> Result<Self, &'static str> {
+        if !(0.0..=1.0).contains(&percentile) {
+            return Err("percentile must be between 0.0 and 1.0");
+        }
+        if window == 0 {
+            return Err("window must be greater than zero");
+        }
+        Ok(Self { percentile, window })
+    }
+
+    /// The percentile of recent lengths the capacity converges on, between
+    /// `0.0` and `1.0`.
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+
+    /// The number of recent cycles the percentile is taken over.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+impl Default for AdaptiveCapacityPolicy {
+    /// Converges on the 95th percentile of the last 64 cycles.
+    fn default() -> Self {
+        Self::new(0.95, 64)
+    }
+}
+
+/// Deserializes a policy from `percentile` and `window` fields, either of
+/// which falls back to the [`Default`] policy's value when missing.
+///
+/// Out-of-range values are reported as errors instead of panicking.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AdaptiveCapacityPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        struct Fields {
+            percentile: f64,
+            window: usize,
+        }
+
+        impl Default for Fields {
+            fn default() -> Self {
+                let policy = AdaptiveCapacityPolicy::default();
+                Self {
+                    percentile: policy.percentile,
+                    window: policy.window,
+                }
+            }
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        Self::try_new(fields.percentile, fields.window).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The policy together with the lengths it has observed.
+#[derive(Debug)]
+pub(crate) struct AdaptiveCapacity {
+    policy: AdaptiveCapacityPolicy,
+    /// The last `window` lengths, oldest first once `next` wraps around.
+    history: Vec<usize>,
+    next: usize,
+    /// Scratch space for sorting the history, kept to avoid allocating on
+    /// every cycle.
+    sorted: Vec<usize>,
+}
+
+impl AdaptiveCapacity {
+    pub(crate) fn new(policy: AdaptiveCapacityPolicy) -> Self {
+        Self {
+            policy,
+            history: Vec::with_capacity(policy.window),
+            next: 0,
+            sorted: Vec::with_capacity(policy.window),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> AdaptiveCapacityPolicy {
+        self.policy
+    }
+
+    /// Records the length and capacity of a finished cycle, returning the
+    /// capacity the container should be shrunk to, if any.
+    pub(crate) fn observe(&mut self, len: usize, capacity: usize) -> Option<usize> {
+        if self.history.len() < self.policy.window {
+            self.history.push(len);
+        } else {
+            self.history[self.next] = len;
+        }
+        self.next = (self.next + 1) % self.policy.window;
+        if self.history.len() < self.policy.window {
+            return None;
+        }
+
+        self.sorted.clear();
+        self.sorted.extend_from_slice(&self.history);
+        self.sorted.sort_unstable();
+        // The nearest-rank percentile.
+        let rank = (self.policy.percentile * self.sorted.len() as f64).ceil() as usize;
+        let target = self.sorted[rank.saturating_sub(1)];
+
+        // Rounding down keeps a hash table that was built for `target`
+        // entries, and so has a little more room, from being rebuilt again.
+        if capacity / 2 > target {
+            Some(target)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinks_only_after_a_full_window() {
+        let mut adaptive = AdaptiveCapacity::new(AdaptiveCapacityPolicy::new(0.5, 4));
+        assert_eq!(adaptive.observe(1000, 1024), None);
+        for _ in 0..2 {
+            assert_eq!(adaptive.observe(10, 1024), None);
+        }
+        assert_eq!(adaptive.observe(10, 1024), Some(10));
+
+        // The target is a percentile, so one outlier in the window is
+        // ignored.
+        assert_eq!(adaptive.observe(1000, 1024), Some(10));
+        assert_eq!(adaptive.observe(10, 20), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "window must be greater than zero")]
+    fn empty_windows_are_rejected() {
+        AdaptiveCapacityPolicy::new(0.95, 0);
+    }
+}
@@ -0,0 +1,271 @@
+//! An allocation-counting global allocator, for tests that prove recycled hot
+//! paths do not allocate.
+//!
+//! Recycling only pays off if the hot path really stops allocating, and that
+//! is easy to break by accident: a `format!` here, a `collect` there.
+//! Install [`TrackingAllocator`] as the global allocator of a test binary,
+//! and [`measure_allocs`] reports what a closure allocated, while
+//! [`assert_no_alloc!`](crate::assert_no_alloc) fails the test if a block
+//! allocates at all.
+//!
+//! Counts are kept per thread, so tests running in parallel do not disturb
+//! each other, but allocations made by other threads on behalf of the
+//! measured code, such as a thread pool's workers, are not counted.
+//!
+//! This module is only available with the `alloc-tracking` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{alloc_tracking::{measure_allocs, TrackingAllocator}, assert_no_alloc, ReusableVec};
+//!
+//! #[global_allocator]
+//! static GLOBAL: TrackingAllocator = TrackingAllocator::system();
+//!
+//! fn main() {
+//!     let mut reusable = ReusableVec::<u32>::default();
+//!     let warm_up = measure_allocs(|| reusable.recycle::<u32>().extend(0..100));
+//!     assert_eq!(warm_up.allocations(), 1);
+//!
+//!     // Once warmed up, the same work does not allocate anymore.
+//!     let sum: u32 = assert_no_alloc! {
+//!         let mut numbers = reusable.recycle::<u32>();
+//!         numbers.extend(0..100);
+//!         numbers.iter().sum()
+//!     };
+//!     assert_eq!(sum, 4950);
+//! }
+//! ```
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A [`GlobalAlloc`] that counts the allocations of every thread before
+/// forwarding them to another allocator.
+///
+/// Install it with `#[global_allocator]`; [`measure_allocs`] panics if it is
+/// not installed, rather than reporting that nothing was allocated.
+#[derive(Debug, Default)]
+pub struct TrackingAllocator<A = System> {
+    inner: A,
+}
+
+impl TrackingAllocator<System> {
+    /// Creates a tracking allocator wrapping the system allocator.
+    pub const fn system() -> Self {
+        Self { inner: System }
+    }
+}
+
+impl<A> TrackingAllocator<A> {
+    /// Creates a tracking allocator wrapping `inner`.
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static COUNTS: Cell<AllocStats> = const { Cell::new(AllocStats::ZERO) };
+}
+
+fn count(update: impl FnOnce(&mut AllocStats)) {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+    // The thread-local is gone while a thread is being torn down. Nothing is
+    // being measured on it by then.
+    let _ = COUNTS.try_with(|counts| {
+        let mut stats = counts.get();
+        update(&mut stats);
+        counts.set(stats);
+    });
+}
+
+// SAFETY: Every call is forwarded unchanged to `inner`, which upholds the
+// `GlobalAlloc` contract. Counting does not allocate.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(|stats| stats.record_allocation(layout.size()));
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(|stats| stats.record_allocation(layout.size()));
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(|stats| {
+            stats.reallocations += 1;
+            stats.bytes_allocated += new_size.saturating_sub(layout.size()) as u64;
+        });
+        self.inner.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(|stats| stats.deallocations += 1);
+        self.inner.dealloc(ptr, layout)
+    }
+}
+
+/// The allocator calls made while [`measure_allocs`] ran its closure.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    allocations: u64,
+    reallocations: u64,
+    deallocations: u64,
+    bytes_allocated: u64,
+}
+
+impl AllocStats {
+    const ZERO: Self = Self {
+        allocations: 0,
+        reallocations: 0,
+        deallocations: 0,
+        bytes_allocated: 0,
+    };
+
+    fn record_allocation(&mut self, size: usize) {
+        self.allocations += 1;
+        self.bytes_allocated += size as u64;
+    }
+
+    fn since(self, start: Self) -> Self {
+        Self {
+            allocations: self.allocations - start.allocations,
+            reallocations: self.reallocations - start.reallocations,
+            deallocations: self.deallocations - start.deallocations,
+            bytes_allocated: self.bytes_allocated - start.bytes_allocated,
+        }
+    }
+
+    /// The number of new allocations.
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    /// The number of allocations that were grown or shrunk.
+    pub fn reallocations(&self) -> u64 {
+        self.reallocations
+    }
+
+    /// The number of allocations that were freed.
+    pub fn deallocations(&self) -> u64 {
+        self.deallocations
+    }
+
+    /// The number of bytes requested by new allocations, plus the growth of
+    /// reallocated ones.
+    pub fn bytes_allocated(&self) -> u64 {
+        self.bytes_allocated
+    }
+
+    /// Returns `true` if nothing was allocated or reallocated.
+    ///
+    /// Deallocations are not counted, since dropping memory allocated before
+    /// the measurement does not make a hot path slower to warm up.
+    pub fn is_allocation_free(&self) -> bool {
+        self.allocations == 0 && self.reallocations == 0
+    }
+}
+
+impl fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocation(s) and {} reallocation(s) requesting {} bytes, {} deallocation(s)",
+            self.allocations, self.reallocations, self.bytes_allocated, self.deallocations
+        )
+    }
+}
+
+/// Runs `f` and returns the allocator calls it made on the current thread.
+///
+/// Measurements can be nested.
+///
+/// # Panics
+///
+/// Panics if [`TrackingAllocator`] is not the global allocator.
+pub fn measure_allocs<F: FnOnce()>(f: F) -> AllocStats {
+    assert!(
+        INSTALLED.load(Ordering::Relaxed),
+        "measure_allocs requires TrackingAllocator to be installed with #[global_allocator]"
+    );
+    let start = COUNTS.with(Cell::get);
+    f();
+    COUNTS.with(Cell::get).since(start)
+}
+
+/// Runs a block, failing with the [`AllocStats`] it caused if it allocated or
+/// reallocated on the current thread, and evaluates to the block's value.
+///
+/// Requires [`TrackingAllocator`](crate::alloc_tracking::TrackingAllocator)
+/// to be the global allocator. See the
+/// [`alloc_tracking`](crate::alloc_tracking) module for an example.
+#[macro_export]
+macro_rules! assert_no_alloc {
+    ($($body:tt)*) => {{
+        let mut result = ::core::option::Option::None;
+        let stats = $crate::alloc_tracking::measure_allocs(|| {
+            result = ::core::option::Option::Some({ $($body)* });
+        });
+        ::core::assert!(
+            stats.is_allocation_free(),
+            "expected no allocations, but the block made {}",
+            stats
+        );
+        match result {
+            ::core::option::Option::Some(value) => value,
+            ::core::option::Option::None => ::core::unreachable!(),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReusableString, ReusableVec};
+
+    #[global_allocator]
+    static GLOBAL: TrackingAllocator = TrackingAllocator::system();
+
+    #[test]
+    fn allocations_are_counted_per_closure() {
+        let stats = measure_allocs(|| {
+            let mut vec = Vec::<u64>::with_capacity(4);
+            vec.extend(0..64);
+        });
+        assert_eq!(stats.allocations(), 1);
+        assert!(stats.reallocations() >= 1);
+        assert_eq!(stats.deallocations(), 1);
+        assert!(stats.bytes_allocated() >= 64 * 8);
+        assert!(!stats.is_allocation_free());
+    }
+
+    #[test]
+    fn warmed_up_recyclers_do_not_allocate() {
+        let mut line = ReusableString::default();
+        line.recycle().push_str("warming up the buffer");
+
+        let len = assert_no_alloc! {
+            let mut line = line.recycle();
+            line.push_str("hello");
+            line.len()
+        };
+        assert_eq!(len, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected no allocations, but the block made 1 allocation(s)")]
+    fn allocating_blocks_fail_the_assertion() {
+        let mut reusable = ReusableVec::<u8>::default();
+        assert_no_alloc! {
+            reusable.recycle::<u8>().push(1);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Reusable containers backed by a custom allocator.
+//!
+//! This module is only available with the `allocator_api` feature, which
+//! requires a nightly compiler, since it builds on the unstable
+//! [`Allocator`] trait.
+use crate::ReuseCastInto;
+use std::{
+    alloc::{Allocator, Global},
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr,
+};
+
+/// A [`ReusableVec`](crate::ReusableVec) whose allocation comes from the
+/// allocator `A`.
+///
+/// This lets a recycled buffer live in a bump arena, a jemalloc arena, or any
+/// other [`Allocator`]. [`recycle`](ReusableAllocVec::recycle) works exactly
+/// like `ReusableVec::recycle`, and the guard dereferences to a `Vec<T2, A>`
+/// that still uses the same allocator, so anything pushed through it grows
+/// the allocation in `A`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(allocator_api)]
+/// use std::alloc::System;
+/// use triple_r::allocator_api::ReusableAllocVec;
+///
+/// let mut reusable = ReusableAllocVec::<&'static str, System>::new_in(System);
+///
+/// for line in [String::from("a b c"), String::from("d e")] {
+///     let mut words = reusable.recycle::<&str>();
+///     words.extend(line.split(' '));
+///     assert!(words.capacity() >= 3);
+/// }
+/// ```
+pub struct ReusableAllocVec<T: 'static, A: Allocator = Global> {
+    inner: UnsafeCell<Vec<T, A>>,
+}
+
+// The `ReusableAllocVec` is safe to send across threads if `T` and the
+// allocator are `Send`.
+unsafe impl<T: Send, A: Allocator + Send> Send for ReusableAllocVec<T, A> {}
+
+// The `ReusableAllocVec` is safe to share across threads if `T` is `Send` and
+// the allocator is `Sync`, because `recycle` requires `&mut self` and
+// `allocator` only hands out a shared reference.
+unsafe impl<T: Send, A: Allocator + Sync> Sync for ReusableAllocVec<T, A> {}
+
+impl<T: 'static, A: Allocator> fmt::Debug for ReusableAllocVec<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableAllocVec")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<T: 'static, A: Allocator + Default> Default for ReusableAllocVec<T, A> {
+    /// Creates a new, empty `ReusableAllocVec` with a default allocator.
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T: 'static, A: Allocator> ReusableAllocVec<T, A> {
+    /// Creates a new, empty `ReusableAllocVec` that allocates from `alloc`.
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            inner: UnsafeCell::new(Vec::new_in(alloc)),
+        }
+    }
+
+    /// Creates a `ReusableAllocVec` with room for `capacity` elements,
+    /// allocated from `alloc`.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self {
+            inner: UnsafeCell::new(Vec::with_capacity_in(capacity, alloc)),
+        }
+    }
+
+    /// Returns a reference to the allocator backing the vector.
+    pub fn allocator(&self) -> &A {
+        // SAFETY: No guard can be alive while `self` is shared, so nothing
+        // is mutating the `Vec`.
+        unsafe { (*self.inner.get()).allocator() }
+    }
+
+    /// The capacity of the retained allocation.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: See `allocator`.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `Vec` holds on to in
+    /// its allocator.
+    pub fn memory_footprint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+
+    /// Reuses the `Vec`'s allocation, returning a guard for temporary access.
+    ///
+    /// As with [`ReusableVec::recycle`](crate::ReusableVec::recycle), the
+    /// element type can be cast to `T2` through [`ReuseCastInto`]. The
+    /// allocator stays the same.
+    pub fn recycle<'parent, T2>(&'parent mut self) -> ReusableAllocVecGuard<'parent, T, T2, A>
+    where
+        T: ReuseCastInto<T2>,
+    {
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the `Vec` is
+        // always empty between guards, so no `T` value is ever read as a
+        // `T2`. The allocator field is untouched by the cast.
+        let inner_ptr = self.inner.get() as *mut Vec<T2, A>;
+
+        ReusableAllocVecGuard {
+            inner: inner_ptr,
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the `Vec` without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    pub fn recycle_same(&mut self) -> ReusableAllocVecGuard<'_, T, T, A> {
+        self.forget_leaked();
+        ReusableAllocVecGuard {
+            inner: self.inner.get(),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Recycles the `Vec` for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableAllocVecGuard<'_, T, T2, A>) -> R,
+    ) -> R
+    where
+        T: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `Vec` and its allocator. The `Vec` is always empty.
+    pub fn into_inner(self) -> Vec<T, A> {
+        let mut this = ManuallyDrop::new(self);
+        this.forget_leaked();
+        // SAFETY: `this` is never dropped, so the `Vec` is only moved out
+        // once. It is the only field.
+        unsafe { ptr::read(this.inner.get()) }
+    }
+
+    /// Leaks anything a leaked guard left in the `Vec`.
+    ///
+    /// Guards leave the `Vec` empty, so any elements in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        // SAFETY: Leaking elements is always sound, and the `Vec` keeps its
+        // allocation.
+        unsafe { self.inner.get_mut().set_len(0) };
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to the `Vec` of a
+/// [`ReusableAllocVec`].
+///
+/// When the guard is dropped, it clears the `Vec`, keeping the allocation in
+/// `A`.
+pub struct ReusableAllocVecGuard<'parent, T1, T2, A>
+where
+    T1: 'static,
+    A: Allocator,
+{
+    inner: *mut Vec<T2, A>,
+    _parent: PhantomData<&'parent mut ReusableAllocVec<T1, A>>,
+}
+
+impl<'parent, T1, T2, A> Deref for ReusableAllocVecGuard<'parent, T1, T2, A>
+where
+    T1: 'static,
+    A: Allocator,
+{
+    type Target = Vec<T2, A>;
+
+    /// Provides immutable access to the underlying `Vec`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, T1, T2, A> ReusableAllocVecGuard<'parent, T1, T2, A>
+where
+    T1: 'static,
+    A: Allocator,
+{
+    /// The estimated number of heap bytes the `Vec` holds on to in its
+    /// allocator, counting its unused capacity.
+    pub fn memory_footprint(&self) -> usize {
+        self.capacity() * mem::size_of::<T2>()
+    }
+}
+
+impl<'parent, T1, T2, A> DerefMut for ReusableAllocVecGuard<'parent, T1, T2, A>
+where
+    T1: 'static,
+    A: Allocator,
+{
+    /// Provides mutable access to the underlying `Vec`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<T: 'static, A: Allocator> Drop for ReusableAllocVec<T, A> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the `Vec`.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, T1, T2, A> Drop for ReusableAllocVecGuard<'parent, T1, T2, A>
+where
+    T1: 'static,
+    A: Allocator,
+{
+    /// Clears the `Vec` when the guard is dropped.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let vec = unsafe { &mut *self.inner };
+        vec.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        alloc::{AllocError, Layout},
+        ptr::NonNull,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[derive(Default)]
+    struct Counting {
+        allocations: AtomicUsize,
+    }
+
+    unsafe impl Allocator for &Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn capacity_is_retained_between_uses() {
+        let mut reusable = ReusableAllocVec::<u32>::default();
+        {
+            let mut guard = reusable.recycle::<u32>();
+            guard.extend(0..16);
+        }
+        assert!(reusable.capacity() >= 16);
+        assert!(reusable.recycle::<u32>().is_empty());
+    }
+
+    #[test]
+    fn guards_allocate_from_the_parent_allocator() {
+        let counting = Counting::default();
+        let mut reusable = ReusableAllocVec::<&'static str, _>::with_capacity_in(8, &counting);
+        assert_eq!(counting.allocations.load(Ordering::Relaxed), 1);
+
+        for round in 0..3 {
+            let owned = format!("round {}", round);
+            let mut guard = reusable.recycle::<&str>();
+            guard.extend(std::iter::repeat(owned.as_str()).take(8));
+            assert!(std::ptr::eq(*guard.allocator(), &counting));
+        }
+        assert_eq!(counting.allocations.load(Ordering::Relaxed), 1);
+        assert!(std::ptr::eq(*reusable.allocator(), &counting));
+    }
+}
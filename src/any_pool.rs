@@ -0,0 +1,165 @@
+use crate::{Recyclable, ReusablePool, ReusablePoolGuard};
+#[cfg(feature = "parking_lot")]
+use parking_lot::{Mutex, MutexGuard};
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{Mutex, MutexGuard};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+};
+
+type Pools = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+/// A pool of recycled containers of any type, looked up by type.
+///
+/// A [`ReusablePool`] holds containers of one type, fixed by whoever creates
+/// it. Framework code that reuses buffers on behalf of its callers can't name
+/// their types up front. An `AnyPool` keeps one `ReusablePool` per container
+/// type, keyed by [`TypeId`], and creates each the first time its type is
+/// asked for. [`get::<Vec<MyRow>>()`](AnyPool::get) returns the same owned
+/// [`ReusablePoolGuard`] a `ReusablePool` would.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use triple_r::AnyPool;
+///
+/// struct Row {
+///     id: u64,
+/// }
+///
+/// let pool = AnyPool::new();
+///
+/// let mut rows = pool.get::<Vec<Row>>();
+/// rows.push(Row { id: 7 });
+/// let mut index = pool.get::<HashMap<u64, usize>>();
+/// index.insert(rows[0].id, 0);
+/// drop((rows, index));
+///
+/// // Each type has its own containers, back, empty, with their allocations.
+/// assert_eq!(pool.available::<Vec<Row>>(), 1);
+/// assert!(pool.get::<Vec<Row>>().capacity() >= 1);
+/// assert!(pool.get::<HashMap<u64, usize>>().capacity() >= 1);
+/// ```
+pub struct AnyPool {
+    pools: Mutex<Pools>,
+    max_idle: usize,
+}
+
+impl Default for AnyPool {
+    /// Creates a new, empty `AnyPool`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for AnyPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyPool")
+            .field("types", &self.lock().len())
+            .field("max_idle", &self.max_idle)
+            .finish()
+    }
+}
+
+impl AnyPool {
+    /// Creates a new, empty `AnyPool`. Containers are created on demand with
+    /// [`Default`].
+    pub fn new() -> Self {
+        Self::with_max_idle(usize::MAX)
+    }
+
+    /// Creates a pool that keeps at most `max_idle` containers of each type
+    /// waiting.
+    pub fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            pools: Mutex::new(HashMap::new()),
+            max_idle,
+        }
+    }
+
+    /// Takes a container of type `T` out of the pool, returning a guard that
+    /// puts it back when dropped.
+    ///
+    /// If the pool has no `T` available, a new one is created with
+    /// [`Default`].
+    pub fn get<T>(&self) -> ReusablePoolGuard<T>
+    where
+        T: Recyclable + Default + Send + 'static,
+    {
+        self.pool::<T>().get()
+    }
+
+    /// Clears `object` and adds it to the pool, unless the pool already
+    /// keeps `max_idle` containers of type `T`.
+    pub fn put<T>(&self, object: T)
+    where
+        T: Recyclable + Send + 'static,
+    {
+        self.pool::<T>().put(object);
+    }
+
+    /// The number of containers of type `T` currently waiting in the pool.
+    pub fn available<T: Send + 'static>(&self) -> usize {
+        self.lock()
+            .get(&TypeId::of::<T>())
+            .map_or(0, |pool| downcast::<T>(Arc::clone(pool)).available())
+    }
+
+    /// The pool of containers of type `T`, created if it doesn't exist yet.
+    ///
+    /// The returned pool is shared with this one, so it can be handed to code
+    /// that only deals with `T`s.
+    pub fn pool<T: Send + 'static>(&self) -> Arc<ReusablePool<T>> {
+        let max_idle = self.max_idle;
+        let pool = self
+            .lock()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(ReusablePool::<T>::with_max_idle(max_idle)))
+            .clone();
+        downcast(pool)
+    }
+
+    #[cfg(not(feature = "parking_lot"))]
+    fn lock(&self) -> MutexGuard<'_, Pools> {
+        // Pools are only ever inserted, so the map is always consistent, even
+        // if a thread panicked while holding the lock.
+        self.pools
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[cfg(feature = "parking_lot")]
+    fn lock(&self) -> MutexGuard<'_, Pools> {
+        self.pools.lock()
+    }
+}
+
+fn downcast<T: Send + 'static>(pool: Arc<dyn Any + Send + Sync>) -> Arc<ReusablePool<T>> {
+    pool.downcast()
+        .unwrap_or_else(|_| unreachable!("pools are keyed by the TypeId of their containers"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn each_type_gets_its_own_containers() {
+        let pool = AnyPool::with_max_idle(1);
+        thread::scope(|s| {
+            s.spawn(|| pool.get::<Vec<u8>>().extend_from_slice(b"bytes"));
+            s.spawn(|| pool.get::<String>().push_str("text"));
+        });
+        pool.put(Vec::<u8>::with_capacity(1));
+
+        assert_eq!(pool.available::<Vec<u8>>(), 1);
+        assert_eq!(pool.available::<String>(), 1);
+        assert_eq!(pool.available::<Vec<u16>>(), 0);
+        assert!(pool.pool::<String>().get().capacity() >= 4);
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    alloc::{self, Layout},
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A single retained allocation, recycled as a `Vec` of a different element
+/// type every cycle.
+///
+/// A [`ReusableVec`](crate::ReusableVec) can only change the lifetimes in
+/// its element type, since its allocation has to be freed with the layout of
+/// the element it was created for. A pipeline that needs a `Vec<u32>` in one
+/// stage and a `Vec<MyStruct>` in the next would keep one allocation per
+/// stage. `ReusableAnyVec` owns a raw allocation instead, and
+/// [`recycle::<T>()`](ReusableAnyVec::recycle) hands it out as a
+/// [`ReusableAnyVecGuard`] that behaves like a `Vec<T>` of any element type.
+///
+/// The allocation keeps its own [`Layout`], which is what it is reallocated
+/// and freed with. When a guard outgrows it, the allocation grows in place
+/// with `realloc` if it is aligned enough for `T`, and is replaced by one
+/// aligned for both `T` and every earlier element type otherwise, so a
+/// buffer shared between differently shaped stages settles on one
+/// allocation quickly.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableAnyVec;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Span {
+///     start: u64,
+///     end: u64,
+/// }
+///
+/// let mut scratch = ReusableAnyVec::default();
+///
+/// {
+///     let mut spans = scratch.recycle::<Span>();
+///     spans.extend((0..64).map(|i| Span { start: i, end: i + 1 }));
+///     assert_eq!(spans[1], Span { start: 1, end: 2 });
+/// } // The spans are dropped here, keeping the allocation.
+///
+/// // The same allocation now holds four times as many `u32`s.
+/// let mut ids = scratch.recycle::<u32>();
+/// assert_eq!(ids.capacity(), 256);
+/// ids.extend(0..256);
+/// drop(ids);
+/// assert_eq!(scratch.capacity(), 64 * 16);
+/// ```
+pub struct ReusableAnyVec {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// SAFETY: The allocation holds no values outside of a guard, which borrows
+// the buffer mutably and is only `Send` when its elements are.
+unsafe impl Send for ReusableAnyVec {}
+
+// SAFETY: Shared references only expose the capacity and alignment.
+unsafe impl Sync for ReusableAnyVec {}
+
+impl Default for ReusableAnyVec {
+    /// Creates a buffer without an allocation.
+    fn default() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            layout: Layout::new::<()>(),
+        }
+    }
+}
+
+impl fmt::Debug for ReusableAnyVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableAnyVec")
+            .field("capacity", &self.capacity())
+            .field("align", &self.align())
+            .finish()
+    }
+}
+
+impl ReusableAnyVec {
+    /// Creates a buffer with room for `capacity` elements of type `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation would be larger than `isize::MAX` bytes.
+    pub fn with_capacity_for<T>(capacity: usize) -> Self {
+        let mut buffer = Self::default();
+        buffer.recycle::<T>().reserve(capacity);
+        buffer
+    }
+
+    /// The size of the retained allocation, in bytes.
+    pub fn capacity(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// The alignment of the retained allocation.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// How many elements of type `T` the retained allocation holds.
+    ///
+    /// This is zero if the allocation is not aligned enough for `T`, and
+    /// `usize::MAX` if `T` is zero-sized.
+    pub fn capacity_for<T>(&self) -> usize {
+        if mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else if mem::align_of::<T>() > self.layout.align() {
+            0
+        } else {
+            self.layout.size() / mem::size_of::<T>()
+        }
+    }
+
+    /// The number of heap bytes the buffer holds on to, which is its
+    /// capacity.
+    pub fn memory_footprint(&self) -> usize {
+        self.capacity()
+    }
+
+    /// Releases the retained allocation, without taking a guard.
+    pub fn shrink_to_fit(&mut self) {
+        self.release();
+        *self = Self::default();
+    }
+
+    /// Returns an empty `Vec`-like guard over elements of type `T`, backed
+    /// by the retained allocation.
+    pub fn recycle<T>(&mut self) -> ReusableAnyVecGuard<'_, T> {
+        ReusableAnyVecGuard {
+            parent: self,
+            len: 0,
+            _elements: PhantomData,
+        }
+    }
+
+    fn release(&mut self) {
+        if self.layout.size() > 0 {
+            // SAFETY: `ptr` was allocated with `layout`.
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+        }
+    }
+}
+
+impl Drop for ReusableAnyVec {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// A `Vec<T>` backed by the allocation of a [`ReusableAnyVec`].
+///
+/// The guard dereferences to `[T]` and can be grown with
+/// [`push`](ReusableAnyVecGuard::push), [`Extend`], and
+/// [`reserve`](ReusableAnyVecGuard::reserve). When it is dropped, its
+/// elements are dropped and the allocation goes back to the parent.
+pub struct ReusableAnyVecGuard<'parent, T> {
+    parent: &'parent mut ReusableAnyVec,
+    len: usize,
+    _elements: PhantomData<T>,
+}
+
+impl<'parent, T> ReusableAnyVecGuard<'parent, T> {
+    /// How many elements the guard can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.parent.capacity_for::<T>()
+    }
+
+    /// Appends `value` to the end of the guard.
+    pub fn push(&mut self, value: T) {
+        if self.len == self.capacity() {
+            self.reserve(1);
+        }
+        // SAFETY: The allocation holds at least `len + 1` elements, and the
+        // one at `len` is unused.
+        unsafe { self.as_mut_ptr().add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    /// Removes the last element and returns it, or `None` if the guard is
+    /// empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: The element at `len` was initialized, and is no longer
+        // counted, so it is never read or dropped again.
+        Some(unsafe { self.as_mut_ptr().add(self.len).read() })
+    }
+
+    /// Drops all elements, keeping the allocation.
+    pub fn clear(&mut self) {
+        let len = self.len;
+        // The elements stop being counted first, so a panicking `Drop` can't
+        // lead to them being dropped again.
+        self.len = 0;
+        // SAFETY: The first `len` elements were initialized, and are no
+        // longer counted.
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), len)) };
+    }
+
+    /// Grows the allocation to hold at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation would be larger than `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let capacity = self.capacity();
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed <= capacity {
+            return;
+        }
+        // Zero-sized elements never get here, since their capacity is
+        // `usize::MAX`.
+        let new_capacity = needed.max(capacity.saturating_mul(2)).max(4);
+        let old = self.parent.layout;
+        let layout = new_capacity
+            .checked_mul(mem::size_of::<T>())
+            .and_then(|size| {
+                Layout::from_size_align(size, old.align().max(mem::align_of::<T>())).ok()
+            })
+            .expect("capacity overflow");
+
+        let ptr = if old.size() > 0 && old.align() == layout.align() {
+            // SAFETY: `ptr` was allocated with `old`, and `layout` checked
+            // that the new, non-zero size fits in `isize` once rounded up to
+            // the same alignment. The elements are moved along.
+            unsafe { alloc::realloc(self.parent.ptr.as_ptr(), old, layout.size()) }
+        } else {
+            // SAFETY: `layout` has a non-zero size, since `T` isn't
+            // zero-sized and `new_capacity` is at least 4.
+            let ptr = unsafe { alloc::alloc(layout) };
+            if !ptr.is_null() {
+                // SAFETY: The first `len` elements are initialized, and the
+                // new allocation is larger and doesn't overlap the old one.
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        self.as_mut_ptr() as *const u8,
+                        ptr,
+                        self.len * mem::size_of::<T>(),
+                    );
+                }
+                self.parent.release();
+            }
+            ptr
+        };
+        self.parent.ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        self.parent.layout = layout;
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        if mem::size_of::<T>() == 0 || self.capacity() == 0 {
+            NonNull::dangling().as_ptr()
+        } else {
+            self.parent.ptr.as_ptr().cast()
+        }
+    }
+
+    fn as_ptr(&self) -> *const T {
+        if mem::size_of::<T>() == 0 || self.capacity() == 0 {
+            NonNull::dangling().as_ptr()
+        } else {
+            self.parent.ptr.as_ptr().cast()
+        }
+    }
+}
+
+impl<'parent, T> Deref for ReusableAnyVecGuard<'parent, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The first `len` elements are initialized, and the pointer
+        // is aligned for `T`.
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+}
+
+impl<'parent, T> DerefMut for ReusableAnyVecGuard<'parent, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: See `deref`, and the guard has exclusive access to the
+        // allocation.
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+}
+
+impl<'parent, T> Extend<T> for ReusableAnyVecGuard<'parent, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<'parent, T: fmt::Debug> fmt::Debug for ReusableAnyVecGuard<'parent, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'parent, T> Drop for ReusableAnyVecGuard<'parent, T> {
+    /// Drops the elements, keeping the allocation.
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn elements_of_any_type_share_the_allocation() {
+        let mut buffer = ReusableAnyVec::with_capacity_for::<u16>(8);
+        let ptr = buffer.recycle::<u16>().as_ptr() as usize;
+
+        let mut pairs = buffer.recycle::<(u8, u8)>();
+        pairs.extend((0..8).map(|i| (i, i)));
+        assert_eq!(pairs.as_ptr() as usize, ptr);
+        drop(pairs);
+
+        // A stricter alignment moves the elements to a new allocation.
+        let mut wide = buffer.recycle::<u64>();
+        wide.extend(0..3);
+        wide.extend(3..8);
+        assert_eq!(wide.pop(), Some(7));
+        assert_eq!(&wide[..], [0, 1, 2, 3, 4, 5, 6]);
+        drop(wide);
+        assert_eq!(buffer.align(), mem::align_of::<u64>());
+        assert_eq!(buffer.capacity_for::<u16>(), buffer.capacity() / 2);
+    }
+
+    #[test]
+    fn elements_are_dropped_with_the_guard() {
+        let shared = Rc::new(());
+        let mut buffer = ReusableAnyVec::default();
+        buffer.recycle().extend((0..10).map(|_| Rc::clone(&shared)));
+        assert_eq!(Rc::strong_count(&shared), 1);
+
+        let mut units = buffer.recycle::<()>();
+        units.extend((0..1000).map(|_| ()));
+        assert_eq!(units.len(), 1000);
+    }
+}
@@ -0,0 +1,572 @@
+use crate::{poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto};
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+/// A generational arena that allows for reusing its allocation between
+/// cycles.
+///
+/// Games and ECS-style systems often need per-frame entities: values that are
+/// created during a frame, referred to by handle, and thrown away at its end.
+/// A `ReusableGenerationalArena` keeps the storage for those values between
+/// frames. Each [`recycle`](ReusableGenerationalArena::recycle) cycle returns a
+/// [`ReusableGenerationalArenaGuard`] that hands out an [`ArenaId`] for every
+/// inserted value.
+///
+/// An `ArenaId` is only valid for the cycle it was created in and until its
+/// value is removed. Looking up a stale `ArenaId`, whether it is from an
+/// earlier cycle or refers to a slot that has since been reused, returns
+/// `None` instead of someone else's value.
+///
+/// When the guard is dropped, every value is dropped and every `ArenaId`
+/// handed out during the cycle is invalidated, but the storage keeps its
+/// capacity.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableGenerationalArena;
+///
+/// let mut entities = ReusableGenerationalArena::<&'static str>::default();
+///
+/// let stale = {
+///     let mut frame = entities.recycle();
+///     let player = frame.insert("player");
+///     let bullet = frame.insert("bullet");
+///     assert_eq!(frame.get(player), Some(&"player"));
+///
+///     frame.remove(bullet);
+///     assert_eq!(frame.get(bullet), None);
+///     player
+/// }; // All entities are dropped and all ids invalidated here.
+///
+/// let mut frame = entities.recycle();
+/// let enemy = frame.insert("enemy");
+/// assert_eq!(frame.get(stale), None);
+/// assert_eq!(frame[enemy], "enemy");
+/// ```
+pub struct ReusableGenerationalArena<T: 'static> {
+    slots: UnsafeCell<Vec<Slot<T>>>,
+    epoch: u64,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableGenerationalArena` is safe to send across threads if `T` is
+// `Send`.
+unsafe impl<T: Send> Send for ReusableGenerationalArena<T> {}
+
+// The `ReusableGenerationalArena` is safe to share across threads if `T` is
+// `Send`. The `recycle` method requires `&mut self`, which prevents concurrent
+// access without external synchronization.
+unsafe impl<T: Send> Sync for ReusableGenerationalArena<T> {}
+
+impl<T: 'static> fmt::Debug for ReusableGenerationalArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        let capacity = unsafe { (*self.slots.get()).capacity() };
+        f.debug_struct("ReusableGenerationalArena")
+            .field("capacity", &capacity)
+            .finish()
+    }
+}
+
+impl<T: 'static> Default for ReusableGenerationalArena<T> {
+    /// Creates a new, empty `ReusableGenerationalArena` with no allocation.
+    fn default() -> Self {
+        Self {
+            slots: UnsafeCell::new(Vec::new()),
+            epoch: 0,
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Slot<T> {
+    Occupied {
+        generation: u32,
+        value: T,
+    },
+    Vacant {
+        generation: u32,
+        next_free: Option<usize>,
+    },
+}
+
+/// A handle to a value in a [`ReusableGenerationalArenaGuard`].
+///
+/// Ids are cheap to copy and compare. They only identify a value for as long
+/// as it is in the arena and the cycle it was inserted in lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArenaId {
+    index: usize,
+    generation: u32,
+    epoch: u64,
+}
+
+impl ArenaId {
+    /// The position of the value in the arena's storage.
+    ///
+    /// Indexes are reused once a value is removed, so they are only unique
+    /// among the values that are alive at the same time.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to a
+/// [`ReusableGenerationalArena`] for one cycle.
+///
+/// When the guard is dropped, all values are dropped and every [`ArenaId`]
+/// it handed out becomes stale. The storage keeps its allocation for the next
+/// cycle.
+pub struct ReusableGenerationalArenaGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    slots: *mut Vec<Slot<T2>>,
+    epoch: &'parent mut u64,
+    free_head: Option<usize>,
+    len: usize,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableGenerationalArena<T1>>,
+}
+
+impl<T1> ReusableGenerationalArena<T1>
+where
+    T1: 'static,
+{
+    /// Reuses the arena's allocation, returning a guard for one cycle.
+    ///
+    /// Like [`ReusableVec::recycle`](crate::ReusableVec::recycle), this allows
+    /// the value type to be "cast" to a new type `T2`, as long as `T1`
+    /// implements [`ReuseCastInto<T2>`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the arena and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, T2>(
+        &'parent mut self,
+    ) -> ReusableGenerationalArenaGuard<'parent, T1, T2>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the storage is
+        // always empty between cycles, so no `T1` is ever seen as a `T2`.
+        let slots = self.slots.get() as *mut Vec<Slot<T2>>;
+
+        ReusableGenerationalArenaGuard {
+            slots,
+            epoch: &mut self.epoch,
+            free_head: None,
+            len: 0,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the arena without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the arena and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableGenerationalArenaGuard<'_, T1, T1> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableGenerationalArenaGuard {
+            slots: self.slots.get(),
+            epoch: &mut self.epoch,
+            free_head: None,
+            len: 0,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the arena and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<T2>(
+        &mut self,
+    ) -> Result<ReusableGenerationalArenaGuard<'_, T1, T2>, PoisonError>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the arena was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the arena as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the arena, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.forget_leaked();
+            *self.slots.get_mut() = Vec::new();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the arena for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableGenerationalArenaGuard<'_, T1, T2>) -> R,
+    ) -> R
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Leaks the values of a guard that was never dropped, and invalidates
+    /// the ids it handed out.
+    ///
+    /// Guards leave the storage empty, so any slots in it come from a leaked
+    /// guard. Their values may be cast ones that borrow data that is gone,
+    /// so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let slots = self.slots.get_mut();
+        if !slots.is_empty() {
+            // SAFETY: Leaking the slots is always sound, and the `Vec` keeps
+            // its allocation.
+            unsafe { slots.set_len(0) };
+            self.epoch = self.epoch.wrapping_add(1);
+        }
+    }
+}
+
+impl<T: 'static> Drop for ReusableGenerationalArena<T> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the arena.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, T1, T2> ReusableGenerationalArenaGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn slots(&self) -> &Vec<Slot<T2>> {
+        // SAFETY: `self.slots` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.slots }
+    }
+
+    fn slots_mut(&mut self) -> &mut Vec<Slot<T2>> {
+        // SAFETY: The same guarantees as `slots` apply. Mutable access is
+        // safe because the `&mut self` borrow on the parent prevents any
+        // other access.
+        unsafe { &mut *self.slots }
+    }
+
+    /// Inserts `value`, returning the id to look it up with.
+    pub fn insert(&mut self, value: T2) -> ArenaId {
+        let epoch = *self.epoch;
+        self.len += 1;
+        match self.free_head {
+            Some(index) => {
+                let slot = &mut self.slots_mut()[index];
+                let (generation, next_free) = match *slot {
+                    Slot::Vacant {
+                        generation,
+                        next_free,
+                    } => (generation, next_free),
+                    Slot::Occupied { .. } => unreachable!("free list points to an occupied slot"),
+                };
+                *slot = Slot::Occupied { generation, value };
+                self.free_head = next_free;
+                ArenaId {
+                    index,
+                    generation,
+                    epoch,
+                }
+            }
+            None => {
+                let slots = self.slots_mut();
+                let index = slots.len();
+                slots.push(Slot::Occupied {
+                    generation: 0,
+                    value,
+                });
+                ArenaId {
+                    index,
+                    generation: 0,
+                    epoch,
+                }
+            }
+        }
+    }
+
+    /// Returns a reference to the value behind `id`, or `None` if `id` is
+    /// stale.
+    pub fn get(&self, id: ArenaId) -> Option<&T2> {
+        if id.epoch != *self.epoch {
+            return None;
+        }
+        match self.slots().get(id.index) {
+            Some(Slot::Occupied { generation, value }) if *generation == id.generation => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the value behind `id`, or `None` if
+    /// `id` is stale.
+    pub fn get_mut(&mut self, id: ArenaId) -> Option<&mut T2> {
+        if id.epoch != *self.epoch {
+            return None;
+        }
+        match self.slots_mut().get_mut(id.index) {
+            Some(Slot::Occupied { generation, value }) if *generation == id.generation => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `id` refers to a value in the arena.
+    pub fn contains(&self, id: ArenaId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Removes and returns the value behind `id`, or returns `None` if `id`
+    /// is stale.
+    ///
+    /// The slot is reused by later insertions, but `id` and its copies stay
+    /// stale.
+    pub fn remove(&mut self, id: ArenaId) -> Option<T2> {
+        if !self.contains(id) {
+            return None;
+        }
+        let next_free = self.free_head;
+        let slot = &mut self.slots_mut()[id.index];
+        let vacant = Slot::Vacant {
+            generation: id.generation.wrapping_add(1),
+            next_free,
+        };
+        let value = match std::mem::replace(slot, vacant) {
+            Slot::Occupied { value, .. } => value,
+            Slot::Vacant { .. } => unreachable!("`contains` checked the slot"),
+        };
+        self.free_head = Some(id.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// The number of values in the arena.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the arena holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of slots the arena can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.slots().capacity()
+    }
+
+    /// The estimated number of heap bytes the arena's storage holds on to,
+    /// counting its unused slots.
+    pub fn memory_footprint(&self) -> usize {
+        self.slots().memory_footprint()
+    }
+
+    /// Iterates over the values in the arena, in slot order, along with
+    /// their ids.
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaId, &T2)> + '_ {
+        let epoch = *self.epoch;
+        self.slots()
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, slot)| match slot {
+                Slot::Occupied { generation, value } => Some((
+                    ArenaId {
+                        index,
+                        generation: *generation,
+                        epoch,
+                    },
+                    value,
+                )),
+                Slot::Vacant { .. } => None,
+            })
+    }
+}
+
+impl<'parent, T1, T2> Index<ArenaId> for ReusableGenerationalArenaGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    type Output = T2;
+
+    /// Returns a reference to the value behind `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is stale.
+    fn index(&self, id: ArenaId) -> &Self::Output {
+        self.get(id).expect("stale ArenaId")
+    }
+}
+
+impl<'parent, T1, T2> IndexMut<ArenaId> for ReusableGenerationalArenaGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Returns a mutable reference to the value behind `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is stale.
+    fn index_mut(&mut self, id: ArenaId) -> &mut Self::Output {
+        self.get_mut(id).expect("stale ArenaId")
+    }
+}
+
+impl<'parent, T1, T2> Drop for ReusableGenerationalArenaGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Drops all values and invalidates every id handed out during the cycle.
+    fn drop(&mut self) {
+        self.slots_mut().clear();
+        *self.epoch = self.epoch.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_invalidated_between_cycles() {
+        let mut arena = ReusableGenerationalArena::<u32>::default();
+        let id = {
+            let mut guard = arena.recycle::<u32>();
+            let id = guard.insert(7);
+            assert_eq!(guard.get(id), Some(&7));
+            id
+        };
+
+        let mut guard = arena.recycle::<u32>();
+        let reused = guard.insert(8);
+        assert_eq!(reused.index(), id.index());
+        assert_eq!(guard.get(id), None);
+        assert_eq!(guard[reused], 8);
+    }
+
+    #[test]
+    fn removed_slots_are_reused_with_a_new_generation() {
+        let mut arena = ReusableGenerationalArena::<String>::default();
+        let mut guard = arena.recycle::<String>();
+        let first = guard.insert("first".to_string());
+        let second = guard.insert("second".to_string());
+
+        assert_eq!(guard.remove(first).as_deref(), Some("first"));
+        assert_eq!(guard.remove(first), None);
+        let third = guard.insert("third".to_string());
+
+        assert_eq!(third.index(), first.index());
+        assert_ne!(third, first);
+        assert!(!guard.contains(first));
+        assert_eq!(guard.len(), 2);
+        guard[second].push('!');
+        let values: Vec<_> = guard.iter().map(|(_, value)| value.as_str()).collect();
+        assert_eq!(values, ["third", "second!"]);
+    }
+
+    #[test]
+    fn capacity_is_retained() {
+        let mut arena = ReusableGenerationalArena::<u64>::default();
+        {
+            let mut guard = arena.recycle::<u64>();
+            for i in 0..100 {
+                guard.insert(i);
+            }
+        }
+        let guard = arena.recycle::<u64>();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 100);
+    }
+
+    #[test]
+    fn reference_reuse_works() {
+        let mut arena = ReusableGenerationalArena::<&'static str>::default();
+        {
+            let name = String::from("short-lived");
+            let mut guard = arena.recycle::<&str>();
+            let id = guard.insert(name.as_str());
+            assert_eq!(guard[id], "short-lived");
+        }
+        assert!(arena.recycle::<&str>().is_empty());
+    }
+
+    #[test]
+    fn a_panic_poisons_the_arena() {
+        let mut arena = ReusableGenerationalArena::<u32>::default();
+        arena.set_poison_policy(PoisonPolicy::Error);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = arena.recycle_same();
+            guard.insert(1);
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(arena.is_poisoned());
+        assert!(arena.try_recycle::<u32>().is_err());
+
+        arena.set_poison_policy(PoisonPolicy::Reset);
+        assert_eq!(arena.recycle_same().capacity(), 0);
+        assert!(!arena.is_poisoned());
+    }
+}
@@ -0,0 +1,221 @@
+//! Recycled buffers for [Arrow](https://docs.rs/arrow) array builders.
+//!
+//! Building a record batch usually means creating a fresh builder per column,
+//! filling it, and calling `finish`. Every one of those builders allocates its
+//! value (and offset) buffers from scratch, and the finished arrays free them
+//! again once the batch has been written out.
+//!
+//! The types in this module keep those buffers in a [`ReusableVec`] between
+//! batches. A builder obtained from them starts out with the retained
+//! capacity, and handing the finished array back with `reclaim` returns its
+//! buffers for the next batch.
+//!
+//! Validity (null) bitmaps are still allocated by Arrow as usual; they are one
+//! bit per slot and rarely dominate a batch's memory.
+//!
+//! This module is only available with the `arrow` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use arrow_array::{types::Int64Type, Array};
+//! use triple_r::arrow::ReusablePrimitiveBuilder;
+//!
+//! let mut scratch = ReusablePrimitiveBuilder::<Int64Type>::default();
+//!
+//! for batch in 0..3 {
+//!     let mut builder = scratch.builder();
+//!     builder.extend((0..100).map(|i| Some(i * batch)));
+//!     let array = builder.finish();
+//!     assert_eq!(array.len(), 100);
+//!
+//!     // ... write the array out ...
+//!
+//!     // Hand the buffer back so the next batch can reuse it.
+//!     assert!(scratch.reclaim(array));
+//! }
+//! ```
+use crate::ReusableVec;
+use arrow_array::{
+    builder::{GenericByteBuilder, PrimitiveBuilder},
+    types::{ByteArrayType, Utf8Type},
+    ArrowPrimitiveType, GenericByteArray, PrimitiveArray,
+};
+use arrow_buffer::{Buffer, MutableBuffer};
+use std::mem;
+
+/// Retained value buffer for building [`PrimitiveArray`]s.
+///
+/// Each call to [`builder`](ReusablePrimitiveBuilder::builder) moves the
+/// retained allocation into a new [`PrimitiveBuilder`]. Once the finished
+/// array is no longer needed, [`reclaim`](ReusablePrimitiveBuilder::reclaim)
+/// moves the allocation back.
+///
+/// If an array is never reclaimed the next builder simply starts without any
+/// retained capacity, exactly like [`PrimitiveBuilder::new`] would.
+#[derive(Debug)]
+pub struct ReusablePrimitiveBuilder<T: ArrowPrimitiveType> {
+    values: ReusableVec<T::Native>,
+}
+
+impl<T: ArrowPrimitiveType> Default for ReusablePrimitiveBuilder<T> {
+    /// Creates a new `ReusablePrimitiveBuilder` with no retained allocation.
+    fn default() -> Self {
+        Self {
+            values: ReusableVec::default(),
+        }
+    }
+}
+
+impl<T: ArrowPrimitiveType> ReusablePrimitiveBuilder<T> {
+    /// Returns an empty [`PrimitiveBuilder`] backed by the retained value
+    /// buffer.
+    pub fn builder(&mut self) -> PrimitiveBuilder<T> {
+        let values = mem::take(self.values.get_mut());
+        PrimitiveBuilder::new_from_buffer(MutableBuffer::from(values), None)
+    }
+
+    /// Takes the value buffer of a finished array back for reuse.
+    ///
+    /// Returns `false` if the buffer could not be reclaimed because it is
+    /// still shared, for example when the array has been cloned or sliced.
+    /// The retained buffer is only replaced if the reclaimed one is larger.
+    pub fn reclaim(&mut self, array: PrimitiveArray<T>) -> bool {
+        let (_, values, _) = array.into_parts();
+        reclaim_into(values.into_inner(), self.values.get_mut())
+    }
+}
+
+/// Retained offset and value buffers for building [`GenericByteArray`]s, such
+/// as [`StringArray`](arrow_array::StringArray) or
+/// [`BinaryArray`](arrow_array::BinaryArray).
+///
+/// This works exactly like [`ReusablePrimitiveBuilder`], but retains both of
+/// the buffers a byte array is made of.
+#[derive(Debug)]
+pub struct ReusableByteBuilder<T: ByteArrayType> {
+    offsets: ReusableVec<T::Offset>,
+    values: ReusableVec<u8>,
+}
+
+/// A [`ReusableByteBuilder`] for [`StringArray`](arrow_array::StringArray)s.
+pub type ReusableStringBuilder = ReusableByteBuilder<Utf8Type>;
+
+impl<T: ByteArrayType> Default for ReusableByteBuilder<T> {
+    /// Creates a new `ReusableByteBuilder` with no retained allocations.
+    fn default() -> Self {
+        Self {
+            offsets: ReusableVec::default(),
+            values: ReusableVec::default(),
+        }
+    }
+}
+
+impl<T: ByteArrayType> ReusableByteBuilder<T> {
+    /// Returns an empty [`GenericByteBuilder`] backed by the retained offset
+    /// and value buffers.
+    pub fn builder(&mut self) -> GenericByteBuilder<T> {
+        let mut offsets = mem::take(self.offsets.get_mut());
+        offsets.push(T::Offset::default());
+        let values = mem::take(self.values.get_mut());
+
+        // SAFETY: A single zero offset over an empty value buffer describes a
+        // valid, empty byte array.
+        unsafe {
+            GenericByteBuilder::new_from_buffer(
+                MutableBuffer::from(offsets),
+                MutableBuffer::from(values),
+                None,
+            )
+        }
+    }
+
+    /// Takes the offset and value buffers of a finished array back for
+    /// reuse.
+    ///
+    /// Returns `false` if either buffer could not be reclaimed because it is
+    /// still shared, for example when the array has been cloned or sliced.
+    /// Each retained buffer is only replaced if the reclaimed one is larger.
+    pub fn reclaim(&mut self, array: GenericByteArray<T>) -> bool {
+        let (offsets, values, _) = array.into_parts();
+        let offsets = reclaim_into(offsets.into_inner().into_inner(), self.offsets.get_mut());
+        let values = reclaim_into(values, self.values.get_mut());
+        offsets && values
+    }
+}
+
+/// Moves the allocation behind `buffer` into `retained` if it is uniquely
+/// owned and larger than what is already retained.
+fn reclaim_into<T: arrow_buffer::ArrowNativeType>(buffer: Buffer, retained: &mut Vec<T>) -> bool {
+    match buffer.into_vec::<T>() {
+        Ok(mut vec) => {
+            if vec.capacity() > retained.capacity() {
+                vec.clear();
+                *retained = vec;
+            }
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{types::Int32Type, Array, StringArray};
+
+    #[test]
+    fn primitive_buffer_is_reused() {
+        let mut scratch = ReusablePrimitiveBuilder::<Int32Type>::default();
+        let mut builder = scratch.builder();
+        builder.extend((0..1000).map(Some));
+        let array = builder.finish();
+        let ptr = array.values().as_ptr();
+        assert!(scratch.reclaim(array));
+
+        let capacity = scratch.values.get_mut().capacity();
+        assert!(capacity >= 1000);
+
+        let mut builder = scratch.builder();
+        assert!(builder.capacity() >= 1000);
+        builder.append_value(7);
+        builder.append_null();
+        let array = builder.finish();
+        assert_eq!(array.values().as_ptr(), ptr);
+        assert_eq!(array.value(0), 7);
+        assert!(array.is_null(1));
+    }
+
+    #[test]
+    fn string_buffers_are_reused() {
+        let mut scratch = ReusableStringBuilder::default();
+        let mut builder = scratch.builder();
+        for i in 0..100 {
+            builder.append_value(format!("value-{}", i));
+        }
+        let array = builder.finish();
+        assert!(scratch.reclaim(array));
+        assert!(scratch.offsets.get_mut().capacity() >= 101);
+        assert!(scratch.values.get_mut().capacity() >= 100 * "value-".len());
+
+        let mut builder = scratch.builder();
+        builder.append_value("hello");
+        builder.append_null();
+        let array: StringArray = builder.finish();
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.value(0), "hello");
+        assert!(array.is_null(1));
+    }
+
+    #[test]
+    fn shared_buffers_are_not_reclaimed() {
+        let mut scratch = ReusablePrimitiveBuilder::<Int32Type>::default();
+        let mut builder = scratch.builder();
+        builder.extend((0..10).map(Some));
+        let array = builder.finish();
+        let clone = array.clone();
+        assert!(!scratch.reclaim(array));
+        assert_eq!(clone.len(), 10);
+        assert_eq!(scratch.values.get_mut().capacity(), 0);
+    }
+}
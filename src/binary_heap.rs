@@ -0,0 +1,442 @@
+use crate::{poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto};
+use std::{
+    cell::UnsafeCell,
+    collections::{binary_heap::Drain, BinaryHeap},
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A wrapper around [`BinaryHeap`] that allows for reusing its allocation.
+///
+/// The open sets of A* and Dijkstra searches are `BinaryHeap`s that are
+/// thrown away after every query. `ReusableBinaryHeap` keeps the heap's
+/// backing `Vec` between queries instead: [`recycle`](ReusableBinaryHeap::recycle)
+/// returns a [`ReusableBinaryHeapGuard`] with exclusive access to the heap,
+/// and when the guard is dropped the heap is cleared but keeps its capacity.
+/// The element type can be cast through [`ReuseCastInto`], so the heap can
+/// hold references that only live for one query.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Reverse;
+/// use triple_r::ReusableBinaryHeap;
+///
+/// // Edges of a small weighted graph, as `(to, cost)` pairs.
+/// let edges = [vec![(1, 4), (2, 1)], vec![(3, 1)], vec![(1, 2), (3, 5)], vec![]];
+/// let mut open_set = ReusableBinaryHeap::<Reverse<(u32, usize)>>::default();
+///
+/// let mut shortest = |from: usize, to: usize| {
+///     let mut heap = open_set.recycle::<Reverse<(u32, usize)>>();
+///     let mut best = vec![u32::MAX; edges.len()];
+///     best[from] = 0;
+///     heap.push(Reverse((0, from)));
+///     while let Some(Reverse((cost, node))) = heap.pop() {
+///         if node == to {
+///             return Some(cost);
+///         }
+///         for &(next, step) in &edges[node] {
+///             if cost + step < best[next] {
+///                 best[next] = cost + step;
+///                 heap.push(Reverse((cost + step, next)));
+///             }
+///         }
+///     }
+///     None
+/// };
+///
+/// assert_eq!(shortest(0, 3), Some(4));
+/// assert_eq!(shortest(3, 0), None);
+/// ```
+pub struct ReusableBinaryHeap<T: 'static> {
+    inner: UnsafeCell<BinaryHeap<T>>,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableBinaryHeap` is safe to send across threads if `T` is `Send`.
+unsafe impl<T: Send> Send for ReusableBinaryHeap<T> {}
+
+// The `ReusableBinaryHeap` is safe to share across threads if `T` is `Send`,
+// because `recycle` requires `&mut self`.
+unsafe impl<T: Send> Sync for ReusableBinaryHeap<T> {}
+
+impl<T: 'static> fmt::Debug for ReusableBinaryHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableBinaryHeap")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<T: 'static + Ord> Default for ReusableBinaryHeap<T> {
+    /// Creates a new, empty `ReusableBinaryHeap`.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(BinaryHeap::new()),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to a `BinaryHeap`
+/// retrieved from a [`ReusableBinaryHeap`].
+///
+/// When the guard is dropped, it clears the `BinaryHeap`.
+pub struct ReusableBinaryHeapGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    inner: *mut BinaryHeap<T2>,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableBinaryHeap<T1>>,
+}
+
+impl<'parent, T1, T2> Deref for ReusableBinaryHeapGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    type Target = BinaryHeap<T2>;
+
+    /// Provides immutable access to the underlying `BinaryHeap`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, T1, T2> DerefMut for ReusableBinaryHeapGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Provides mutable access to the underlying `BinaryHeap`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'parent, T1, T2> ReusableBinaryHeapGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: Ord,
+{
+    /// The estimated number of heap bytes the `BinaryHeap` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `BinaryHeap`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one. Use this when a cycle's result has to outlive the guard,
+    /// for example to send it to another thread.
+    pub fn detach(mut self) -> BinaryHeap<T2> {
+        mem::take(&mut *self)
+    }
+}
+
+impl<T1> ReusableBinaryHeap<T1>
+where
+    T1: 'static,
+{
+    /// Reuses the `BinaryHeap`'s allocation, returning a guard cast to the
+    /// element type `T2`.
+    ///
+    /// The heap is empty, so it trivially upholds the heap property for the
+    /// ordering of `T2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the heap and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, T2>(&'parent mut self) -> ReusableBinaryHeapGuard<'parent, T1, T2>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the heap is
+        // always empty between guards, so no `T1` value is ever read as a
+        // `T2`.
+        let inner_ptr = self.inner.get() as *mut BinaryHeap<T2>;
+
+        ReusableBinaryHeapGuard {
+            inner: inner_ptr,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the heap without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the heap and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableBinaryHeapGuard<'_, T1, T1> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableBinaryHeapGuard {
+            inner: self.inner.get(),
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the heap and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<T2>(&mut self) -> Result<ReusableBinaryHeapGuard<'_, T1, T2>, PoisonError>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the heap was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the heap as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the heap, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.forget_leaked();
+            self.inner.get_mut().shrink_to_fit();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the heap for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableBinaryHeapGuard<'_, T1, T2>) -> R,
+    ) -> R
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `BinaryHeap`, which is always empty.
+    pub fn into_inner(mut self) -> BinaryHeap<T1> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// Leaks anything a leaked guard left in the heap.
+    ///
+    /// Guards leave the heap empty, so any elements in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let heap = self.inner.get_mut();
+        if !heap.is_empty() {
+            mem::forget(mem::take(heap));
+        }
+    }
+
+    /// The number of elements the retained `BinaryHeap` can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `BinaryHeap` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+}
+
+impl<T: 'static> Drop for ReusableBinaryHeap<T> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the heap.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, T1, T2> Drop for ReusableBinaryHeapGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Clears the `BinaryHeap` when the guard is dropped, keeping its
+    /// allocation.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let heap = unsafe { &mut *self.inner };
+        heap.clear();
+    }
+}
+
+/// A draining iterator over the contents of a [`ReusableBinaryHeapGuard`].
+///
+/// Returned by the guard's `IntoIterator` impl. The elements are moved out by
+/// value, and the `BinaryHeap` keeps its allocation for the next guard.
+///
+/// Like [`BinaryHeap::drain`], it yields the elements in arbitrary order.
+pub struct ReusableBinaryHeapIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    drain: Drain<'parent, T2>,
+    // Declared after `drain`, so the guard's cleanup runs once the drain has
+    // finished with the `BinaryHeap`.
+    _guard: ReusableBinaryHeapGuard<'parent, T1, T2>,
+}
+
+impl<'parent, T1, T2> IntoIterator for ReusableBinaryHeapGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    type Item = T2;
+    type IntoIter = ReusableBinaryHeapIntoIter<'parent, T1, T2>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // SAFETY: `self.inner` is valid and exclusive for `'parent`. The
+        // guard is moved into the iterator and only touches the `BinaryHeap` again
+        // when it is dropped, after the drain.
+        let drain = unsafe { &mut *self.inner }.drain();
+        ReusableBinaryHeapIntoIter {
+            drain,
+            _guard: self,
+        }
+    }
+}
+
+impl<'parent, T1, T2> Iterator for ReusableBinaryHeapIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    type Item = T2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<'parent, T1, T2> DoubleEndedIterator for ReusableBinaryHeapIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<'parent, T1, T2> ExactSizeIterator for ReusableBinaryHeapIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_is_retained_across_uses() {
+        let mut heap = ReusableBinaryHeap::<u32>::default();
+        let capacity = {
+            let mut guard = heap.recycle::<u32>();
+            guard.extend([3, 1, 4, 1, 5]);
+            assert_eq!(guard.pop(), Some(5));
+            guard.capacity()
+        };
+
+        let guard = heap.recycle::<u32>();
+        assert!(guard.is_empty());
+        assert_eq!(guard.capacity(), capacity);
+    }
+
+    #[test]
+    fn borrowed_elements_can_be_recycled() {
+        let mut heap = ReusableBinaryHeap::<&'static str>::default();
+        for round in 0..2 {
+            let owned = format!("node {}", round);
+            let mut guard = heap.recycle::<&str>();
+            guard.push(owned.as_str());
+            guard.push("a");
+            assert_eq!(guard.pop(), Some(owned.as_str()));
+        }
+    }
+
+    #[test]
+    fn a_panic_poisons_the_heap_until_it_is_reset() {
+        let mut heap = ReusableBinaryHeap::<u32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = heap.recycle_same();
+            guard.extend(0..64);
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(heap.is_poisoned());
+
+        // The default policy starts over without an allocation.
+        assert_eq!(heap.recycle_same().capacity(), 0);
+        assert!(!heap.is_poisoned());
+    }
+}
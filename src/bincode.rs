@@ -0,0 +1,112 @@
+//! [bincode](https://docs.rs/bincode) serialization into recycled buffers.
+//!
+//! Snapshotting state or sending it over an IPC channel usually goes through
+//! [`bincode::serde::encode_to_vec`], which allocates a new `Vec<u8>` every
+//! time. [`serialize_reusing`] writes into a [`ReusableVec<u8>`] instead, and
+//! [`deserialize_reusing`] reads a whole message into one before decoding it,
+//! so a steady stream of snapshots needs no allocations for the byte buffers
+//! at all.
+//!
+//! This module is only available with the `bincode` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{bincode::{deserialize_reusing, serialize_reusing}, ReusableVec};
+//!
+//! let config = bincode::config::standard();
+//! let mut write_buffer = ReusableVec::<u8>::default();
+//! let mut read_buffer = ReusableVec::<u8>::default();
+//!
+//! for tick in 0..3u64 {
+//!     let snapshot = (tick, vec!["a".to_string(), "b".to_string()]);
+//!     let bytes = serialize_reusing(&snapshot, &mut write_buffer, config).unwrap();
+//!
+//!     let decoded: (u64, Vec<String>) =
+//!         deserialize_reusing(bytes.as_slice(), &mut read_buffer, config).unwrap();
+//!     assert_eq!(decoded, snapshot);
+//! }
+//! ```
+use crate::{ReusableVec, ReusableVecGuard};
+use bincode::{config::Config, error::EncodeError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{self, Read};
+
+/// Serializes `value` into the recycled `buffer`, returning a guard over the
+/// encoded bytes.
+///
+/// This is the recycling counterpart of [`bincode::serde::encode_to_vec`].
+pub fn serialize_reusing<'parent, T, C>(
+    value: &T,
+    buffer: &'parent mut ReusableVec<u8>,
+    config: C,
+) -> Result<ReusableVecGuard<'parent, u8, u8>, EncodeError>
+where
+    T: Serialize + ?Sized,
+    C: Config,
+{
+    let mut guard = buffer.recycle();
+    bincode::serde::encode_into_std_write(value, &mut *guard, config)?;
+    Ok(guard)
+}
+
+/// Reads `reader` to the end into the recycled `buffer` and deserializes a
+/// `T` from the bytes read.
+///
+/// Decoding errors are reported as [`io::ErrorKind::InvalidData`]. The buffer
+/// is cleared again before this function returns, keeping its capacity for
+/// the next message.
+pub fn deserialize_reusing<T, R, C>(
+    mut reader: R,
+    buffer: &mut ReusableVec<u8>,
+    config: C,
+) -> io::Result<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+    C: Config,
+{
+    let mut guard = buffer.recycle::<u8>();
+    reader.read_to_end(&mut guard)?;
+    bincode::serde::decode_from_slice(&guard, config)
+        .map(|(value, _)| value)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::config::{legacy, standard};
+
+    #[test]
+    fn encoding_follows_the_config() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let mut read_buffer = ReusableVec::<u8>::default();
+        let value = (300u32, "three hundred".to_string());
+
+        let encoded = serialize_reusing(&value, &mut buffer, standard())
+            .unwrap()
+            .to_vec();
+        assert_eq!(
+            encoded,
+            bincode::serde::encode_to_vec(&value, standard()).unwrap()
+        );
+        let decoded: (u32, String) =
+            deserialize_reusing(encoded.as_slice(), &mut read_buffer, standard()).unwrap();
+        assert_eq!(decoded, value);
+
+        // The legacy config writes fixed-width integers, so the same value
+        // takes more bytes.
+        let legacy_encoded = serialize_reusing(&value, &mut buffer, legacy()).unwrap();
+        assert_eq!(
+            *legacy_encoded,
+            bincode::serde::encode_to_vec(&value, legacy()).unwrap()
+        );
+        assert!(legacy_encoded.len() > encoded.len());
+
+        let err =
+            deserialize_reusing::<String, _, _>(&[0xff, 0xff][..], &mut read_buffer, standard())
+                .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
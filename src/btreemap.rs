@@ -0,0 +1,401 @@
+use crate::{poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto};
+use std::{
+    cell::UnsafeCell,
+    collections::{btree_map, BTreeMap},
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A wrapper around `BTreeMap` that can be recycled across iterations and
+/// across compatible key and value types.
+///
+/// `ReusableBTreeMap` follows the same pattern as
+/// [`ReusableHashMap`](crate::ReusableHashMap): [`recycle`](ReusableBTreeMap::recycle)
+/// returns a [`ReusableBTreeMapGuard`] with exclusive access to the map, and
+/// the map is cleared when the guard is dropped. Key and value types can be
+/// cast through [`ReuseCastInto`], so a `ReusableBTreeMap<&'static str, _>`
+/// can hold `&'a str` keys borrowed from a single iteration's input.
+///
+/// # What is retained
+///
+/// Unlike `Vec` or `HashMap`, a `BTreeMap` has no capacity: its elements live
+/// in individually allocated nodes, and the standard library frees every node
+/// when the map is cleared. There is no stable API to keep them, so recycling
+/// a `BTreeMap` does not save node allocations. What it does provide is a
+/// single long-lived map that can be reused with borrowed, per-iteration keys,
+/// which a plain `BTreeMap` field cannot do without reallocating the map or
+/// fighting the borrow checker.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableBTreeMap;
+///
+/// let mut index = ReusableBTreeMap::<&'static str, usize>::default();
+///
+/// for batch in ["b a c a", "z y z"] {
+///     let mut counts = index.recycle::<&str, usize>();
+///     for token in batch.split(' ') {
+///         *counts.entry(token).or_default() += 1;
+///     }
+///     // Iteration is in key order.
+///     let first = counts.iter().next().unwrap();
+///     assert!(first == (&"a", &2) || first == (&"y", &1));
+/// }
+/// ```
+pub struct ReusableBTreeMap<K: 'static, V: 'static> {
+    inner: UnsafeCell<BTreeMap<K, V>>,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableBTreeMap` is safe to send across threads if its contents are
+// `Send`.
+unsafe impl<K: Send, V: Send> Send for ReusableBTreeMap<K, V> {}
+
+// The `ReusableBTreeMap` is safe to share across threads if its contents are
+// `Send`, because `recycle` requires `&mut self`.
+unsafe impl<K: Send, V: Send> Sync for ReusableBTreeMap<K, V> {}
+
+// A B-tree keeps no allocation between guards, so there is nothing to show.
+impl<K: 'static, V: 'static> fmt::Debug for ReusableBTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableBTreeMap").finish_non_exhaustive()
+    }
+}
+
+impl<K: 'static, V: 'static> Default for ReusableBTreeMap<K, V> {
+    /// Creates a new, empty `ReusableBTreeMap`.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(BTreeMap::new()),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to a `BTreeMap`
+/// retrieved from a [`ReusableBTreeMap`].
+///
+/// When the guard is dropped, it clears the `BTreeMap`.
+///
+/// # Type Parameters
+///
+/// - `'parent`: The lifetime of the mutable borrow of the parent [`ReusableBTreeMap`].
+/// - `K1`, `V1`: The original key and value types of the `ReusableBTreeMap`.
+/// - `K2`, `V2`: The new key and value types for the current use.
+pub struct ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+{
+    inner: *mut BTreeMap<K2, V2>,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableBTreeMap<K1, V1>>,
+}
+
+impl<'parent, K1, V1, K2, V2> Deref for ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+{
+    type Target = BTreeMap<K2, V2>;
+
+    /// Provides immutable access to the underlying `BTreeMap`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, K1, V1, K2, V2> DerefMut for ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+{
+    /// Provides mutable access to the underlying `BTreeMap`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'parent, K1, V1, K2, V2> ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Ord,
+{
+    /// The estimated number of heap bytes the `BTreeMap` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `BTreeMap`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one. Use this when a cycle's result has to outlive the guard,
+    /// for example to send it to another thread.
+    pub fn detach(mut self) -> BTreeMap<K2, V2> {
+        mem::take(&mut *self)
+    }
+}
+
+impl<K1, V1> ReusableBTreeMap<K1, V1>
+where
+    K1: 'static,
+    V1: 'static,
+{
+    /// Returns a guard for the map, cast to the key type `K2` and the value
+    /// type `V2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the map and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, K2, V2>(
+        &'parent mut self,
+    ) -> ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the map is
+        // always empty between guards, so no `K1`/`V1` value is ever read as
+        // a `K2`/`V2`.
+        let inner_ptr = self.inner.get() as *mut BTreeMap<K2, V2>;
+
+        ReusableBTreeMapGuard {
+            inner: inner_ptr,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the map without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the map and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableBTreeMapGuard<'_, K1, V1, K1, V1> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableBTreeMapGuard {
+            inner: self.inner.get(),
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the map and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<K2, V2>(
+        &mut self,
+    ) -> Result<ReusableBTreeMapGuard<'_, K1, V1, K2, V2>, PoisonError>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the map was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the map as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the map, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            // A B-tree keeps nothing between guards, so there is nothing
+            // to reset.
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the map for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<K2, V2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableBTreeMapGuard<'_, K1, V1, K2, V2>) -> R,
+    ) -> R
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `BTreeMap`, which is always empty.
+    pub fn into_inner(mut self) -> BTreeMap<K1, V1> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// Leaks anything a leaked guard left in the map.
+    ///
+    /// Guards leave the map empty, so any elements in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let map = self.inner.get_mut();
+        if !map.is_empty() {
+            mem::forget(mem::take(map));
+        }
+    }
+
+    /// The estimated number of heap bytes the retained `BTreeMap` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+}
+
+impl<K: 'static, V: 'static> Drop for ReusableBTreeMap<K, V> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the map.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, K1, V1, K2, V2> Drop for ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+{
+    /// Clears the `BTreeMap` when the guard is dropped.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let map = unsafe { &mut *self.inner };
+        map.clear();
+    }
+}
+
+impl<'parent, K1, V1, K2, V2> IntoIterator for ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Ord,
+{
+    type Item = (K2, V2);
+    type IntoIter = btree_map::IntoIter<K2, V2>;
+
+    /// Moves the contents out of the BTreeMap by value.
+    ///
+    /// A `BTreeMap` keeps no allocation once it is empty, so taking its contents
+    /// loses nothing the guard would have retained.
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::take(&mut *self).into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_iter_yields_entries_in_order() {
+        let mut map = ReusableBTreeMap::<u32, &'static str>::default();
+        let mut r_map = map.recycle::<u32, &str>();
+        r_map.insert(2, "two");
+        r_map.insert(1, "one");
+        let entries: Vec<_> = r_map.into_iter().collect();
+        assert_eq!(entries, [(1, "one"), (2, "two")]);
+        assert!(map.recycle_same().is_empty());
+    }
+
+    #[test]
+    fn maps_are_cleared_between_uses() {
+        let mut map = ReusableBTreeMap::<u32, String>::default();
+        {
+            let mut guard = map.recycle::<u32, String>();
+            guard.insert(2, "two".to_string());
+            guard.insert(1, "one".to_string());
+            assert_eq!(guard.keys().copied().collect::<Vec<_>>(), [1, 2]);
+        }
+        assert!(map.recycle::<u32, String>().is_empty());
+    }
+
+    #[test]
+    fn borrowed_keys_can_be_recycled() {
+        let mut map = ReusableBTreeMap::<&'static str, &'static str>::default();
+        for round in 0..2 {
+            let owned = format!("key {}", round);
+            let mut guard = map.recycle::<&str, &str>();
+            guard.insert(owned.as_str(), "value");
+            assert_eq!(guard.len(), 1);
+            assert_eq!(guard.get(owned.as_str()), Some(&"value"));
+        }
+    }
+
+    #[test]
+    fn a_panic_poisons_the_map() {
+        let mut map = ReusableBTreeMap::<u32, u32>::default();
+        map.set_poison_policy(PoisonPolicy::Error);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = map.recycle_same();
+            guard.insert(1, 1);
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(map.is_poisoned());
+        assert!(map.try_recycle::<u32, u32>().is_err());
+
+        map.clear_poison();
+        assert!(map.try_recycle::<u32, u32>().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto};
+use std::{
+    cell::UnsafeCell,
+    collections::{btree_set, BTreeSet},
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A wrapper around `BTreeSet` that can be recycled across iterations and
+/// across compatible element types.
+///
+/// This is the set counterpart of [`ReusableBTreeMap`](crate::ReusableBTreeMap):
+/// [`recycle`](ReusableBTreeSet::recycle) returns a [`ReusableBTreeSetGuard`],
+/// the set is cleared when the guard is dropped, and the element type can be
+/// cast through [`ReuseCastInto`], for example from `&'static str` to the
+/// `&'a str` tokens of one batch.
+///
+/// As with the map, the standard library frees a `BTreeSet`'s nodes when it
+/// is cleared, so recycling does not save node allocations. See
+/// [`ReusableBTreeMap`](crate::ReusableBTreeMap#what-is-retained) for
+/// details.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableBTreeSet;
+///
+/// let mut vocabulary = ReusableBTreeSet::<&'static str>::default();
+///
+/// for batch in [String::from("to be or not to be"), String::from("let it be")] {
+///     let mut tokens = vocabulary.recycle::<&str>();
+///     tokens.extend(batch.split(' '));
+///     println!("{:?}", *tokens);
+/// }
+/// ```
+pub struct ReusableBTreeSet<T: 'static> {
+    inner: UnsafeCell<BTreeSet<T>>,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableBTreeSet` is safe to send across threads if `T` is `Send`.
+unsafe impl<T: Send> Send for ReusableBTreeSet<T> {}
+
+// The `ReusableBTreeSet` is safe to share across threads if `T` is `Send`,
+// because `recycle` requires `&mut self`.
+unsafe impl<T: Send> Sync for ReusableBTreeSet<T> {}
+
+// A B-tree keeps no allocation between guards, so there is nothing to show.
+impl<T: 'static> fmt::Debug for ReusableBTreeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableBTreeSet").finish_non_exhaustive()
+    }
+}
+
+impl<T: 'static> Default for ReusableBTreeSet<T> {
+    /// Creates a new, empty `ReusableBTreeSet`.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(BTreeSet::new()),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to a `BTreeSet`
+/// retrieved from a [`ReusableBTreeSet`].
+///
+/// When the guard is dropped, it clears the `BTreeSet`.
+pub struct ReusableBTreeSetGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    inner: *mut BTreeSet<T2>,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableBTreeSet<T1>>,
+}
+
+impl<'parent, T1, T2> Deref for ReusableBTreeSetGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    type Target = BTreeSet<T2>;
+
+    /// Provides immutable access to the underlying `BTreeSet`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, T1, T2> DerefMut for ReusableBTreeSetGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Provides mutable access to the underlying `BTreeSet`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'parent, T1, T2> ReusableBTreeSetGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: Ord,
+{
+    /// The estimated number of heap bytes the `BTreeSet` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `BTreeSet`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one. Use this when a cycle's result has to outlive the guard,
+    /// for example to send it to another thread.
+    pub fn detach(mut self) -> BTreeSet<T2> {
+        mem::take(&mut *self)
+    }
+}
+
+impl<T1> ReusableBTreeSet<T1>
+where
+    T1: 'static,
+{
+    /// Returns a guard for the set, cast to the element type `T2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the set and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, T2>(&'parent mut self) -> ReusableBTreeSetGuard<'parent, T1, T2>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the set is
+        // always empty between guards, so no `T1` value is ever read as a
+        // `T2`.
+        let inner_ptr = self.inner.get() as *mut BTreeSet<T2>;
+
+        ReusableBTreeSetGuard {
+            inner: inner_ptr,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the set without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the set and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableBTreeSetGuard<'_, T1, T1> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableBTreeSetGuard {
+            inner: self.inner.get(),
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the set and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<T2>(&mut self) -> Result<ReusableBTreeSetGuard<'_, T1, T2>, PoisonError>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the set was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the set as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the set, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            // A B-tree keeps nothing between guards, so there is nothing
+            // to reset.
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the set for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableBTreeSetGuard<'_, T1, T2>) -> R,
+    ) -> R
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `BTreeSet`, which is always empty.
+    pub fn into_inner(mut self) -> BTreeSet<T1> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// Leaks anything a leaked guard left in the set.
+    ///
+    /// Guards leave the set empty, so any elements in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let set = self.inner.get_mut();
+        if !set.is_empty() {
+            mem::forget(mem::take(set));
+        }
+    }
+
+    /// The estimated number of heap bytes the retained `BTreeSet` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+}
+
+impl<T: 'static> Drop for ReusableBTreeSet<T> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the set.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, T1, T2> Drop for ReusableBTreeSetGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Clears the `BTreeSet` when the guard is dropped.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let set = unsafe { &mut *self.inner };
+        set.clear();
+    }
+}
+
+impl<'parent, T1, T2> IntoIterator for ReusableBTreeSetGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: Ord,
+{
+    type Item = T2;
+    type IntoIter = btree_set::IntoIter<T2>;
+
+    /// Moves the contents out of the BTreeSet by value.
+    ///
+    /// A `BTreeSet` keeps no allocation once it is empty, so taking its contents
+    /// loses nothing the guard would have retained.
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::take(&mut *self).into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_are_sorted_and_cleared_between_uses() {
+        let mut set = ReusableBTreeSet::<&'static str>::default();
+        {
+            let text = String::from("b c a b");
+            let mut guard = set.recycle::<&str>();
+            guard.extend(text.split(' '));
+            assert_eq!(guard.iter().copied().collect::<Vec<_>>(), ["a", "b", "c"]);
+        }
+        assert!(set.recycle::<&str>().is_empty());
+    }
+
+    #[test]
+    fn a_panic_poisons_the_set() {
+        let mut set = ReusableBTreeSet::<u32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = set.recycle_same();
+            guard.insert(1);
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(set.is_poisoned());
+
+        // The default policy carries on with an empty set.
+        assert!(set.recycle_same().is_empty());
+        assert!(!set.is_poisoned());
+    }
+}
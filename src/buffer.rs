@@ -0,0 +1,133 @@
+use crate::{ReusableString, ReusableStringBytesGuard, ReusableStringGuard};
+use std::fmt;
+
+/// One retained allocation, recycled as text or as bytes.
+///
+/// Code that alternates between formatting text and encoding binary data
+/// would otherwise keep a [`ReusableString`] and a
+/// [`ReusableVec<u8>`](crate::ReusableVec) side by side, each holding on to
+/// an allocation sized for its own peak. A `ReusableBuffer` keeps one.
+/// [`recycle_str`](ReusableBuffer::recycle_str) hands it out as a `String`,
+/// and [`recycle_bytes`](ReusableBuffer::recycle_bytes) as a `Vec<u8>`. Both
+/// borrow the buffer mutably, so only one view exists at a time, and the
+/// allocation is cleared whenever a view is dropped, so bytes written
+/// through one never show up as text in the other.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Write;
+/// use triple_r::ReusableBuffer;
+///
+/// let mut buffer = ReusableBuffer::default();
+///
+/// for id in 0..3u32 {
+///     let mut key = buffer.recycle_str();
+///     write!(key, "session:{}", id).unwrap();
+///     assert!(key.starts_with("session:"));
+///     drop(key);
+///
+///     let mut frame = buffer.recycle_bytes();
+///     frame.extend_from_slice(&id.to_be_bytes());
+///     assert_eq!(frame.len(), 4);
+/// }
+///
+/// assert!(buffer.capacity() >= 9);
+/// ```
+#[derive(Default)]
+pub struct ReusableBuffer {
+    inner: ReusableString,
+}
+
+impl fmt::Debug for ReusableBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableBuffer")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl From<String> for ReusableBuffer {
+    /// Adopts the allocation of `string`, discarding its contents.
+    fn from(string: String) -> Self {
+        Self {
+            inner: ReusableString::from(string),
+        }
+    }
+}
+
+impl From<Vec<u8>> for ReusableBuffer {
+    /// Adopts the allocation of `bytes`, discarding its contents.
+    fn from(mut bytes: Vec<u8>) -> Self {
+        bytes.clear();
+        let string = String::from_utf8(bytes).expect("an empty buffer is valid UTF-8");
+        Self::from(string)
+    }
+}
+
+impl ReusableBuffer {
+    /// Creates a `ReusableBuffer` whose allocation already holds `capacity`
+    /// bytes, so the first cycles don't reallocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: ReusableString::with_capacity(capacity),
+        }
+    }
+
+    /// Reuses the allocation as a `String`.
+    pub fn recycle_str(&mut self) -> ReusableStringGuard<'_> {
+        self.inner.recycle()
+    }
+
+    /// Reuses the allocation as a `Vec<u8>`, whose contents don't have to be
+    /// valid UTF-8.
+    pub fn recycle_bytes(&mut self) -> ReusableStringBytesGuard<'_> {
+        self.inner.recycle_as_bytes()
+    }
+
+    /// The number of bytes the retained allocation can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// The estimated number of heap bytes the retained allocation holds on
+    /// to.
+    pub fn memory_footprint(&self) -> usize {
+        self.inner.memory_footprint()
+    }
+
+    /// Releases the retained allocation, without taking a guard.
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+
+    /// The [`ReusableString`] behind the buffer, to configure its capacity
+    /// policies and hooks, which apply to both views.
+    pub fn reusable_string_mut(&mut self) -> &mut ReusableString {
+        &mut self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_views_share_one_allocation() {
+        let mut buffer = ReusableBuffer::from(Vec::with_capacity(32));
+        buffer
+            .reusable_string_mut()
+            .set_max_retained_capacity(Some(64));
+
+        let ptr = buffer.recycle_bytes().as_ptr();
+        buffer.recycle_bytes().extend_from_slice(&[0xff; 16]);
+        let text = buffer.recycle_str();
+        assert!(text.is_empty());
+        assert_eq!(text.as_ptr(), ptr);
+        drop(text);
+
+        buffer.recycle_str().push_str(&"x".repeat(128));
+        assert_eq!(buffer.capacity(), 64);
+    }
+}
@@ -0,0 +1,463 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr, slice, str,
+};
+
+/// A single retained slab that byte buffers, strings, and aligned buffers can
+/// all be carved from.
+///
+/// A subsystem that needs several kinds of byte scratch per cycle, say a
+/// [`ReusableVec<u8>`](crate::ReusableVec) for I/O, a
+/// [`ReusableString`](crate::ReusableString) for formatting, and an aligned
+/// buffer for SIMD, ends up pinning one allocation per recycler, each sized
+/// for its own peak. A `BytePool` retains one slab instead, and every
+/// [`recycle`](BytePool::recycle) cycle hands out pieces of it through a
+/// bump-allocating [`BytePoolGuard`]:
+///
+/// - [`bytes`](BytePoolGuard::bytes) carves a [`SlabBytes`], a growable-looking
+///   byte buffer with a fixed capacity.
+/// - [`string`](BytePoolGuard::string) carves a [`SlabString`], its UTF-8
+///   counterpart.
+/// - [`aligned`](BytePoolGuard::aligned) carves a zeroed `&mut [u8]` with the
+///   requested alignment.
+///
+/// The standard `Vec` and `String` always own an allocation from the global
+/// allocator, so they cannot be carved from a slab on stable Rust. The slab
+/// buffers expose the same everyday API instead, and dereference to `[u8]`
+/// and `str`.
+///
+/// Carving is a pointer bump. If a cycle needs more room than the slab has,
+/// the excess is served from temporary overflow chunks, and the slab is grown
+/// to fit the whole cycle when the guard is dropped. After one cycle at peak
+/// size, every carve is served from the slab.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Write;
+/// use triple_r::BytePool;
+///
+/// let mut pool = BytePool::default();
+///
+/// for frame in 0..3 {
+///     let scratch = pool.recycle();
+///
+///     let mut packet = scratch.bytes(64);
+///     packet.extend_from_slice(&[0xAB; 16]);
+///
+///     let mut label = scratch.string(32);
+///     write!(label, "frame {}", frame).unwrap();
+///
+///     let lanes = scratch.aligned(128, 64);
+///     assert_eq!(lanes.as_ptr() as usize % 64, 0);
+///
+///     assert_eq!(packet.len(), 16);
+///     assert_eq!(*label, format!("frame {}", frame));
+/// }
+///
+/// // Everything from the first cycle now fits in the retained slab.
+/// assert!(pool.capacity() >= 64 + 32 + 128);
+/// ```
+#[derive(Default)]
+pub struct BytePool {
+    slab: Vec<MaybeUninit<u8>>,
+}
+
+impl fmt::Debug for BytePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BytePool")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl BytePool {
+    /// Creates a `BytePool` with a slab of `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut pool = Self::default();
+        pool.grow_to(capacity);
+        pool
+    }
+
+    /// The size of the retained slab, in bytes.
+    pub fn capacity(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Starts a new cycle, returning a guard that carves buffers out of the
+    /// slab.
+    ///
+    /// The `&mut self` requirement ensures that only one cycle is active at a
+    /// time.
+    pub fn recycle(&mut self) -> BytePoolGuard<'_> {
+        BytePoolGuard {
+            slab: self.slab.as_mut_ptr(),
+            slab_len: self.slab.len(),
+            offset: Cell::new(0),
+            overflow: RefCell::new(Vec::new()),
+            overflow_bytes: Cell::new(0),
+            pool: self,
+        }
+    }
+
+    fn grow_to(&mut self, capacity: usize) {
+        if capacity > self.slab.len() {
+            self.slab = Vec::with_capacity(capacity);
+            // SAFETY: `MaybeUninit<u8>` does not need to be initialized.
+            unsafe { self.slab.set_len(capacity) };
+        }
+    }
+}
+
+/// A RAII guard for one cycle of a [`BytePool`].
+///
+/// Buffers are carved through `&self`, so any number of them can be alive at
+/// the same time. They all borrow the guard, and are therefore gone by the
+/// time the guard is dropped and the slab is reused.
+pub struct BytePoolGuard<'parent> {
+    slab: *mut MaybeUninit<u8>,
+    slab_len: usize,
+    offset: Cell<usize>,
+    overflow: RefCell<Vec<Vec<MaybeUninit<u8>>>>,
+    overflow_bytes: Cell<usize>,
+    pool: &'parent mut BytePool,
+}
+
+impl<'parent> BytePoolGuard<'parent> {
+    /// Carves an empty byte buffer with room for `capacity` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` overflows `isize`.
+    pub fn bytes(&self, capacity: usize) -> SlabBytes<'_> {
+        SlabBytes {
+            ptr: self.carve(capacity, 1),
+            len: 0,
+            capacity,
+            _guard: PhantomData,
+        }
+    }
+
+    /// Carves an empty string with room for `capacity` bytes.
+    pub fn string(&self, capacity: usize) -> SlabString<'_> {
+        SlabString {
+            bytes: self.bytes(capacity),
+        }
+    }
+
+    /// Carves a zeroed buffer of `len` bytes whose start is aligned to
+    /// `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or if `len` plus the
+    /// alignment padding overflows `isize`.
+    // Every carve is a distinct piece of the slab, like with any bump
+    // allocator, so handing out `&mut` from `&self` does not alias.
+    #[allow(clippy::mut_from_ref)]
+    pub fn aligned(&self, len: usize, align: usize) -> &mut [u8] {
+        assert!(align.is_power_of_two(), "align must be a power of two");
+        let ptr = self.carve(len, align).cast::<u8>();
+        // SAFETY: `carve` returned `len` bytes that nothing else refers to,
+        // and they are initialized right here.
+        unsafe {
+            ptr::write_bytes(ptr, 0, len);
+            slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
+    /// The number of bytes carved so far in this cycle, including alignment
+    /// padding.
+    pub fn used(&self) -> usize {
+        self.offset
+            .get()
+            .checked_add(self.overflow_bytes.get())
+            .expect("carved bytes overflow usize")
+    }
+
+    /// Reserves `len` bytes aligned to `align`, from the slab if they fit and
+    /// from a new overflow chunk otherwise.
+    fn carve(&self, len: usize, align: usize) -> *mut MaybeUninit<u8> {
+        let offset = self.offset.get();
+        let padding = (self.slab as usize + offset).wrapping_neg() & (align - 1);
+        if let Some(end) = offset
+            .checked_add(padding)
+            .and_then(|start| start.checked_add(len))
+        {
+            if end <= self.slab_len {
+                self.offset.set(end);
+                // SAFETY: `offset + padding` is within the slab.
+                return unsafe { self.slab.add(offset + padding) };
+            }
+        }
+
+        let size = len
+            .checked_add(align - 1)
+            .filter(|&size| size <= isize::MAX as usize)
+            .expect("requested size overflows isize");
+        let overflow_bytes = self.overflow_bytes.get().checked_add(size);
+        self.overflow_bytes
+            .set(overflow_bytes.expect("carved bytes overflow usize"));
+        let mut chunk = Vec::with_capacity(size);
+        // SAFETY: `MaybeUninit<u8>` does not need to be initialized.
+        unsafe { chunk.set_len(size) };
+        let start = chunk.as_mut_ptr();
+        let padding = (start as usize).wrapping_neg() & (align - 1);
+        // Moving the chunk into the list does not move its heap buffer.
+        self.overflow.borrow_mut().push(chunk);
+        // SAFETY: `padding < align`, so `padding + len` fits in the chunk.
+        unsafe { start.add(padding) }
+    }
+}
+
+impl<'parent> Drop for BytePoolGuard<'parent> {
+    /// Frees any overflow chunks and grows the slab so the next cycle fits in
+    /// it.
+    fn drop(&mut self) {
+        let used = self.used();
+        self.overflow.get_mut().clear();
+        self.pool.grow_to(used);
+    }
+}
+
+/// A fixed-capacity byte buffer carved from a [`BytePool`].
+///
+/// It dereferences to the initialized bytes and supports the usual ways of
+/// appending to a `Vec<u8>`, but it can never grow past the capacity it was
+/// carved with. Writing beyond it panics.
+pub struct SlabBytes<'guard> {
+    ptr: *mut MaybeUninit<u8>,
+    len: usize,
+    capacity: usize,
+    _guard: PhantomData<&'guard mut [u8]>,
+}
+
+impl<'guard> SlabBytes<'guard> {
+    /// The number of bytes this buffer was carved with.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Appends `data` to the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer does not have room for `data`.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        assert!(
+            data.len() <= self.capacity - self.len,
+            "slab buffers cannot grow beyond their capacity"
+        );
+        // SAFETY: The check above keeps the write within the carved bytes,
+        // which do not overlap `data`.
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), self.ptr.add(self.len).cast(), data.len());
+        }
+        self.len += data.len();
+    }
+
+    /// Appends a single byte to the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is full.
+    pub fn push(&mut self, byte: u8) {
+        self.extend_from_slice(&[byte]);
+    }
+
+    /// Shortens the buffer to `len` bytes. Does nothing if it is already
+    /// shorter.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Empties the buffer, keeping its capacity.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<'guard> Deref for SlabBytes<'guard> {
+    type Target = [u8];
+
+    /// Provides immutable access to the initialized bytes.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The first `len` carved bytes have been initialized.
+        unsafe { slice::from_raw_parts(self.ptr.cast(), self.len) }
+    }
+}
+
+impl<'guard> DerefMut for SlabBytes<'guard> {
+    /// Provides mutable access to the initialized bytes.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply.
+        unsafe { slice::from_raw_parts_mut(self.ptr.cast(), self.len) }
+    }
+}
+
+impl<'guard> std::io::Write for SlabBytes<'guard> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.capacity - self.len);
+        self.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'guard> fmt::Debug for SlabBytes<'guard> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A fixed-capacity string carved from a [`BytePool`].
+///
+/// This is the UTF-8 counterpart of [`SlabBytes`]. Appending beyond its
+/// capacity panics, except through [`fmt::Write`], which reports an error
+/// instead.
+pub struct SlabString<'guard> {
+    bytes: SlabBytes<'guard>,
+}
+
+impl<'guard> SlabString<'guard> {
+    /// The number of bytes this string was carved with.
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Appends `s` to the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string does not have room for `s`.
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends a single character to the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string does not have room for `c`.
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Empties the string, keeping its capacity.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+}
+
+impl<'guard> Deref for SlabString<'guard> {
+    type Target = str;
+
+    /// Provides immutable access to the string.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: Only whole `str`s are ever appended.
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl<'guard> DerefMut for SlabString<'guard> {
+    /// Provides mutable access to the string.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply.
+        unsafe { str::from_utf8_unchecked_mut(&mut self.bytes) }
+    }
+}
+
+impl<'guard> fmt::Write for SlabString<'guard> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() > self.capacity() - self.bytes.len() {
+            return Err(fmt::Error);
+        }
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl<'guard> fmt::Debug for SlabString<'guard> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carves_share_one_slab() {
+        let mut pool = BytePool::with_capacity(256);
+        let guard = pool.recycle();
+        let mut bytes = guard.bytes(16);
+        let mut string = guard.string(16);
+        bytes.extend_from_slice(b"bytes");
+        string.push_str("string");
+        let aligned = guard.aligned(32, 32);
+
+        assert_eq!(&*bytes, b"bytes");
+        assert_eq!(&*string, "string");
+        assert!(aligned.iter().all(|&byte| byte == 0));
+        assert_eq!(aligned.as_ptr() as usize % 32, 0);
+        assert!(guard.overflow.borrow().is_empty());
+        assert!(guard.used() >= 64);
+    }
+
+    #[test]
+    fn overflow_grows_the_slab_for_the_next_cycle() {
+        let mut pool = BytePool::default();
+        {
+            let guard = pool.recycle();
+            let mut first = guard.bytes(100);
+            let mut second = guard.bytes(200);
+            first.push(1);
+            second.push(2);
+            assert_eq!((first[0], second[0]), (1, 2));
+            assert_eq!(guard.overflow.borrow().len(), 2);
+        }
+        assert!(pool.capacity() >= 300);
+
+        let guard = pool.recycle();
+        let _first = guard.bytes(100);
+        let _second = guard.bytes(200);
+        assert!(guard.overflow.borrow().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "beyond their capacity")]
+    fn slab_buffers_do_not_grow() {
+        let mut pool = BytePool::with_capacity(8);
+        let guard = pool.recycle();
+        let mut bytes = guard.bytes(4);
+        bytes.extend_from_slice(b"12345");
+    }
+
+    #[test]
+    #[should_panic(expected = "requested size overflows isize")]
+    fn huge_carves_are_rejected() {
+        let mut pool = BytePool::with_capacity(8);
+        let guard = pool.recycle();
+        guard.aligned(usize::MAX, 2);
+    }
+
+    #[test]
+    fn formatting_past_the_capacity_fails() {
+        use std::fmt::Write;
+
+        let mut pool = BytePool::default();
+        let guard = pool.recycle();
+        let mut string = guard.string(4);
+        assert!(write!(string, "{}", 12).is_ok());
+        assert!(write!(string, "{}", 345).is_err());
+        assert_eq!(&*string, "12");
+    }
+}
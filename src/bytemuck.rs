@@ -0,0 +1,121 @@
+//! Checked casts between [bytemuck](https://docs.rs/bytemuck) `Pod` types.
+//!
+//! Reusing a `Vec<u32>` index buffer as a `Vec<i32>` or a `Vec<[u8; 4]>`
+//! normally needs a hand-written `unsafe impl ReuseCastInto`. For plain-old-
+//! data types, [`ReusableVec::try_recycle_cast`] does it without any unsafe
+//! code: it checks that both types have the same size and alignment, and
+//! returns a [`LayoutMismatch`] if they don't.
+//!
+//! This module is only available with the `bytemuck` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::ReusableVec;
+//!
+//! let mut indices = ReusableVec::<u32>::default();
+//! indices.recycle::<u32>().extend(0..1024);
+//!
+//! // The same allocation, now holding signed offsets.
+//! let mut offsets = indices.try_recycle_cast::<i32>().unwrap();
+//! assert!(offsets.capacity() >= 1024);
+//! offsets.push(-1);
+//! drop(offsets);
+//!
+//! // A `u64` doesn't fit in a `u32`'s slot.
+//! assert!(indices.try_recycle_cast::<u64>().is_err());
+//! ```
+use crate::{ReusableVec, ReusableVecGuard};
+use bytemuck::Pod;
+use std::{alloc::Layout, error::Error, fmt};
+
+impl<T1> ReusableVec<T1>
+where
+    T1: Pod,
+{
+    /// Returns a guard for the `Vec` cast to another `Pod` type, if both
+    /// types have the same size and alignment.
+    ///
+    /// Unlike [`recycle`](ReusableVec::recycle), this needs no
+    /// [`ReuseCastInto`](crate::ReuseCastInto) impl: any bit pattern is valid
+    /// for a `Pod` type, and matching layouts mean the allocation can be
+    /// handed back to the allocator as either type.
+    pub fn try_recycle_cast<T2>(&mut self) -> Result<ReusableVecGuard<'_, T1, T2>, LayoutMismatch>
+    where
+        T2: Pod,
+    {
+        let source = Layout::new::<T1>();
+        let target = Layout::new::<T2>();
+        if source != target {
+            return Err(LayoutMismatch { source, target });
+        }
+        // SAFETY: `T1` and `T2` have the same size and alignment, and
+        // neither needs to be dropped.
+        Ok(unsafe { self.recycle_unchecked() })
+    }
+}
+
+/// The error returned by [`ReusableVec::try_recycle_cast`] when the two
+/// element types have different layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    source: Layout,
+    target: Layout,
+}
+
+impl LayoutMismatch {
+    /// The layout of the element type the `Vec` holds.
+    pub fn source_layout(&self) -> Layout {
+        self.source
+    }
+
+    /// The layout of the element type that was requested.
+    pub fn target_layout(&self) -> Layout {
+        self.target
+    }
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot cast elements of size {} and alignment {} to size {} and alignment {}",
+            self.source.size(),
+            self.source.align(),
+            self.target.size(),
+            self.target.align()
+        )
+    }
+}
+
+impl Error for LayoutMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_layouts_share_the_allocation() {
+        let mut buffer = ReusableVec::<u32>::default();
+        let ptr = {
+            let mut guard = buffer.recycle::<u32>();
+            guard.extend(0..16);
+            guard.as_ptr() as usize
+        };
+
+        let mut guard = buffer.try_recycle_cast::<f32>().unwrap();
+        assert!(guard.is_empty());
+        guard.push(1.5);
+        assert_eq!(guard.as_ptr() as usize, ptr);
+    }
+
+    #[test]
+    fn mismatched_layouts_are_rejected() {
+        let mut buffer = ReusableVec::<u32>::default();
+        let err = buffer.try_recycle_cast::<[u8; 4]>().err().unwrap();
+        assert_eq!(err.source_layout(), Layout::new::<u32>());
+        assert_eq!(err.target_layout().align(), 1);
+
+        assert!(buffer.try_recycle_cast::<u16>().is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! [`BufMut`] support for recycled byte buffers.
+//!
+//! Tokio's `read_buf`, prost's `encode`, and many other APIs write into a
+//! [`BufMut`]. With this feature, a [`ReusableVecGuard`] over `u8` is one, so
+//! those APIs can fill a recycled buffer directly instead of a fresh
+//! `Vec<u8>` or `BytesMut`. The guard grows the `Vec` on demand, just like
+//! `Vec<u8>`'s own impl.
+//!
+//! [`BytesMut`] is [`Recyclable`] as well, so it can be kept in a
+//! [`Reusable`](crate::Reusable) or a [`ReusablePool`](crate::ReusablePool)
+//! when the filled buffer has to be frozen into `Bytes`.
+//!
+//! This module is only available with the `bytes` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use bytes::BufMut;
+//! use triple_r::ReusableVec;
+//!
+//! let mut frame = ReusableVec::<u8>::default();
+//!
+//! for id in [1u32, 2, 3] {
+//!     let mut buf = frame.recycle_same();
+//!     buf.put_u8(0x7f);
+//!     buf.put_u32(id);
+//!     buf.put_slice(b"payload");
+//!     assert_eq!(buf.len(), 12);
+//! }
+//! ```
+use crate::{Recyclable, ReusableVecGuard};
+use bytes::{buf::UninitSlice, BufMut, BytesMut};
+
+// SAFETY: Every method forwards to `Vec<u8>`'s `BufMut` impl, which upholds
+// the trait's contract. The guard has exclusive access to the `Vec` for its
+// whole lifetime.
+unsafe impl<'parent, T1> BufMut for ReusableVecGuard<'parent, T1, u8>
+where
+    T1: 'static,
+{
+    fn remaining_mut(&self) -> usize {
+        (**self).remaining_mut()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        // SAFETY: The caller upholds `advance_mut`'s contract for the `Vec`.
+        unsafe { (**self).advance_mut(cnt) }
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        (**self).chunk_mut()
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        self.extend_from_slice(src);
+    }
+
+    fn put_bytes(&mut self, val: u8, cnt: usize) {
+        (**self).put_bytes(val, cnt);
+    }
+}
+
+impl Recyclable for BytesMut {
+    fn clear(&mut self) {
+        BytesMut::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        BytesMut::capacity(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReusableVec;
+    use bytes::Buf;
+
+    #[test]
+    fn writes_land_in_the_recycled_allocation() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let ptr = {
+            let mut buf = buffer.recycle_same();
+            buf.put_bytes(0, 64);
+            buf.as_ptr() as usize
+        };
+
+        let mut buf = buffer.recycle_same();
+        buf.put_u16_le(0xbeef);
+        buf.put(&b"tail"[..]);
+        assert_eq!(buf.as_ptr() as usize, ptr);
+
+        let mut read = &buf[..];
+        assert_eq!(read.get_u16_le(), 0xbeef);
+        assert_eq!(read, b"tail");
+    }
+}
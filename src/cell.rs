@@ -0,0 +1,664 @@
+use crate::{Recyclable, Recycle};
+use std::{
+    cell::{Cell, UnsafeCell},
+    error::Error,
+    fmt,
+    ops::{Deref, DerefMut},
+    panic::Location,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+/// A cell that hands out a reusable container through a shared reference,
+/// checking at runtime that only one caller uses it at a time.
+///
+/// The reusable containers require `&mut self` to [`recycle`](crate::ReusableVec::recycle),
+/// which the borrow checker enforces for free. When a container has to be
+/// reached through `&self` instead, for example from several callbacks that
+/// share one scratch buffer, `RecycleCell` moves that check to runtime, much
+/// like [`RefCell`](std::cell::RefCell) does.
+///
+/// Unlike `RefCell`, a failed acquisition always reports where the container
+/// is currently held: [`acquire`](RecycleCell::acquire) and
+/// [`try_acquire`](RecycleCell::try_acquire) are `#[track_caller]`, so the
+/// [`AlreadyRecycled`] error and the panic message point at the line that
+/// still holds the guard, in debug and release builds alike.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{RecycleCell, ReusableVec};
+///
+/// let scratch = RecycleCell::new(ReusableVec::<u32>::default());
+///
+/// let mut held = scratch.acquire();
+/// let mut vec = held.recycle::<u32>();
+/// vec.push(1);
+///
+/// let err = scratch.try_acquire().unwrap_err();
+/// assert_eq!(err.location().file(), file!());
+/// assert!(err.to_string().starts_with("already recycled at "));
+///
+/// drop(vec);
+/// drop(held);
+/// assert!(scratch.try_acquire().is_ok());
+/// ```
+pub struct RecycleCell<R> {
+    inner: UnsafeCell<R>,
+    held_at: Cell<Option<&'static Location<'static>>>,
+}
+
+impl<R> RecycleCell<R> {
+    /// Creates a cell holding `reusable`.
+    pub fn new(reusable: R) -> Self {
+        Self {
+            inner: UnsafeCell::new(reusable),
+            held_at: Cell::new(None),
+        }
+    }
+
+    /// Acquires the container, or returns where it is currently held.
+    #[track_caller]
+    pub fn try_acquire(&self) -> Result<RecycleCellGuard<'_, R>, AlreadyRecycled> {
+        if let Some(location) = self.held_at.get() {
+            return Err(AlreadyRecycled { location });
+        }
+        self.held_at.set(Some(Location::caller()));
+        Ok(RecycleCellGuard {
+            // SAFETY: `held_at` was empty, so no other guard is alive, and it
+            // stays set until this guard is dropped.
+            inner: unsafe { &mut *self.inner.get() },
+            held_at: &self.held_at,
+        })
+    }
+
+    /// Acquires the container.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the container is already held, naming the location it was
+    /// acquired at.
+    #[track_caller]
+    pub fn acquire(&self) -> RecycleCellGuard<'_, R> {
+        match self.try_acquire() {
+            Ok(guard) => guard,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns where the container is currently held, if it is.
+    pub fn held_at(&self) -> Option<&'static Location<'static>> {
+        self.held_at.get()
+    }
+
+    /// Returns a mutable reference to the container.
+    ///
+    /// No runtime check is needed, since `&mut self` guarantees that no guard
+    /// is alive.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Consumes the cell, returning the container.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: Default> Default for RecycleCell<R> {
+    /// Creates a cell holding a default container.
+    fn default() -> Self {
+        Self::new(R::default())
+    }
+}
+
+impl<R> fmt::Debug for RecycleCell<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecycleCell")
+            .field("held_at", &self.held_at.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Exclusive access to the container of a [`RecycleCell`].
+///
+/// The cell becomes available again when the guard is dropped.
+pub struct RecycleCellGuard<'cell, R> {
+    inner: &'cell mut R,
+    held_at: &'cell Cell<Option<&'static Location<'static>>>,
+}
+
+impl<'cell, R> Deref for RecycleCellGuard<'cell, R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl<'cell, R> DerefMut for RecycleCellGuard<'cell, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner
+    }
+}
+
+impl<'cell, R: fmt::Debug> fmt::Debug for RecycleCellGuard<'cell, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<'cell, R> Drop for RecycleCellGuard<'cell, R> {
+    /// Releases the cell.
+    fn drop(&mut self) {
+        self.held_at.set(None);
+    }
+}
+
+/// A single-threaded [`Reusable`](crate::Reusable) that recycles through a
+/// shared reference, tracking the borrow at runtime.
+///
+/// GUI and game code often keeps scratch containers in application state that
+/// is shared between callbacks, where `&mut` can't be had statically.
+/// `LocalReusable` is to [`Reusable`](crate::Reusable) what
+/// [`RefCell`](std::cell::RefCell) is to a plain value:
+/// [`recycle`](LocalReusable::recycle) takes `&self` and panics if a guard is
+/// already alive, and [`try_recycle`](LocalReusable::try_recycle) returns an
+/// [`AlreadyRecycled`] error instead. Either way, the location that holds the
+/// guard is reported, like with [`RecycleCell`].
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+/// use triple_r::LocalReusable;
+///
+/// let scratch = Rc::new(LocalReusable::<Vec<u32>>::default());
+/// let on_click = {
+///     let scratch = Rc::clone(&scratch);
+///     move |x: u32| {
+///         let mut hits = scratch.recycle();
+///         hits.extend([x, x + 1]);
+///         hits.len()
+///     }
+/// };
+///
+/// assert_eq!(on_click(1), 2);
+/// let held = scratch.recycle();
+/// assert!(held.capacity() >= 2);
+/// assert!(scratch.try_recycle().is_err());
+/// ```
+pub struct LocalReusable<C> {
+    inner: UnsafeCell<C>,
+    held_at: Cell<Option<&'static Location<'static>>>,
+}
+
+impl<C: Recyclable> LocalReusable<C> {
+    /// Wraps `container`, clearing it so the first guard starts out empty.
+    pub fn new(mut container: C) -> Self {
+        container.clear();
+        Self {
+            inner: UnsafeCell::new(container),
+            held_at: Cell::new(None),
+        }
+    }
+
+    /// Returns a guard for the container, or where the current guard was
+    /// created if there is one.
+    #[track_caller]
+    pub fn try_recycle(&self) -> Result<LocalReusableGuard<'_, C>, AlreadyRecycled> {
+        if let Some(location) = self.held_at.get() {
+            return Err(AlreadyRecycled { location });
+        }
+        self.held_at.set(Some(Location::caller()));
+        // SAFETY: `held_at` was empty, so no other guard is alive, and it
+        // stays set until this guard is dropped.
+        let inner = unsafe { &mut *self.inner.get() };
+        // A guard that was leaked instead of dropped left its contents behind.
+        inner.clear();
+        Ok(LocalReusableGuard {
+            inner,
+            held_at: &self.held_at,
+        })
+    }
+
+    /// Returns a guard for the container.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a guard is already alive, naming the location it was created
+    /// at.
+    #[track_caller]
+    pub fn recycle(&self) -> LocalReusableGuard<'_, C> {
+        match self.try_recycle() {
+            Ok(guard) => guard,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns where the current guard was created, if there is one.
+    pub fn held_at(&self) -> Option<&'static Location<'static>> {
+        self.held_at.get()
+    }
+
+    /// The capacity of the retained container.
+    ///
+    /// No runtime check is needed, since `&mut self` guarantees that no guard
+    /// is alive.
+    pub fn capacity(&mut self) -> usize {
+        self.inner.get_mut().capacity()
+    }
+
+    /// Returns the retained container, which is always empty.
+    pub fn into_inner(self) -> C {
+        let mut container = self.inner.into_inner();
+        container.clear();
+        container
+    }
+}
+
+impl<C: Recyclable + Default> Default for LocalReusable<C> {
+    /// Creates a `LocalReusable` with a default container.
+    fn default() -> Self {
+        Self::new(C::default())
+    }
+}
+
+impl<C> fmt::Debug for LocalReusable<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalReusable")
+            .field("held_at", &self.held_at.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to the container
+/// of a [`LocalReusable`].
+///
+/// When the guard is dropped, it clears the container and releases the
+/// `LocalReusable`.
+pub struct LocalReusableGuard<'parent, C: Recyclable> {
+    inner: &'parent mut C,
+    held_at: &'parent Cell<Option<&'static Location<'static>>>,
+}
+
+impl<'parent, C: Recyclable> Deref for LocalReusableGuard<'parent, C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl<'parent, C: Recyclable> DerefMut for LocalReusableGuard<'parent, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner
+    }
+}
+
+impl<'parent, C: Recyclable + fmt::Debug> fmt::Debug for LocalReusableGuard<'parent, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<'parent, C: Recyclable> Drop for LocalReusableGuard<'parent, C> {
+    /// Clears the container and releases the `LocalReusable`.
+    fn drop(&mut self) {
+        self.inner.clear();
+        self.held_at.set(None);
+    }
+}
+
+/// A thread-safe cell that hands out a reusable container through a shared
+/// reference, checking with an atomic flag that only one caller uses it at a
+/// time.
+///
+/// [`RecycleCell`] is for a single thread. A `SyncRecycleCell` can be shared
+/// between threads, behind an [`Arc`](std::sync::Arc) or in a `static`,
+/// where `&mut` access can't be had but contention is known to be rare.
+/// Instead of blocking like a mutex, [`try_recycle`](SyncRecycleCell::try_recycle)
+/// returns `None` while the container is held, so the caller can fall back
+/// to a fresh allocation. [`try_acquire`](SyncRecycleCell::try_acquire)
+/// returns the container itself rather than recycling it, and an
+/// [`AlreadyRecycled`] error naming where it is held, like
+/// [`RecycleCell::try_acquire`].
+///
+/// Both are `#[track_caller]`. The location of the holder is kept next to
+/// the flag, behind a mutex that is only locked for as long as it takes to
+/// read or update it, so neither waits for the holder to finish.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use triple_r::{ReusableVec, SyncRecycleCell};
+///
+/// let scratch = Arc::new(SyncRecycleCell::new(ReusableVec::<u32>::default()));
+///
+/// let mut ids = scratch.try_recycle().expect("nobody else holds it");
+/// ids.push(1);
+/// assert!(scratch.try_recycle().is_none());
+///
+/// let err = scratch.try_acquire().unwrap_err();
+/// assert_eq!(err.location().file(), file!());
+///
+/// drop(ids);
+/// assert!(scratch.try_recycle().unwrap().is_empty());
+/// ```
+pub struct SyncRecycleCell<R> {
+    inner: UnsafeCell<R>,
+    held: AtomicBool,
+    held_at: Mutex<Option<&'static Location<'static>>>,
+}
+
+// SAFETY: The `held` flag gives at most one guard access to the container at
+// a time, so sharing the cell only ever moves that access between threads.
+unsafe impl<R: Send> Sync for SyncRecycleCell<R> {}
+
+impl<R> SyncRecycleCell<R> {
+    /// Creates a cell holding `reusable`.
+    pub const fn new(reusable: R) -> Self {
+        Self {
+            inner: UnsafeCell::new(reusable),
+            held: AtomicBool::new(false),
+            held_at: Mutex::new(None),
+        }
+    }
+
+    /// Acquires the container, or returns where it is currently held.
+    #[track_caller]
+    pub fn try_acquire(&self) -> Result<SyncRecycleCellGuard<'_, R>, AlreadyRecycled> {
+        // The flag only changes while the location is locked, so a holder has
+        // always recorded where it is by the time anyone else sees the flag.
+        let mut held_at = lock(&self.held_at);
+        // Acquire pairs with the release in the guard's drop, so this guard
+        // sees every write the previous one made.
+        if self
+            .held
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            let location = held_at.expect("the holder records its location with the flag");
+            return Err(AlreadyRecycled { location });
+        }
+        *held_at = Some(Location::caller());
+        Ok(SyncRecycleCellGuard {
+            // SAFETY: The flag was clear, so no other guard is alive, and it
+            // stays set until this guard is dropped.
+            inner: unsafe { &mut *self.inner.get() },
+            release: Release {
+                held: &self.held,
+                held_at: &self.held_at,
+            },
+        })
+    }
+
+    /// Whether the container is currently held.
+    pub fn is_held(&self) -> bool {
+        self.held.load(Ordering::Relaxed)
+    }
+
+    /// Returns where the container is currently held, if it is.
+    pub fn held_at(&self) -> Option<&'static Location<'static>> {
+        *lock(&self.held_at)
+    }
+
+    /// Returns a mutable reference to the container.
+    ///
+    /// No runtime check is needed, since `&mut self` guarantees that no guard
+    /// is alive.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Consumes the cell, returning the container.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: Recycle> SyncRecycleCell<R> {
+    /// Recycles the container, or returns `None` if it is currently held.
+    ///
+    /// The returned guard dereferences to the container's own guard, which
+    /// clears the container when it is dropped, after which the cell is
+    /// released. While it is alive, [`held_at`](Self::held_at) reports the
+    /// caller of `try_recycle`.
+    #[track_caller]
+    pub fn try_recycle(&self) -> Option<SyncRecycleGuard<'_, R>> {
+        let SyncRecycleCellGuard { inner, release } = self.try_acquire().ok()?;
+        Some(SyncRecycleGuard {
+            guard: inner.recycle(),
+            _release: release,
+        })
+    }
+}
+
+impl<R: Default> Default for SyncRecycleCell<R> {
+    /// Creates a cell holding a default container.
+    fn default() -> Self {
+        Self::new(R::default())
+    }
+}
+
+impl<R> fmt::Debug for SyncRecycleCell<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncRecycleCell")
+            .field("held_at", &self.held_at())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Exclusive access to the container of a [`SyncRecycleCell`].
+///
+/// The cell becomes available again when the guard is dropped.
+pub struct SyncRecycleCellGuard<'cell, R> {
+    inner: &'cell mut R,
+    release: Release<'cell>,
+}
+
+impl<'cell, R> Deref for SyncRecycleCellGuard<'cell, R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl<'cell, R> DerefMut for SyncRecycleCellGuard<'cell, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner
+    }
+}
+
+impl<'cell, R: fmt::Debug> fmt::Debug for SyncRecycleCellGuard<'cell, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// A recycled container of a [`SyncRecycleCell`], created by
+/// [`try_recycle`](SyncRecycleCell::try_recycle).
+///
+/// The guard dereferences to the container's own guard. When it is dropped,
+/// the container is cleared and the cell becomes available again.
+pub struct SyncRecycleGuard<'cell, R: Recycle + 'cell> {
+    guard: R::Guard<'cell>,
+    // Declared after `guard`, so the cell is only released once the
+    // container has been cleared.
+    _release: Release<'cell>,
+}
+
+impl<'cell, R: Recycle> Deref for SyncRecycleGuard<'cell, R> {
+    type Target = R::Guard<'cell>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<'cell, R: Recycle> DerefMut for SyncRecycleGuard<'cell, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<'cell, R> fmt::Debug for SyncRecycleGuard<'cell, R>
+where
+    R: Recycle,
+    R::Guard<'cell>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.guard.fmt(f)
+    }
+}
+
+/// Clears the flag of a [`SyncRecycleCell`], and where it was set, when
+/// dropped.
+struct Release<'cell> {
+    held: &'cell AtomicBool,
+    held_at: &'cell Mutex<Option<&'static Location<'static>>>,
+}
+
+impl Drop for Release<'_> {
+    /// Releases the cell.
+    fn drop(&mut self) {
+        let mut held_at = lock(self.held_at);
+        *held_at = None;
+        self.held.store(false, Ordering::Release);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // The location is only ever replaced as a whole, so it is consistent even
+    // if a thread panicked while holding the lock.
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The error returned by [`RecycleCell::try_acquire`],
+/// [`SyncRecycleCell::try_acquire`], and [`LocalReusable::try_recycle`] while
+/// the container is held elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyRecycled {
+    location: &'static Location<'static>,
+}
+
+impl AlreadyRecycled {
+    /// Where the guard that still holds the container was acquired.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+impl fmt::Display for AlreadyRecycled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "already recycled at {}", self.location)
+    }
+}
+
+impl Error for AlreadyRecycled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReusableString;
+
+    #[test]
+    fn conflicts_report_the_holding_location() {
+        let cell = RecycleCell::new(ReusableString::default());
+        let line = line!() + 1;
+        let mut held = cell.acquire();
+        held.recycle().push_str("busy");
+
+        let err = cell.try_acquire().unwrap_err();
+        assert_eq!(err.location().file(), file!());
+        assert_eq!(err.location().line(), line);
+        assert_eq!(cell.held_at(), Some(err.location()));
+    }
+
+    #[test]
+    fn dropping_the_guard_releases_the_cell() {
+        let mut cell = RecycleCell::new(ReusableString::default());
+        {
+            let mut held = cell.acquire();
+            held.recycle().push_str("retained");
+        }
+        assert_eq!(cell.held_at(), None);
+        assert!(cell.acquire().recycle().capacity() >= 8);
+        assert!(cell.get_mut().recycle().is_empty());
+    }
+
+    #[test]
+    fn local_reusables_recycle_through_shared_references() {
+        let mut local = LocalReusable::<String>::default();
+        let line = line!() + 1;
+        let mut held = local.recycle();
+        held.push_str("scratch");
+
+        let err = local.try_recycle().unwrap_err();
+        assert_eq!(err.location().line(), line);
+        drop(held);
+
+        assert!(local.recycle().is_empty());
+        assert!(local.capacity() >= 7);
+    }
+
+    #[test]
+    fn sync_cells_hand_the_container_to_one_thread_at_a_time() {
+        let cell = SyncRecycleCell::new(ReusableString::default());
+        let acquired = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| match cell.try_acquire() {
+                        Ok(mut held) => {
+                            held.recycle().push_str("scratch");
+                            1
+                        }
+                        Err(_) => 0,
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum::<u32>()
+        });
+        assert!(acquired >= 1);
+        assert!(!cell.is_held());
+        assert!(cell.try_acquire().unwrap().recycle().capacity() >= 7);
+    }
+
+    #[test]
+    fn sync_cells_recycle_through_shared_references() {
+        let cell = SyncRecycleCell::new(ReusableString::default());
+        let line = line!() + 1;
+        let mut text = cell.try_recycle().unwrap();
+        text.push_str("scratch");
+        assert!(cell.is_held());
+        assert!(cell.try_recycle().is_none());
+
+        let err = cell.try_acquire().unwrap_err();
+        assert_eq!(err.location().file(), file!());
+        assert_eq!(err.location().line(), line);
+        assert_eq!(cell.held_at(), Some(err.location()));
+        drop(text);
+        assert_eq!(cell.held_at(), None);
+
+        let text = cell.try_recycle().unwrap();
+        assert!(text.is_empty());
+        assert!(text.capacity() >= 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "already recycled at src/cell.rs")]
+    fn acquire_panics_with_the_holding_location() {
+        let cell = RecycleCell::new(ReusableString::default());
+        let _held = cell.acquire();
+        let _again = cell.acquire();
+    }
+}
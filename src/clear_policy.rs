@@ -0,0 +1,116 @@
+//! When recycled collections are cleared.
+//!
+//! By default a guard clears its collection when it is dropped, so the work
+//! of dropping the elements, or of resetting a hash table's control bytes,
+//! lands at the end of whatever the guard was used for. A [`ClearPolicy`]
+//! can move that work to the next `recycle` call instead, or do it at both
+//! points.
+//!
+//! Elements without drop glue, like integers or `&str`s, are cleared by
+//! resetting a length, so for a `Vec` of them the drop-time clear is nearly
+//! free, while a hash table still resets a control byte per bucket.
+//! [`ClearPolicy::OnRecycleIfTrivial`] skips the drop-time clear for exactly
+//! those types, and keeps clearing on drop for everything else, where
+//! deferring would keep values alive for a whole cycle.
+//!
+//! [`ReusableVec`](crate::ReusableVec) and
+//! [`ReusableHashMap`](crate::ReusableHashMap) accept a policy through
+//! `set_clear_policy`.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{ClearPolicy, ReusableHashMap};
+//!
+//! let mut seen = ReusableHashMap::<u64, u64>::default();
+//! seen.set_clear_policy(ClearPolicy::OnRecycle);
+//!
+//! seen.recycle_same().extend((0..1000).map(|i| (i, i)));
+//! // The table was left as it was, and is only cleared here.
+//! assert!(seen.recycle_same().is_empty());
+//! ```
+
+/// When a recycled collection is cleared.
+///
+/// Leaving contents behind is only sound when nothing can observe them
+/// after their lifetimes end, so a guard that casts the element types only
+/// defers clearing if neither the stored nor the cast-to types need to be
+/// dropped. Guards that don't cast, such as those from `recycle_same`, can
+/// always defer. When a guard can't defer, it clears on drop as with
+/// [`OnDrop`](ClearPolicy::OnDrop).
+///
+/// With the `serde` feature, the policy deserializes from `"on_drop"`,
+/// `"on_recycle"`, `"on_recycle_if_trivial"` or `"both"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ClearPolicy {
+    /// Clear when the guard is dropped. This is the default.
+    #[default]
+    OnDrop,
+    /// Leave the contents in place when the guard is dropped, and clear them
+    /// at the start of the next `recycle`, or whenever the container is
+    /// otherwise used.
+    OnRecycle,
+    /// Like [`OnRecycle`](ClearPolicy::OnRecycle) for element types that
+    /// don't need to be dropped, as reported by
+    /// [`mem::needs_drop`](std::mem::needs_drop), and like
+    /// [`OnDrop`](ClearPolicy::OnDrop) for the others.
+    OnRecycleIfTrivial,
+    /// Clear when the guard is dropped, and again at the start of every
+    /// `recycle`.
+    ///
+    /// Since `recycle` always disposes of whatever a guard leaked with
+    /// [`mem::forget`](std::mem::forget) left behind, this behaves like
+    /// [`OnDrop`](ClearPolicy::OnDrop).
+    Both,
+}
+
+impl ClearPolicy {
+    /// Whether a guard that is allowed to defer clearing should do so,
+    /// given whether its elements are `trivial` to drop.
+    pub(crate) fn defers(self, trivial: bool) -> bool {
+        match self {
+            ClearPolicy::OnRecycle => true,
+            ClearPolicy::OnRecycleIfTrivial => trivial,
+            ClearPolicy::OnDrop | ClearPolicy::Both => false,
+        }
+    }
+}
+
+/// What a guard left in its container for the next `recycle` to deal with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Leftovers {
+    /// Nothing beyond what the container keeps on purpose.
+    None,
+    /// Elements that can be dropped as the stored types: a guard deferred
+    /// clearing, or one that couldn't cast was leaked.
+    Droppable,
+    /// Elements from a leaked guard that cast the stored types. They may
+    /// borrow data that is gone, so they are leaked rather than dropped.
+    Leaked,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReusableVec;
+    use std::rc::Rc;
+
+    #[test]
+    fn deferred_elements_are_dropped_on_the_next_recycle() {
+        let shared = Rc::new(());
+        let mut vec = ReusableVec::<Rc<()>>::default();
+        vec.set_clear_policy(ClearPolicy::OnRecycle);
+
+        vec.recycle_same().push(Rc::clone(&shared));
+        assert_eq!(Rc::strong_count(&shared), 2);
+        assert!(vec.is_empty());
+
+        assert!(vec.recycle_same().is_empty());
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! A lock-free pool of recycled containers, backed by
+//! [crossbeam-queue](https://docs.rs/crossbeam-queue).
+//!
+//! [`ReusablePool`](crate::ReusablePool) keeps its free containers behind a
+//! mutex, which every checkout and every return has to take. On a server
+//! handing scratch buffers to thousands of requests a second across all of
+//! its cores, that lock is where the threads meet. A [`ConcurrentPool`] keeps
+//! them in a bounded [`ArrayQueue`] instead, so checking a container in or
+//! out is a single atomic operation, and the pool never allocates after it is
+//! created.
+//!
+//! The pool is a cheap handle to the shared queue: clone it into each task,
+//! and each guard holds a clone of its own, so guards can cross `.await`
+//! points and threads freely.
+//!
+//! This module is only available with the `crossbeam-queue` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::{collections::HashMap, thread};
+//! use triple_r::crossbeam_queue::ConcurrentPool;
+//!
+//! let pool = ConcurrentPool::<HashMap<u64, u64>>::new(8);
+//!
+//! let workers: Vec<_> = (0..4)
+//!     .map(|i| {
+//!         let pool = pool.clone();
+//!         thread::spawn(move || {
+//!             for _ in 0..10 {
+//!                 let mut scratch = pool.get();
+//!                 scratch.extend((0..100).map(|n| (n, n * i)));
+//!                 assert_eq!(scratch.len(), 100);
+//!             }
+//!         })
+//!     })
+//!     .collect();
+//!
+//! for worker in workers {
+//!     worker.join().unwrap();
+//! }
+//! // Every map that was ever created is back in the pool, empty.
+//! assert!((1..=4).contains(&pool.available()));
+//! assert!(pool.get().is_empty());
+//! ```
+use crate::Recyclable;
+use crossbeam_queue::ArrayQueue;
+use std::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::Arc,
+};
+
+/// A lock-free, bounded pool of recycled [`Recyclable`] containers.
+///
+/// Cloning a `ConcurrentPool` shares the same containers. At most
+/// [`max_idle`](ConcurrentPool::max_idle) containers wait in the pool;
+/// containers handed back to a full pool are dropped, and a checkout from an
+/// empty pool creates a new one with [`Default`].
+pub struct ConcurrentPool<T> {
+    free: Arc<ArrayQueue<T>>,
+}
+
+impl<T> Clone for ConcurrentPool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            free: Arc::clone(&self.free),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ConcurrentPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrentPool")
+            .field("available", &self.available())
+            .field("max_idle", &self.max_idle())
+            .finish()
+    }
+}
+
+impl<T> ConcurrentPool<T> {
+    /// Creates a new, empty pool that keeps at most `max_idle` containers
+    /// waiting.
+    ///
+    /// The queue holding them is allocated up front.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_idle` is zero.
+    pub fn new(max_idle: usize) -> Self {
+        assert!(
+            max_idle > 0,
+            "a concurrent pool must hold at least one container"
+        );
+        Self {
+            free: Arc::new(ArrayQueue::new(max_idle)),
+        }
+    }
+
+    /// Takes a container out of the pool, returning a guard that puts it back
+    /// when dropped.
+    ///
+    /// If the pool has no container available, a new one is created with
+    /// [`Default`].
+    pub fn get(&self) -> ConcurrentPoolGuard<T>
+    where
+        T: Recyclable + Default,
+    {
+        let object = self.free.pop().unwrap_or_default();
+        ConcurrentPoolGuard {
+            object: ManuallyDrop::new(object),
+            pool: self.clone(),
+        }
+    }
+
+    /// Clears `object` and adds it to the pool, unless the pool is full.
+    ///
+    /// `object` does not need to have come from this pool.
+    pub fn put(&self, mut object: T)
+    where
+        T: Recyclable,
+    {
+        object.clear();
+        // A full pool hands the container back, and it is dropped here.
+        let _ = self.free.push(object);
+    }
+
+    /// The number of containers currently waiting in the pool.
+    ///
+    /// Other threads may check containers in and out at any time, so this is
+    /// only a snapshot.
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+
+    /// The most containers the pool keeps waiting.
+    pub fn max_idle(&self) -> usize {
+        self.free.capacity()
+    }
+}
+
+/// An owned guard over a container taken from a [`ConcurrentPool`].
+///
+/// When the guard is dropped, the container is cleared and returned to the
+/// pool, preserving its allocation for the next user.
+pub struct ConcurrentPoolGuard<T: Recyclable> {
+    object: ManuallyDrop<T>,
+    pool: ConcurrentPool<T>,
+}
+
+impl<T: Recyclable> ConcurrentPoolGuard<T> {
+    /// Detaches the container from the pool, so it is not returned when the
+    /// guard would have been dropped.
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so the container and
+        // the pool handle are each moved out exactly once.
+        unsafe {
+            drop(ptr::read(&this.pool));
+            ManuallyDrop::take(&mut this.object)
+        }
+    }
+}
+
+impl<T: Recyclable> Deref for ConcurrentPoolGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.object
+    }
+}
+
+impl<T: Recyclable> DerefMut for ConcurrentPoolGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.object
+    }
+}
+
+impl<T: Recyclable + fmt::Debug> fmt::Debug for ConcurrentPoolGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.object, f)
+    }
+}
+
+impl<T: Recyclable> Drop for ConcurrentPoolGuard<T> {
+    /// Clears the container and returns it to the pool.
+    fn drop(&mut self) {
+        // SAFETY: The container is only taken here, and the guard is gone
+        // afterwards.
+        let object = unsafe { ManuallyDrop::take(&mut self.object) };
+        self.pool.put(object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_full_pool_drops_returned_containers() {
+        let pool = ConcurrentPool::<Vec<u32>>::new(1);
+        let mut first = pool.get();
+        let mut second = pool.clone().get();
+        first.extend(0..10);
+        second.push(1);
+        drop(first);
+        drop(second);
+
+        // Only the first one was kept, empty, with its buffer.
+        assert_eq!(pool.available(), 1);
+        let reused = pool.get();
+        assert!(reused.is_empty());
+        assert!(reused.capacity() >= 10);
+
+        let detached = reused.into_inner();
+        assert_eq!(pool.available(), 0);
+        assert!(detached.capacity() >= 10);
+    }
+}
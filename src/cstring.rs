@@ -0,0 +1,231 @@
+use crate::MemoryFootprint;
+use std::{error::Error, ffi::CStr, fmt, os::raw::c_char};
+
+/// A reusable buffer for building NUL-terminated C strings.
+///
+/// Code that calls into C often builds a fresh `CString` for every call,
+/// which allocates every time. `ReusableCStringBuf` keeps one byte buffer
+/// instead: [`recycle`](ReusableCStringBuf::recycle) returns a
+/// [`ReusableCStringBufGuard`] to append content through, and the guard can
+/// hand out a `&CStr` at any point without copying, because the buffer always
+/// ends with a NUL byte. When the guard is dropped, the buffer is emptied but
+/// keeps its allocation.
+///
+/// Content containing a NUL byte is rejected with an [`InteriorNulError`], so
+/// the `&CStr` always covers everything that was appended.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Write;
+/// use triple_r::ReusableCStringBuf;
+///
+/// let mut path = ReusableCStringBuf::default();
+///
+/// for id in 0..3 {
+///     let mut c_path = path.recycle();
+///     write!(c_path, "/tmp/cache-{}", id).unwrap();
+///     assert_eq!(c_path.as_c_str().to_str().unwrap(), format!("/tmp/cache-{}", id));
+///     // `c_path.as_ptr()` can be passed to a C function expecting a
+///     // `const char *`.
+/// }
+///
+/// let mut c_path = path.recycle();
+/// assert!(c_path.push_str("bad\0path").is_err());
+/// assert!(c_path.as_c_str().to_bytes().is_empty());
+/// ```
+#[derive(Default)]
+pub struct ReusableCStringBuf {
+    bytes: Vec<u8>,
+}
+
+impl fmt::Debug for ReusableCStringBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableCStringBuf")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl ReusableCStringBuf {
+    /// Returns a guard holding an empty C string.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    pub fn recycle(&mut self) -> ReusableCStringBufGuard<'_> {
+        self.bytes.clear();
+        self.bytes.push(0);
+        ReusableCStringBufGuard {
+            bytes: &mut self.bytes,
+        }
+    }
+
+    /// Recycles the buffer for the duration of `f`, clearing it when `f`
+    /// returns, so the guard can't be held past the closure.
+    pub fn recycle_with<R>(&mut self, f: impl FnOnce(&mut ReusableCStringBufGuard<'_>) -> R) -> R {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained byte buffer, which is always empty.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        // A guard that was leaked instead of dropped left its bytes behind.
+        self.bytes.clear();
+        self.bytes
+    }
+
+    /// The number of bytes the retained buffer can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// The estimated number of heap bytes the retained buffer holds on to.
+    pub fn memory_footprint(&self) -> usize {
+        self.bytes.memory_footprint()
+    }
+}
+
+/// A RAII guard over the buffer of a [`ReusableCStringBuf`].
+///
+/// The buffer always holds the appended content followed by a single NUL
+/// byte. When the guard is dropped, the buffer is cleared, preserving its
+/// allocation.
+pub struct ReusableCStringBufGuard<'parent> {
+    bytes: &'parent mut Vec<u8>,
+}
+
+impl<'parent> ReusableCStringBufGuard<'parent> {
+    /// The estimated number of heap bytes the buffer holds on to, counting
+    /// its unused capacity.
+    pub fn memory_footprint(&self) -> usize {
+        self.bytes.memory_footprint()
+    }
+
+    /// Appends `bytes`, which must not contain a NUL byte.
+    ///
+    /// On error, nothing is appended.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), InteriorNulError> {
+        if let Some(position) = bytes.iter().position(|&byte| byte == 0) {
+            return Err(InteriorNulError { position });
+        }
+        self.bytes.pop();
+        self.bytes.extend_from_slice(bytes);
+        self.bytes.push(0);
+        Ok(())
+    }
+
+    /// Appends a string slice, which must not contain a NUL character.
+    ///
+    /// On error, nothing is appended.
+    pub fn push_str(&mut self, s: &str) -> Result<(), InteriorNulError> {
+        self.push_bytes(s.as_bytes())
+    }
+
+    /// Returns the content as a `&CStr`.
+    pub fn as_c_str(&self) -> &CStr {
+        // SAFETY: The buffer always ends with exactly one NUL byte, and
+        // `push_bytes` rejects content containing any other.
+        unsafe { CStr::from_bytes_with_nul_unchecked(self.bytes) }
+    }
+
+    /// Returns a pointer to the NUL-terminated content, valid until the guard
+    /// is modified or dropped.
+    pub fn as_ptr(&self) -> *const c_char {
+        self.bytes.as_ptr() as *const c_char
+    }
+
+    /// The number of bytes appended so far, not counting the NUL terminator.
+    pub fn len(&self) -> usize {
+        self.bytes.len() - 1
+    }
+
+    /// Returns `true` if nothing has been appended.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the content, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.bytes.push(0);
+    }
+}
+
+impl<'parent> fmt::Write for ReusableCStringBufGuard<'parent> {
+    /// Appends `s`, failing if it contains a NUL character.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl<'parent> fmt::Debug for ReusableCStringBufGuard<'parent> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl<'parent> Drop for ReusableCStringBufGuard<'parent> {
+    /// Clears the buffer, preserving its allocation.
+    fn drop(&mut self) {
+        self.bytes.clear();
+    }
+}
+
+/// The error returned when appending content that contains a NUL byte to a
+/// [`ReusableCStringBufGuard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteriorNulError {
+    position: usize,
+}
+
+impl InteriorNulError {
+    /// The position of the first NUL byte within the rejected content.
+    pub fn nul_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for InteriorNulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nul byte found at position {}", self.position)
+    }
+}
+
+impl Error for InteriorNulError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_is_nul_terminated_and_reused() {
+        let mut buf = ReusableCStringBuf::default();
+        let ptr = {
+            let mut guard = buf.recycle();
+            guard.push_str("hello").unwrap();
+            guard.push_bytes(b", world").unwrap();
+            assert_eq!(guard.as_c_str().to_bytes(), b"hello, world");
+            assert_eq!(guard.len(), 12);
+            assert_eq!(unsafe { CStr::from_ptr(guard.as_ptr()) }, guard.as_c_str());
+            guard.as_ptr()
+        };
+
+        let mut guard = buf.recycle();
+        assert!(guard.is_empty());
+        guard.push_str("again").unwrap();
+        assert_eq!(guard.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn interior_nuls_are_rejected() {
+        let mut buf = ReusableCStringBuf::default();
+        let mut guard = buf.recycle();
+        guard.push_str("ok").unwrap();
+        let err = guard.push_bytes(b"a\0b").unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+        assert_eq!(guard.as_c_str().to_bytes(), b"ok");
+
+        guard.clear();
+        assert_eq!(guard.as_c_str().to_bytes(), b"");
+    }
+}
@@ -0,0 +1,130 @@
+//! Dropping recycled elements on a background thread.
+//!
+//! Clearing a guard drops every element it holds, and for a `Vec<String>` of
+//! a few million entries that is millions of calls to `free` on whichever
+//! thread drops the guard. A [`DropThread`] moves that work elsewhere: once
+//! it is installed with
+//! [`ReusableVec::set_drop_thread`](crate::ReusableVec::set_drop_thread), a
+//! guard that would have to drop elements ships the whole `Vec`, buffer and
+//! all, to the background thread instead, and leaves a fresh allocation of
+//! the same capacity behind. The
+//! [maximum retained capacity](crate::ReusableVec::set_max_retained_capacity)
+//! and the [adaptive capacity policy](crate::ReusableVec::set_adaptive_capacity)
+//! still decide how much of it is kept.
+//!
+//! The dropping thread only pays off when dropping the elements costs more
+//! than one allocation, so guards whose elements need no dropping clear in
+//! place as usual, as do guards under [`ClearPolicy::OnRecycle`] and, with
+//! the `zeroize` feature, `DropPolicy::Zeroize`.
+//!
+//! This module is only available with the `defer-drop` feature.
+//!
+//! [`ClearPolicy::OnRecycle`]: crate::ClearPolicy::OnRecycle
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{defer_drop::DropThread, ReusableVec};
+//!
+//! let drops = DropThread::spawn().expect("failed to spawn the drop thread");
+//! let mut names = ReusableVec::<String>::default();
+//! names.set_drop_thread(Some(drops));
+//!
+//! names.recycle_same().extend((0..10_000).map(|i| i.to_string()));
+//! // The strings are freed in the background, and the capacity is kept.
+//! assert!(names.capacity() >= 10_000);
+//! ```
+use std::{
+    fmt, io,
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+/// A handle to a background thread that drops whatever it is sent.
+///
+/// Cloning the handle shares the thread, so one thread can serve any number
+/// of containers. The thread exits once every handle, and every container
+/// it was installed in, is gone.
+#[derive(Clone)]
+pub struct DropThread {
+    sender: Sender<Box<dyn Send>>,
+}
+
+impl DropThread {
+    /// Spawns the dropping thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from [`thread::Builder::spawn`] if the thread can't
+    /// be created.
+    pub fn spawn() -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Box<dyn Send>>();
+        thread::Builder::new()
+            .name("triple-r-drop".to_owned())
+            .spawn(move || receiver.into_iter().for_each(drop))?;
+        Ok(Self { sender })
+    }
+
+    /// Drops `value` on the background thread.
+    ///
+    /// If the thread is gone, because an earlier destructor panicked on it,
+    /// `value` is dropped on the calling thread instead.
+    pub fn drop_later<T: Send + 'static>(&self, value: T) {
+        // A failed send hands the value back, and it is dropped right here.
+        let _ = self.sender.send(Box::new(value));
+    }
+}
+
+impl fmt::Debug for DropThread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropThread").finish_non_exhaustive()
+    }
+}
+
+/// A [`DropThread`] installed in a container, together with a way to send it
+/// the container's contents that is only built where they are `Send`.
+pub(crate) struct DropOffload<T> {
+    thread: DropThread,
+    send: fn(&DropThread, T),
+}
+
+impl<T: Send + 'static> DropOffload<T> {
+    pub(crate) fn new(thread: DropThread) -> Self {
+        Self {
+            thread,
+            send: |thread, value| thread.drop_later(value),
+        }
+    }
+}
+
+impl<T> DropOffload<T> {
+    pub(crate) fn send(&self, value: T) {
+        (self.send)(&self.thread, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReusableVec;
+
+    /// Reports the thread it is dropped on.
+    struct Notify(Sender<thread::ThreadId>);
+
+    impl Drop for Notify {
+        fn drop(&mut self) {
+            let _ = self.0.send(thread::current().id());
+        }
+    }
+
+    #[test]
+    fn guards_drop_their_elements_in_the_background() {
+        let (sender, receiver) = mpsc::channel();
+        let mut vec = ReusableVec::<Notify>::default();
+        vec.set_drop_thread(Some(DropThread::spawn().unwrap()));
+
+        vec.recycle_same().push(Notify(sender));
+        assert_ne!(receiver.recv().unwrap(), thread::current().id());
+        assert!(vec.capacity() >= 1);
+    }
+}
@@ -0,0 +1,67 @@
+//! Keeping recyclers alive across R calls with [extendr](https://extendr.github.io).
+//!
+//! An R package that calls into Rust for every chunk of data usually creates
+//! its scratch buffers inside each `#[extendr]` function, so their
+//! allocations are thrown away when the call returns. Storing a recycler in an
+//! R external pointer instead lets it live on the R side, for example in a
+//! package-level environment, and be handed back to Rust on every call.
+//!
+//! [`new_recycler`] creates such an external pointer and [`with_recycler`]
+//! borrows the recycler it holds. The recycler is dropped by R's garbage
+//! collector once the external pointer is no longer referenced.
+//!
+//! This module is only available with the `extendr` feature, which requires
+//! an R installation to build.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use extendr_api::prelude::*;
+//! use triple_r::{
+//!     extendr::{new_recycler, with_recycler},
+//!     ReusableVec,
+//! };
+//!
+//! /// Creates the scratch space, to be stored on the R side.
+//! #[extendr]
+//! fn scratch_new() -> Robj {
+//!     new_recycler::<ReusableVec<f64>>()
+//! }
+//!
+//! /// Sums the squares of `x`, reusing the scratch space between calls.
+//! #[extendr]
+//! fn sum_of_squares(scratch: Robj, x: &[f64]) -> Result<f64> {
+//!     with_recycler(scratch, |scratch: &mut ReusableVec<f64>| {
+//!         let mut squares = scratch.recycle();
+//!         squares.extend(x.iter().map(|x| x * x));
+//!         squares.iter().sum()
+//!     })
+//! }
+//! ```
+use extendr_api::{ExternalPtr, Result, Robj};
+use std::{any::Any, fmt::Debug};
+
+/// Creates a default recycler of type `T` and wraps it in an R external
+/// pointer.
+///
+/// The returned object can be stored anywhere on the R side and passed back
+/// to [`with_recycler`] in later calls.
+pub fn new_recycler<T>() -> Robj
+where
+    T: Any + Debug + Default,
+{
+    ExternalPtr::new(T::default()).into()
+}
+
+/// Runs `f` with the recycler of type `T` held by the external pointer
+/// `robj`.
+///
+/// Returns an error if `robj` is not an external pointer created for a `T`,
+/// for example by [`new_recycler`].
+pub fn with_recycler<T, R>(robj: Robj, f: impl FnOnce(&mut T) -> R) -> Result<R>
+where
+    T: Any + Debug,
+{
+    let mut recycler = ExternalPtr::<T>::try_from(robj)?;
+    Ok(f(&mut recycler))
+}
@@ -0,0 +1,459 @@
+//! A C interface to a shared pool of byte buffers, and to single reusable
+//! byte buffers.
+//!
+//! In a codebase that mixes Rust with C or C++, the foreign side often needs
+//! scratch buffers too. The functions in this module let it check buffers out
+//! of the same [`VecPool<u8>`] that the Rust side uses, instead of keeping a
+//! separate set of allocations.
+//!
+//! A pool is referred to through an opaque [`TripleRPool`] handle. A buffer is
+//! passed to C as a [`TripleRBuffer`]: a pointer, the number of initialized
+//! bytes, and the capacity. C may write up to `cap` bytes into it, but must
+//! not free or reallocate it. Every buffer has to be handed back with
+//! [`triple_r_pool_return`]. Buffers are not tied to the handle they came
+//! from, so they can be returned to any pool.
+//!
+//! A host that only needs one scratch buffer per call site, across many
+//! calls, can hold a [`TripleRBuf`] instead. It owns a [`ReusableVec<u8>`]
+//! and its current contents. [`triple_r_buf_recycle`] clears the contents
+//! between calls, keeping the allocation within the capacity policies the
+//! `ReusableVec` was configured with, and the other `triple_r_buf_*`
+//! functions read, grow, and fill it in place.
+//!
+//! The C declarations are:
+//!
+//! ```c
+//! typedef struct TripleRPool TripleRPool;
+//!
+//! typedef struct {
+//!     uint8_t *ptr;
+//!     size_t len;
+//!     size_t cap;
+//! } TripleRBuffer;
+//!
+//! TripleRPool *triple_r_pool_new(void);
+//! TripleRPool *triple_r_pool_clone(const TripleRPool *pool);
+//! void triple_r_pool_free(TripleRPool *pool);
+//! TripleRBuffer triple_r_pool_checkout(const TripleRPool *pool, size_t min_capacity);
+//! void triple_r_pool_return(const TripleRPool *pool, TripleRBuffer buffer);
+//! size_t triple_r_pool_available(const TripleRPool *pool);
+//!
+//! typedef struct TripleRBuf TripleRBuf;
+//!
+//! TripleRBuf *triple_r_buf_new(void);
+//! void triple_r_buf_recycle(TripleRBuf *buf);
+//! uint8_t *triple_r_buf_data(TripleRBuf *buf);
+//! size_t triple_r_buf_len(const TripleRBuf *buf);
+//! size_t triple_r_buf_capacity(const TripleRBuf *buf);
+//! bool triple_r_buf_reserve(TripleRBuf *buf, size_t additional);
+//! void triple_r_buf_set_len(TripleRBuf *buf, size_t len);
+//! void triple_r_buf_free(TripleRBuf *buf);
+//! ```
+//!
+//! This module is only available with the `ffi` feature.
+//!
+//! # Examples
+//!
+//! Sharing a pool that Rust code already uses with a C library:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use triple_r::{
+//!     ffi::{triple_r_pool_free, TripleRPool},
+//!     VecPool,
+//! };
+//!
+//! let pool = Arc::new(VecPool::<u8>::new());
+//! let handle = TripleRPool::new_handle(Arc::clone(&pool));
+//!
+//! // ... pass `handle` to C ...
+//!
+//! // SAFETY: `handle` was created by `new_handle` and is not used again.
+//! unsafe { triple_r_pool_free(handle) };
+//! ```
+use crate::{ReusableVec, VecPool};
+use std::{
+    mem::{self, ManuallyDrop},
+    panic::{self, AssertUnwindSafe},
+    process, ptr,
+    sync::Arc,
+};
+
+/// An opaque handle to a shared [`VecPool<u8>`].
+///
+/// Handles are reference counted: the pool is only dropped once every handle
+/// has been freed and the Rust side has dropped its own references.
+#[derive(Debug)]
+pub struct TripleRPool {
+    pool: Arc<VecPool<u8>>,
+}
+
+impl TripleRPool {
+    /// Creates a handle to `pool` that can be passed to C.
+    ///
+    /// The handle must eventually be released with [`triple_r_pool_free`].
+    pub fn new_handle(pool: Arc<VecPool<u8>>) -> *mut TripleRPool {
+        Box::into_raw(Box::new(TripleRPool { pool }))
+    }
+
+    /// Returns the pool behind a handle.
+    pub fn pool(&self) -> &Arc<VecPool<u8>> {
+        &self.pool
+    }
+}
+
+/// A byte buffer checked out of a pool, as seen from C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TripleRBuffer {
+    /// The start of the buffer. Null if the checkout failed.
+    pub ptr: *mut u8,
+    /// The number of initialized bytes, which is always zero on checkout.
+    pub len: usize,
+    /// The number of bytes that may be written to `ptr`.
+    pub cap: usize,
+}
+
+/// Creates a new, empty pool and returns a handle to it.
+#[no_mangle]
+pub extern "C" fn triple_r_pool_new() -> *mut TripleRPool {
+    TripleRPool::new_handle(Arc::new(VecPool::new()))
+}
+
+/// Returns a new handle to the same pool as `pool`.
+///
+/// # Safety
+///
+/// `pool` must be a live handle returned by one of the functions in this
+/// module.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_pool_clone(pool: *const TripleRPool) -> *mut TripleRPool {
+    TripleRPool::new_handle(Arc::clone(&(*pool).pool))
+}
+
+/// Releases a handle. Does nothing if `pool` is null.
+///
+/// # Safety
+///
+/// `pool` must be null or a live handle returned by one of the functions in
+/// this module, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_pool_free(pool: *mut TripleRPool) {
+    if !pool.is_null() {
+        drop(Box::from_raw(pool));
+    }
+}
+
+/// Checks out an empty buffer with room for at least `min_capacity` bytes.
+///
+/// Returns a buffer with a null `ptr` if `min_capacity` bytes can't be
+/// allocated.
+///
+/// # Safety
+///
+/// `pool` must be a live handle returned by one of the functions in this
+/// module.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_pool_checkout(
+    pool: *const TripleRPool,
+    min_capacity: usize,
+) -> TripleRBuffer {
+    let mut vec = (*pool).pool.take();
+    // Panicking on a failed allocation would unwind into C.
+    if vec.try_reserve(min_capacity).is_err() {
+        (*pool).pool.put(vec);
+        return TripleRBuffer {
+            ptr: ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        };
+    }
+    let mut vec = ManuallyDrop::new(vec);
+    TripleRBuffer {
+        ptr: vec.as_mut_ptr(),
+        len: vec.len(),
+        cap: vec.capacity(),
+    }
+}
+
+/// Returns a buffer to the pool, making its allocation available to the next
+/// checkout from either side.
+///
+/// Does nothing if `buffer.ptr` is null.
+///
+/// # Safety
+///
+/// `pool` must be a live handle returned by one of the functions in this
+/// module. `buffer` must have been returned by [`triple_r_pool_checkout`]
+/// with its `ptr` and `cap` unchanged, must not have been returned before, and
+/// its `len` must not exceed `cap`.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_pool_return(pool: *const TripleRPool, buffer: TripleRBuffer) {
+    if buffer.ptr.is_null() {
+        return;
+    }
+    debug_assert!(buffer.len <= buffer.cap);
+    // The contents are discarded anyway, so the `Vec` is rebuilt empty rather
+    // than trusting `len`.
+    let vec = Vec::from_raw_parts(buffer.ptr, 0, buffer.cap);
+    (*pool).pool.put(vec);
+}
+
+/// The number of buffers currently waiting in the pool.
+///
+/// # Safety
+///
+/// `pool` must be a live handle returned by one of the functions in this
+/// module.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_pool_available(pool: *const TripleRPool) -> usize {
+    (*pool).pool.available()
+}
+
+/// An opaque handle to a reusable byte buffer and its current contents.
+#[derive(Debug)]
+pub struct TripleRBuf {
+    reusable: ReusableVec<u8>,
+    contents: Vec<u8>,
+}
+
+impl TripleRBuf {
+    /// Creates a handle to a buffer that can be passed to C, taking over the
+    /// allocation and the capacity policies of `reusable`.
+    ///
+    /// The handle must eventually be released with [`triple_r_buf_free`].
+    pub fn new_handle(mut reusable: ReusableVec<u8>) -> *mut TripleRBuf {
+        let mut contents = Vec::new();
+        reusable.swap(&mut contents);
+        Box::into_raw(Box::new(TripleRBuf { reusable, contents }))
+    }
+
+    /// The current contents of the buffer.
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    fn recycle(&mut self) {
+        // The contents go through a guard, so they are cleared the way any
+        // other cycle of the `ReusableVec` is, and then the allocation comes
+        // back out for the next call.
+        let mut guard = self.reusable.recycle_same();
+        mem::swap(&mut *guard, &mut self.contents);
+        drop(guard);
+        self.reusable.swap(&mut self.contents);
+    }
+}
+
+/// Creates a new, empty buffer and returns a handle to it.
+#[no_mangle]
+pub extern "C" fn triple_r_buf_new() -> *mut TripleRBuf {
+    TripleRBuf::new_handle(ReusableVec::default())
+}
+
+/// Clears the contents of the buffer, keeping its allocation for the next
+/// call.
+///
+/// This runs the capacity policies and hooks of the `ReusableVec`. If any of
+/// them panics, the process is aborted, since unwinding into C is undefined
+/// behavior.
+///
+/// # Safety
+///
+/// `buf` must be a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`].
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_recycle(buf: *mut TripleRBuf) {
+    let buf = &mut *buf;
+    if panic::catch_unwind(AssertUnwindSafe(|| buf.recycle())).is_err() {
+        process::abort();
+    }
+}
+
+/// Returns the start of the buffer, which is never null.
+///
+/// The pointer is valid for `triple_r_buf_capacity(buf)` bytes, until the
+/// next call to [`triple_r_buf_reserve`], [`triple_r_buf_recycle`], or
+/// [`triple_r_buf_free`].
+///
+/// # Safety
+///
+/// `buf` must be a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`].
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_data(buf: *mut TripleRBuf) -> *mut u8 {
+    (*buf).contents.as_mut_ptr()
+}
+
+/// The number of bytes the buffer holds.
+///
+/// # Safety
+///
+/// `buf` must be a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`].
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_len(buf: *const TripleRBuf) -> usize {
+    (*buf).contents.len()
+}
+
+/// The number of bytes that may be written to `triple_r_buf_data(buf)`.
+///
+/// # Safety
+///
+/// `buf` must be a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`].
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_capacity(buf: *const TripleRBuf) -> usize {
+    (*buf).contents.capacity()
+}
+
+/// Grows the buffer to hold at least `additional` bytes more than its
+/// length, which may move it.
+///
+/// Returns `false`, leaving the buffer as it was, if the new capacity
+/// overflows or can't be allocated.
+///
+/// # Safety
+///
+/// `buf` must be a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`].
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_reserve(buf: *mut TripleRBuf, additional: usize) -> bool {
+    (*buf).contents.try_reserve(additional).is_ok()
+}
+
+/// Sets the number of bytes the buffer holds, after C wrote them.
+///
+/// # Safety
+///
+/// `buf` must be a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`]. `len` must not exceed
+/// `triple_r_buf_capacity(buf)`, and the first `len` bytes of
+/// `triple_r_buf_data(buf)` must have been written.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_set_len(buf: *mut TripleRBuf, len: usize) {
+    debug_assert!(len <= (*buf).contents.capacity());
+    (*buf).contents.set_len(len);
+}
+
+/// Releases a buffer and its allocation. Does nothing if `buf` is null.
+///
+/// # Safety
+///
+/// `buf` must be null or a live handle returned by [`triple_r_buf_new`] or
+/// [`TripleRBuf::new_handle`], and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn triple_r_buf_free(buf: *mut TripleRBuf) {
+    if !buf.is_null() {
+        drop(Box::from_raw(buf));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_round_trip_through_the_c_interface() {
+        unsafe {
+            let pool = triple_r_pool_new();
+            let buffer = triple_r_pool_checkout(pool, 64);
+            assert!(!buffer.ptr.is_null());
+            assert_eq!(buffer.len, 0);
+            assert!(buffer.cap >= 64);
+
+            // Simulate C filling the buffer.
+            ptr::write_bytes(buffer.ptr, 7, 10);
+            triple_r_pool_return(pool, TripleRBuffer { len: 10, ..buffer });
+            assert_eq!(triple_r_pool_available(pool), 1);
+
+            let again = triple_r_pool_checkout(pool, 16);
+            assert_eq!(again.ptr, buffer.ptr);
+            assert_eq!(again.cap, buffer.cap);
+            triple_r_pool_return(pool, again);
+            triple_r_pool_free(pool);
+        }
+    }
+
+    #[test]
+    fn failed_checkouts_return_a_null_buffer() {
+        unsafe {
+            let pool = triple_r_pool_new();
+            let buffer = triple_r_pool_checkout(pool, usize::MAX);
+            assert!(buffer.ptr.is_null());
+            assert_eq!(buffer.cap, 0);
+            triple_r_pool_return(pool, buffer);
+            triple_r_pool_free(pool);
+        }
+    }
+
+    #[test]
+    fn handles_share_a_pool_with_rust() {
+        let pool = Arc::new(VecPool::<u8>::new());
+        pool.put(Vec::with_capacity(32));
+
+        let handle = TripleRPool::new_handle(Arc::clone(&pool));
+        unsafe {
+            let clone = triple_r_pool_clone(handle);
+            triple_r_pool_free(handle);
+
+            let buffer = triple_r_pool_checkout(clone, 0);
+            assert!(buffer.cap >= 32);
+            assert_eq!(pool.available(), 0);
+            triple_r_pool_return(clone, buffer);
+            assert_eq!(pool.available(), 1);
+            triple_r_pool_free(clone);
+        }
+        assert_eq!(Arc::strong_count(&pool), 1);
+    }
+
+    #[test]
+    fn buffers_keep_their_allocation_across_calls() {
+        let mut reusable = ReusableVec::<u8>::with_capacity(8);
+        reusable.set_max_retained_capacity(Some(256));
+        let buf = TripleRBuf::new_handle(reusable);
+        unsafe {
+            assert!(triple_r_buf_capacity(buf) >= 8);
+            assert!(triple_r_buf_reserve(buf, 64));
+            let data = triple_r_buf_data(buf);
+            // Simulate C filling the buffer.
+            ptr::write_bytes(data, 7, 10);
+            triple_r_buf_set_len(buf, 10);
+            assert_eq!((*buf).contents(), [7; 10]);
+
+            triple_r_buf_recycle(buf);
+            assert_eq!(triple_r_buf_len(buf), 0);
+            assert_eq!(triple_r_buf_data(buf), data);
+            assert!(triple_r_buf_capacity(buf) >= 64);
+
+            // The policies of the `ReusableVec` still apply.
+            assert!(triple_r_buf_reserve(buf, 4096));
+            triple_r_buf_recycle(buf);
+            assert!(triple_r_buf_capacity(buf) <= 256);
+
+            // A capacity that can't be allocated is reported, not a panic.
+            let data = triple_r_buf_data(buf);
+            assert!(!triple_r_buf_reserve(buf, usize::MAX));
+            assert_eq!(triple_r_buf_data(buf), data);
+            triple_r_buf_free(buf);
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_ignored() {
+        unsafe {
+            triple_r_pool_free(ptr::null_mut());
+            triple_r_buf_free(ptr::null_mut());
+            let pool = triple_r_pool_new();
+            triple_r_pool_return(
+                pool,
+                TripleRBuffer {
+                    ptr: ptr::null_mut(),
+                    len: 0,
+                    cap: 0,
+                },
+            );
+            assert_eq!(triple_r_pool_available(pool), 0);
+            triple_r_pool_free(pool);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
+    mem,
+};
+
+/// An estimate of the heap memory a container holds on to.
+///
+/// Every reusable type in this crate has an inherent `memory_footprint`
+/// method, on the container and on its guard, built from this trait. The
+/// estimate is capacity times element size, plus the control bytes of hash
+/// tables; it ignores allocator overhead and anything the elements allocate
+/// themselves. Implementing it for a custom [`Recyclable`](crate::Recyclable)
+/// or [`Reset`](crate::Reset) type makes
+/// [`Reusable::memory_footprint`](crate::Reusable::memory_footprint) and
+/// [`ReusableNestedVec::memory_footprint`](crate::ReusableNestedVec::memory_footprint)
+/// available for it.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{MemoryFootprint, ReusableVec};
+///
+/// let samples = ReusableVec::<f64>::with_capacity(1024);
+/// assert_eq!(samples.memory_footprint(), 1024 * 8);
+///
+/// let scratch: Vec<u32> = Vec::with_capacity(16);
+/// assert_eq!(scratch.memory_footprint(), 16 * 4);
+/// ```
+pub trait MemoryFootprint {
+    /// The estimated number of heap bytes the value retains.
+    fn memory_footprint(&self) -> usize;
+}
+
+impl<T> MemoryFootprint for Vec<T> {
+    fn memory_footprint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl<T> MemoryFootprint for VecDeque<T> {
+    fn memory_footprint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl<T> MemoryFootprint for BinaryHeap<T> {
+    fn memory_footprint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl MemoryFootprint for String {
+    fn memory_footprint(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<K, V, S> MemoryFootprint for HashMap<K, V, S> {
+    fn memory_footprint(&self) -> usize {
+        table_footprint::<(K, V)>(self.capacity())
+    }
+}
+
+impl<T, S> MemoryFootprint for HashSet<T, S> {
+    fn memory_footprint(&self) -> usize {
+        table_footprint::<T>(self.capacity())
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K, V, S> MemoryFootprint for hashbrown::HashMap<K, V, S> {
+    fn memory_footprint(&self) -> usize {
+        table_footprint::<(K, V)>(self.capacity())
+    }
+}
+
+// B-trees free their nodes when cleared, so only the live entries count and
+// an empty tree retains nothing.
+impl<K, V> MemoryFootprint for BTreeMap<K, V> {
+    fn memory_footprint(&self) -> usize {
+        self.len() * mem::size_of::<(K, V)>()
+    }
+}
+
+impl<T> MemoryFootprint for BTreeSet<T> {
+    fn memory_footprint(&self) -> usize {
+        self.len() * mem::size_of::<T>()
+    }
+}
+
+/// The size of a SwissTable with room for `capacity` entries of type `T`.
+///
+/// The table keeps one bucket in eight free, and has one control byte per
+/// bucket plus a trailing group of them.
+pub(crate) fn table_footprint<T>(capacity: usize) -> usize {
+    const GROUP_WIDTH: usize = 16;
+
+    if capacity == 0 {
+        return 0;
+    }
+    let buckets = if capacity < 8 {
+        (capacity + 1).next_power_of_two()
+    } else {
+        (capacity / 7 * 8).next_power_of_two()
+    };
+    buckets * (mem::size_of::<T>() + 1) + GROUP_WIDTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_count_buckets_and_control_bytes() {
+        let map: HashMap<u64, u64> = HashMap::with_capacity(100);
+        let buckets = map.capacity() * 8 / 7;
+        assert!(map.memory_footprint() >= buckets * 17);
+        assert!(map.memory_footprint() <= 2 * buckets * 17 + 16);
+
+        assert_eq!(HashMap::<u64, u64>::new().memory_footprint(), 0);
+    }
+}
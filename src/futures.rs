@@ -0,0 +1,162 @@
+//! Chunking [`Stream`]s into pooled buffers, for
+//! [futures](https://docs.rs/futures).
+//!
+//! Batching a stream with `StreamExt::chunks` allocates a fresh `Vec` for
+//! every batch, which a streaming pipeline then drops as soon as the batch is
+//! written out. [`RecycledChunks::recycled_chunks`] yields every batch in a
+//! [`PooledVec`] from a [`VecPool`] instead. Once a batch is dropped, its
+//! buffer goes back to the pool and carries the next batch, so a pipeline
+//! that handles one batch at a time only ever allocates one buffer.
+//!
+//! This module is only available with the `futures` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use futures::{executor::block_on, stream, StreamExt};
+//! use triple_r::{futures::RecycledChunks, VecPool};
+//!
+//! let pool = VecPool::<u32>::new();
+//! let mut batches = stream::iter(0..10).recycled_chunks(4, &pool);
+//!
+//! block_on(async {
+//!     let mut lens = Vec::new();
+//!     while let Some(batch) = batches.next().await {
+//!         lens.push(batch.len());
+//!     } // Each batch returns its buffer to the pool here.
+//!     assert_eq!(lens, [4, 4, 2]);
+//! });
+//! assert_eq!(pool.available(), 1);
+//! ```
+use crate::{PooledVec, VecPool};
+use futures_core::Stream;
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Extends streams with
+/// [`recycled_chunks`](RecycledChunks::recycled_chunks).
+pub trait RecycledChunks: Stream {
+    /// Batches the items of the stream into buffers of `size` items taken
+    /// from `pool`.
+    ///
+    /// The last batch holds whatever is left when the stream ends, and is
+    /// only yielded if it is not empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    fn recycled_chunks(self, size: usize, pool: &VecPool<Self::Item>) -> Chunks<'_, Self>
+    where
+        Self: Sized,
+    {
+        assert!(size > 0, "chunks must hold at least one item");
+        Chunks {
+            stream: self,
+            size,
+            pool,
+            batch: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream> RecycledChunks for S {}
+
+/// A stream of batches in pooled buffers, created by
+/// [`RecycledChunks::recycled_chunks`].
+pub struct Chunks<'pool, S: Stream> {
+    stream: S,
+    size: usize,
+    pool: &'pool VecPool<S::Item>,
+    batch: Option<PooledVec<'pool, S::Item>>,
+    done: bool,
+}
+
+impl<'pool, S: Stream> Stream for Chunks<'pool, S> {
+    type Item = PooledVec<'pool, S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: `stream` is pinned structurally: it is never moved out of
+        // `Chunks`, which has no `Drop` impl and is only `Unpin` when `S` is.
+        // The other fields are never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            // SAFETY: See above.
+            let stream = unsafe { Pin::new_unchecked(&mut this.stream) };
+            match stream.poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    let size = this.size;
+                    let pool = this.pool;
+                    let batch = this.batch.get_or_insert_with(|| {
+                        let mut batch = pool.get();
+                        batch.reserve(size);
+                        batch
+                    });
+                    batch.push(item);
+                    if batch.len() == size {
+                        return Poll::Ready(this.batch.take());
+                    }
+                }
+                Poll::Ready(None) => {
+                    this.done = true;
+                    return Poll::Ready(this.batch.take());
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let buffered = self.batch.as_ref().map_or(0, |batch| batch.len());
+        let (lower, upper) = self.stream.size_hint();
+        let lower = lower.saturating_add(buffered);
+        let upper = upper.and_then(|upper| upper.checked_add(buffered));
+        let batches = |items: usize| items / self.size + usize::from(items % self.size != 0);
+        (batches(lower), upper.map(batches))
+    }
+}
+
+impl<S: Stream + fmt::Debug> fmt::Debug for Chunks<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chunks")
+            .field("stream", &self.stream)
+            .field("size", &self.size)
+            .field(
+                "buffered",
+                &self.batch.as_ref().map_or(0, |batch| batch.len()),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, stream, StreamExt};
+
+    #[test]
+    fn batches_reuse_the_same_buffer() {
+        let pool = VecPool::<String>::new();
+        let words = stream::iter(["a", "b", "c", "d", "e"].map(String::from));
+        let batches = words.recycled_chunks(2, &pool);
+        assert_eq!(batches.size_hint(), (3, Some(3)));
+
+        let ptrs = block_on(
+            batches
+                .map(|batch| batch.as_ptr() as usize)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(ptrs.len(), 3);
+        assert!(ptrs.iter().all(|&ptr| ptr == ptrs[0]));
+        assert_eq!(pool.available(), 1);
+    }
+}
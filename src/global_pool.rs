@@ -0,0 +1,182 @@
+use crate::{Recyclable, ReusablePool};
+use std::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+/// A process-wide pool of recycled [`Recyclable`] containers, usable as a
+/// `static`.
+///
+/// A library that needs scratch space in its hot paths would otherwise have
+/// to take a `&mut ReusableVec` in every public function, or allocate on
+/// every call. A `GlobalPool` is created in a `const` context, so it can live
+/// in a `static` and be used from anywhere. Nothing is allocated until the
+/// first container is returned to it, so an unused pool costs nothing.
+///
+/// [`get`](GlobalPool::get) returns a [`GlobalPoolGuard`] that puts the
+/// container back, cleared, when dropped. Like [`ReusablePool`], which it is
+/// built on, any number of guards can be out at once, from any thread.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::GlobalPool;
+///
+/// static SCRATCH: GlobalPool<Vec<u8>> = GlobalPool::new();
+///
+/// pub fn checksum(words: &[&str]) -> u32 {
+///     let mut bytes = SCRATCH.get();
+///     for word in words {
+///         bytes.extend_from_slice(word.as_bytes());
+///     }
+///     bytes.iter().map(|&b| u32::from(b)).sum()
+/// }
+///
+/// assert_eq!(checksum(&["a", "b"]), 195);
+/// // The buffer from the first call is reused by the second.
+/// assert_eq!(SCRATCH.available(), 1);
+/// assert_eq!(checksum(&["c"]), 99);
+/// ```
+pub struct GlobalPool<T> {
+    pool: ReusablePool<T>,
+}
+
+impl<T> Default for GlobalPool<T> {
+    /// Creates a new, empty `GlobalPool`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for GlobalPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlobalPool")
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+impl<T> GlobalPool<T> {
+    /// Creates a new, empty `GlobalPool`. Containers are created on demand
+    /// with [`Default`].
+    pub const fn new() -> Self {
+        Self::with_max_idle(usize::MAX)
+    }
+
+    /// Creates a pool that keeps at most `max_idle` containers waiting.
+    ///
+    /// Containers handed back to a full pool are dropped.
+    pub const fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            pool: ReusablePool::with_max_idle(max_idle),
+        }
+    }
+
+    /// Takes a container out of the pool, returning a guard that puts it back
+    /// when dropped.
+    ///
+    /// If the pool has no container available, a new one is created with
+    /// [`Default`].
+    pub fn get(&'static self) -> GlobalPoolGuard<T>
+    where
+        T: Recyclable + Default,
+    {
+        GlobalPoolGuard {
+            object: ManuallyDrop::new(self.pool.take()),
+            pool: self,
+        }
+    }
+
+    /// Clears `object` and adds it to the pool, unless the pool is full.
+    ///
+    /// `object` does not need to have come from this pool.
+    pub fn put(&self, object: T)
+    where
+        T: Recyclable,
+    {
+        self.pool.put(object);
+    }
+
+    /// The number of containers currently waiting in the pool.
+    pub fn available(&self) -> usize {
+        self.pool.available()
+    }
+}
+
+/// A guard over a container taken from a [`GlobalPool`].
+///
+/// When the guard is dropped, the container is cleared and returned to the
+/// pool, preserving its allocation for the next user.
+pub struct GlobalPoolGuard<T: Recyclable + 'static> {
+    object: ManuallyDrop<T>,
+    pool: &'static GlobalPool<T>,
+}
+
+impl<T: Recyclable> GlobalPoolGuard<T> {
+    /// Detaches the container from the pool, so it is not returned when the
+    /// guard would have been dropped.
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so the container is
+        // moved out exactly once.
+        unsafe { ManuallyDrop::take(&mut this.object) }
+    }
+}
+
+impl<T: Recyclable> Deref for GlobalPoolGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.object
+    }
+}
+
+impl<T: Recyclable> DerefMut for GlobalPoolGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.object
+    }
+}
+
+impl<T: Recyclable + fmt::Debug> fmt::Debug for GlobalPoolGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.object, f)
+    }
+}
+
+impl<T: Recyclable> Drop for GlobalPoolGuard<T> {
+    /// Clears the container and returns it to the pool.
+    fn drop(&mut self) {
+        // SAFETY: The container is only taken here, and the guard is gone
+        // afterwards.
+        let object = unsafe { ManuallyDrop::take(&mut self.object) };
+        self.pool.put(object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, thread};
+
+    static MAPS: GlobalPool<HashMap<u32, u32>> = GlobalPool::with_max_idle(2);
+
+    #[test]
+    fn a_static_pool_is_shared_across_threads() {
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                thread::spawn(move || {
+                    let mut map = MAPS.get();
+                    map.insert(i, i);
+                    map.len()
+                })
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), 1);
+        }
+
+        assert!((1..=2).contains(&MAPS.available()));
+        assert!(MAPS.get().is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+//! A reusable hash map backed by [hashbrown](https://docs.rs/hashbrown).
+//!
+//! [`ReusableHashMap`] here follows the same recycling pattern as
+//! [`crate::ReusableHashMap`], but its guards dereference to a
+//! `hashbrown::HashMap`, so APIs only `hashbrown` provides, like
+//! `entry_ref`, are available on them. It is a separate type rather than a
+//! replacement, so crates that enable this feature don't change the map that
+//! other crates in the same build get from `triple_r::ReusableHashMap`.
+//!
+//! This module is only available with the `hashbrown` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::hashbrown::ReusableHashMap;
+//!
+//! let mut counts = ReusableHashMap::<String, usize>::default();
+//!
+//! for line in ["a b a", "c c"] {
+//!     let mut words = counts.recycle::<String, usize>();
+//!     for word in line.split(' ') {
+//!         // `entry_ref` only allocates a `String` for new keys.
+//!         *words.entry_ref(word).or_insert(0) += 1;
+//!     }
+//!     assert_eq!(words.values().max(), Some(&2));
+//! }
+//! ```
+use crate::{poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto};
+use hashbrown::HashMap;
+use std::{
+    cell::UnsafeCell,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A wrapper around a `hashbrown` [`HashMap`] that allows for reusing its
+/// allocation across compatible key and value types.
+pub struct ReusableHashMap<K: 'static, V: 'static, S: 'static + BuildHasher + Default = RandomState>
+{
+    inner: UnsafeCell<HashMap<K, V, S>>,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableHashMap` is safe to send across threads if its contents are
+// `Send`.
+unsafe impl<K: Send, V: Send, S: 'static + Send + BuildHasher + Default> Send
+    for ReusableHashMap<K, V, S>
+{
+}
+
+// The `ReusableHashMap` is safe to share across threads if its contents are
+// `Send`, because `recycle` requires `&mut self`.
+unsafe impl<K: Send, V: Send, S: 'static + Send + BuildHasher + Default> Sync
+    for ReusableHashMap<K, V, S>
+{
+}
+
+impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> fmt::Debug
+    for ReusableHashMap<K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableHashMap")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> Default
+    for ReusableHashMap<K, V, S>
+{
+    /// Creates a new, empty `ReusableHashMap` with the default hasher.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(HashMap::default()),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> From<HashMap<K, V, S>>
+    for ReusableHashMap<K, V, S>
+{
+    /// Adopts the allocation of `map`, discarding its contents.
+    fn from(mut map: HashMap<K, V, S>) -> Self {
+        map.clear();
+        Self {
+            inner: UnsafeCell::new(map),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to a `hashbrown`
+/// `HashMap` retrieved from a [`ReusableHashMap`].
+///
+/// When the guard is dropped, it clears the `HashMap`, preserving its
+/// allocation for the next cycle.
+pub struct ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    inner: *mut HashMap<K2, V2, S>,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableHashMap<K1, V1, S>>,
+}
+
+impl<'parent, K1, V1, K2, V2, S> Deref for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    type Target = HashMap<K2, V2, S>;
+
+    /// Provides immutable access to the underlying `HashMap`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> DerefMut for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Provides mutable access to the underlying `HashMap`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> fmt::Debug for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: fmt::Debug,
+    V2: fmt::Debug,
+    S: 'static + BuildHasher + Default,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// The estimated number of heap bytes the `HashMap` holds on to,
+    /// counting its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+}
+
+impl<K1, V1, S> ReusableHashMap<K1, V1, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Creates a `ReusableHashMap` whose table can already hold `capacity`
+    /// entries, so the first cycles don't reallocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: UnsafeCell::new(HashMap::with_capacity_and_hasher(capacity, S::default())),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+
+    /// Reuses the `HashMap`'s allocation, returning a guard cast to the key
+    /// type `K2` and value type `V2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the map and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, K2, V2>(
+        &'parent mut self,
+    ) -> ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the map is
+        // always empty between guards, so no `(K1, V1)` entry is ever read as
+        // a `(K2, V2)`.
+        let inner_ptr = self.inner.get() as *mut HashMap<K2, V2, S>;
+
+        ReusableHashMapGuard {
+            inner: inner_ptr,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the map without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the map and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableHashMapGuard<'_, K1, V1, K1, V1, S> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableHashMapGuard {
+            inner: self.inner.get(),
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the map and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<K2, V2>(
+        &mut self,
+    ) -> Result<ReusableHashMapGuard<'_, K1, V1, K2, V2, S>, PoisonError>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the map was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the map as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the map, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.forget_leaked();
+            *self.inner.get_mut() = HashMap::default();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the map for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to key and
+    /// value types are inferred from how `f` uses it.
+    pub fn recycle_with<K2, V2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableHashMapGuard<'_, K1, V1, K2, V2, S>) -> R,
+    ) -> R
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Recycles the map and fills it from `iter`, returning the populated
+    /// guard.
+    ///
+    /// Room for the iterator's lower size hint is reserved up front, so a
+    /// warm map usually fills without allocating.
+    pub fn recycle_collect<K2, V2, I>(
+        &mut self,
+        iter: I,
+    ) -> ReusableHashMapGuard<'_, K1, V1, K2, V2, S>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+        K2: Eq + Hash,
+        I: IntoIterator<Item = (K2, V2)>,
+    {
+        let iter = iter.into_iter();
+        let mut guard = self.recycle();
+        guard.reserve(iter.size_hint().0);
+        guard.extend(iter);
+        guard
+    }
+
+    /// Returns the retained `HashMap`, which is always empty.
+    pub fn into_inner(mut self) -> HashMap<K1, V1, S> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// Leaks anything a leaked guard left in the map.
+    ///
+    /// Guards leave the map empty, so any entries in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let map = self.inner.get_mut();
+        if !map.is_empty() {
+            mem::forget(mem::take(map));
+        }
+    }
+
+    /// The number of entries the retained table can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `HashMap` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+}
+
+impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> Drop for ReusableHashMap<K, V, S> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the map.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> Drop for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Clears the `HashMap` when the guard is dropped, keeping its
+    /// allocation.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let map = unsafe { &mut *self.inner };
+        map.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed_keys_can_be_recycled() {
+        let mut map = ReusableHashMap::<&'static str, usize>::with_capacity(8);
+        let capacity = map.capacity();
+        for round in 0..2 {
+            let owned = format!("key {}", round);
+            let mut guard = map.recycle::<&str, usize>();
+            *guard.entry_ref(owned.as_str()).or_insert(0) += 1;
+            *guard.entry_ref(owned.as_str()).or_insert(0) += 1;
+            assert_eq!(guard.get(owned.as_str()), Some(&2));
+        }
+        assert_eq!(map.capacity(), capacity);
+        assert!(map.into_inner().is_empty());
+    }
+
+    #[test]
+    fn a_panic_poisons_the_map_until_it_is_reset() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.recycle_collect((0..64).map(|i| (i, i)));
+            let _guard = map.recycle_same();
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(map.is_poisoned());
+
+        // The default policy starts over with an empty table.
+        assert_eq!(map.recycle_same().capacity(), 0);
+        assert!(!map.is_poisoned());
+    }
+}
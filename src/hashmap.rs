@@ -1,10 +1,27 @@
-use crate::ReuseCastInto;
+#[cfg(feature = "stats")]
+use crate::stats::RecycleStats;
+#[cfg(feature = "tracing")]
+use crate::trace::GuardTrace;
+use crate::{
+    adaptive::AdaptiveCapacity,
+    clear_policy::Leftovers,
+    hooks::{HookWatch, Hooks},
+    poison::PoisonOnPanic,
+    AdaptiveCapacityPolicy, ClearPolicy, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto,
+};
 use std::{
+    borrow::{Borrow, BorrowMut},
     cell::UnsafeCell,
-    collections::{hash_map::RandomState, HashMap},
-    hash::BuildHasher,
+    collections::{
+        hash_map::{Drain, RandomState},
+        HashMap,
+    },
+    fmt,
+    hash::{BuildHasher, Hash},
     marker::PhantomData,
+    mem,
     ops::{Deref, DerefMut},
+    sync::Arc,
 };
 
 /// A wrapper around `HashMap` that allows for reusing its allocation across
@@ -76,10 +93,189 @@ use std::{
 ///     assert_eq!(map_guard.get(key), Some(&100));
 /// } // The guard is dropped, and `key` can no longer be accessed through it.
 /// ```
-#[derive(Debug)]
 pub struct ReusableHashMap<K: 'static, V: 'static, S: 'static + BuildHasher + Default = RandomState>
 {
     inner: UnsafeCell<HashMap<K, V, S>>,
+    shrink: Option<LoadFactorShrink>,
+    max_capacity: Option<usize>,
+    adaptive: Option<AdaptiveCapacity>,
+    clear_policy: ClearPolicy,
+    /// What the last guard left in the map, if it deferred clearing or was
+    /// leaked.
+    leftovers: Leftovers,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+    hooks: Hooks,
+    /// Makes the hasher of a rebuilt table from the current one.
+    clone_hasher: fn(&S) -> S,
+    #[cfg(feature = "stats")]
+    stats: RecycleStats,
+}
+
+/// A policy that shrinks the table of a [`ReusableHashMap`] once it has been
+/// persistently oversized.
+///
+/// A recycled map keeps the bucket array it needed at its historical peak.
+/// If a single large batch is followed by a long run of small ones, every
+/// subsequent cycle pays for clearing (and pins the memory of) a table that
+/// is far larger than necessary.
+///
+/// With this policy installed, every time a guard is dropped the map compares
+/// the number of elements it held against its capacity. When that load factor
+/// stays below [`min_load_factor`](LoadFactorShrinkPolicy::min_load_factor)
+/// for [`cycles`](LoadFactorShrinkPolicy::cycles) consecutive cycles, the
+/// table is reallocated with just enough room for the largest element count
+/// seen during that run. A single cycle at or above the threshold resets the
+/// count, so a map that is only occasionally underused is never rehashed.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{LoadFactorShrinkPolicy, ReusableHashMap};
+///
+/// let mut reusable_map = ReusableHashMap::<u32, u32>::default();
+/// reusable_map.set_shrink_policy(Some(LoadFactorShrinkPolicy::new(0.125, 2)));
+///
+/// // One outlier batch grows the table.
+/// reusable_map.recycle::<u32, u32>().extend((0..1024).map(|i| (i, i)));
+/// let peak_capacity = reusable_map.recycle::<u32, u32>().capacity();
+///
+/// // Two consecutive small batches bring it back down.
+/// for _ in 0..2 {
+///     reusable_map.recycle::<u32, u32>().extend((0..4).map(|i| (i, i)));
+/// }
+/// assert!(reusable_map.recycle::<u32, u32>().capacity() < peak_capacity);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadFactorShrinkPolicy {
+    min_load_factor: f64,
+    cycles: usize,
+}
+
+impl LoadFactorShrinkPolicy {
+    /// Creates a policy that shrinks the table after `cycles` consecutive
+    /// cycles whose load factor was below `min_load_factor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_load_factor` is not in `0.0..=1.0` or if `cycles` is
+    /// zero.
+    pub fn new(min_load_factor: f64, cycles: usize) -> Self {
+        match Self::try_new(min_load_factor, cycles) {
+            Ok(policy) => policy,
+            Err(msg) => panic!("{}", msg),
+        }
+    }
+
+    fn try_new(min_load_factor: f64, cycles: usize) -> Result<Self, &'static str> {
+        if !(0.0..=1.0).contains(&min_load_factor) {
+            return Err("min_load_factor must be between 0.0 and 1.0");
+        }
+        if cycles == 0 {
+            return Err("cycles must be greater than zero");
+        }
+        Ok(Self {
+            min_load_factor,
+            cycles,
+        })
+    }
+
+    /// The load factor (elements divided by capacity) below which a cycle
+    /// counts as underused.
+    pub fn min_load_factor(&self) -> f64 {
+        self.min_load_factor
+    }
+
+    /// The number of consecutive underused cycles required before the table
+    /// is shrunk.
+    pub fn cycles(&self) -> usize {
+        self.cycles
+    }
+}
+
+impl Default for LoadFactorShrinkPolicy {
+    /// Shrinks after 16 consecutive cycles below a load factor of `0.125`.
+    ///
+    /// The load factor is measured against `capacity`, the entries a table
+    /// holds before it has to grow, and growing doubles it, so a freshly
+    /// grown table sits at a load factor of about 0.5. Staying below 0.125
+    /// means the table is at least four times larger than a freshly grown
+    /// one would be.
+    fn default() -> Self {
+        Self::new(0.125, 16)
+    }
+}
+
+/// Deserializes a policy from `min_load_factor` and `cycles` fields, either of
+/// which falls back to the [`Default`] policy's value when missing.
+///
+/// Out-of-range values are reported as errors instead of panicking.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LoadFactorShrinkPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        struct Fields {
+            min_load_factor: f64,
+            cycles: usize,
+        }
+
+        impl Default for Fields {
+            fn default() -> Self {
+                let policy = LoadFactorShrinkPolicy::default();
+                Self {
+                    min_load_factor: policy.min_load_factor,
+                    cycles: policy.cycles,
+                }
+            }
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        Self::try_new(fields.min_load_factor, fields.cycles).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The policy together with the streak it has observed so far.
+#[derive(Debug)]
+struct LoadFactorShrink {
+    policy: LoadFactorShrinkPolicy,
+    low_cycles: usize,
+    peak_len: usize,
+}
+
+impl LoadFactorShrink {
+    fn new(policy: LoadFactorShrinkPolicy) -> Self {
+        Self {
+            policy,
+            low_cycles: 0,
+            peak_len: 0,
+        }
+    }
+
+    /// Records the element count and capacity of a finished cycle, returning
+    /// the capacity the table should be shrunk to, if any.
+    fn observe(&mut self, len: usize, capacity: usize) -> Option<usize> {
+        if capacity == 0 || len as f64 >= capacity as f64 * self.policy.min_load_factor {
+            self.low_cycles = 0;
+            self.peak_len = 0;
+            return None;
+        }
+
+        self.low_cycles += 1;
+        self.peak_len = self.peak_len.max(len);
+        if self.low_cycles < self.policy.cycles {
+            return None;
+        }
+
+        let target = self.peak_len;
+        self.low_cycles = 0;
+        self.peak_len = 0;
+        Some(target)
+    }
 }
 
 // The `ReusableHashMap` is safe to send across threads if its contents are `Send`.
@@ -100,6 +296,20 @@ unsafe impl<K: Send, V: Send, S: 'static + Send + BuildHasher + Default> Sync
 {
 }
 
+// Only the table's size is shown, for the same reason as `ReusableVec`'s
+// `Debug`: the entries may be stale or borrow data that is gone.
+impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> fmt::Debug
+    for ReusableHashMap<K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ReusableHashMap");
+        debug.field("capacity", &self.capacity());
+        #[cfg(feature = "stats")]
+        debug.field("stats", &self.stats);
+        debug.finish()
+    }
+}
+
 impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> Default
     for ReusableHashMap<K, V, S>
 {
@@ -114,9 +324,20 @@ impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default> Default
     /// assert_eq!(map.recycle::<String, i32>().capacity(), 0);
     /// ```
     fn default() -> Self {
-        Self {
-            inner: UnsafeCell::new(HashMap::default()),
-        }
+        Self::from_table(HashMap::default(), default_hasher)
+    }
+}
+
+impl<K: 'static, V: 'static, S: 'static + BuildHasher + Default + Clone> From<HashMap<K, V, S>>
+    for ReusableHashMap<K, V, S>
+{
+    /// Adopts the table and hasher of `map`, dropping its entries.
+    ///
+    /// The hasher is cloned whenever the table is rebuilt, so it is kept
+    /// when the map shrinks.
+    fn from(mut map: HashMap<K, V, S>) -> Self {
+        map.clear();
+        Self::from_table(map, S::clone)
     }
 }
 
@@ -147,9 +368,36 @@ where
     S: 'static + BuildHasher + Default,
 {
     inner: *mut HashMap<K2, V2, S>,
+    shrink: &'parent mut Option<LoadFactorShrink>,
+    max_capacity: Option<usize>,
+    adaptive: &'parent mut Option<AdaptiveCapacity>,
+    leftovers: &'parent mut Leftovers,
+    /// Whether the entries are left for the next `recycle` to clear.
+    defer: bool,
+    _poison: PoisonOnPanic<'parent>,
+    hooks: HookWatch<'parent>,
+    clone_hasher: fn(&S) -> S,
+    #[cfg(feature = "stats")]
+    stats: &'parent mut RecycleStats,
+    #[cfg(feature = "tracing")]
+    trace: GuardTrace,
     _parent: PhantomData<&'parent mut ReusableHashMap<K1, V1, S>>,
 }
 
+// SAFETY: The guard is an exclusive borrow of its parent, which is `Send` and
+// `Sync` when its contents are. The parent stays mutably borrowed while the
+// guard is alive, so nothing else can reach the map or the parent's fields the
+// guard points to.
+unsafe impl<'parent, K1, V1, K2, V2, S> Send for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: Send,
+    V1: Send,
+    K2: Send,
+    V2: Send,
+    S: 'static + Send + BuildHasher + Default,
+{
+}
+
 impl<'parent, K1, V1, K2, V2, S> Deref for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
 where
     K1: 'static,
@@ -202,12 +450,349 @@ where
     }
 }
 
+impl<'parent, K1, V1, K2, V2, S> ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// The estimated number of heap bytes the `HashMap` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `HashMap`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one. Use this when a cycle's result has to outlive the guard,
+    /// for example to send it to another thread.
+    pub fn detach(mut self) -> HashMap<K2, V2, S> {
+        mem::take(&mut *self)
+    }
+
+    /// Gives up mutable access, so the map can be read from several places
+    /// at once.
+    ///
+    /// The returned [`ReusableHashMapFrozen`] is a shared read guard. Cloning
+    /// it hands out another one for the same map, which can be moved into a
+    /// closure or a scoped thread, and the map is only cleared once the last
+    /// of them is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use triple_r::ReusableHashMap;
+    ///
+    /// let mut scores = ReusableHashMap::<&str, u32>::default();
+    /// let mut guard = scores.recycle_same();
+    /// guard.extend([("ada", 3), ("grace", 5)]);
+    ///
+    /// let frozen = guard.freeze();
+    /// thread::scope(|scope| {
+    ///     for name in ["ada", "grace"] {
+    ///         let reader = frozen.clone();
+    ///         scope.spawn(move || assert!(reader[name] >= 3));
+    ///     }
+    ///     // The readers keep the map alive after this one is gone.
+    ///     drop(frozen);
+    /// });
+    ///
+    /// assert!(scores.recycle_same().is_empty());
+    /// ```
+    pub fn freeze(self) -> ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S> {
+        ReusableHashMapFrozen {
+            guard: Arc::new(self),
+        }
+    }
+}
+
+/// A shared, read-only [`ReusableHashMapGuard`], created by
+/// [`freeze`](ReusableHashMapGuard::freeze).
+///
+/// Frozen guards are reference counted. Each clone reads the same map, and
+/// the map is cleared when the last clone is dropped, just like when the
+/// guard they came from would have been.
+pub struct ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    guard: Arc<ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>>,
+}
+
+// SAFETY: Frozen guards only hand out `&HashMap<K2, V2, S>`, which can be
+// shared between threads when the map is `Sync`. Whichever thread drops the
+// last clone drops the guard, which is `Send` under the same bounds.
+unsafe impl<'parent, K1, V1, K2, V2, S> Send for ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S>
+where
+    K1: Send,
+    V1: Send,
+    K2: Send + Sync,
+    V2: Send + Sync,
+    S: 'static + Send + Sync + BuildHasher + Default,
+{
+}
+
+// SAFETY: See `Send`. A shared frozen guard can be cloned, so it can send a
+// new one to another thread.
+unsafe impl<'parent, K1, V1, K2, V2, S> Sync for ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S>
+where
+    K1: Send,
+    V1: Send,
+    K2: Send + Sync,
+    V2: Send + Sync,
+    S: 'static + Send + Sync + BuildHasher + Default,
+{
+}
+
+impl<'parent, K1, V1, K2, V2, S> Clone for ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Returns another read guard for the same map.
+    fn clone(&self) -> Self {
+        Self {
+            guard: Arc::clone(&self.guard),
+        }
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> Deref for ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    type Target = HashMap<K2, V2, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> fmt::Debug for ReusableHashMapFrozen<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: fmt::Debug,
+    V2: fmt::Debug,
+    S: 'static + BuildHasher + Default,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> fmt::Debug for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: fmt::Debug,
+    V2: fmt::Debug,
+    S: 'static + BuildHasher + Default,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, 'b, K1, V1, L1, W1, K2, V2, S> PartialEq<ReusableHashMapGuard<'b, L1, W1, K2, V2, S>>
+    for ReusableHashMapGuard<'a, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    L1: 'static,
+    W1: 'static,
+    K2: Eq + Hash,
+    V2: PartialEq,
+    S: 'static + BuildHasher + Default,
+{
+    fn eq(&self, other: &ReusableHashMapGuard<'b, L1, W1, K2, V2, S>) -> bool {
+        **self == **other
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> PartialEq<HashMap<K2, V2, S>>
+    for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Eq + Hash,
+    V2: PartialEq,
+    S: 'static + BuildHasher + Default,
+{
+    fn eq(&self, other: &HashMap<K2, V2, S>) -> bool {
+        **self == *other
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> PartialEq<ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>>
+    for HashMap<K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Eq + Hash,
+    V2: PartialEq,
+    S: 'static + BuildHasher + Default,
+{
+    fn eq(&self, other: &ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>) -> bool {
+        *self == **other
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> Eq for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Eq + Hash,
+    V2: Eq,
+    S: 'static + BuildHasher + Default,
+{
+}
+
+impl<'parent, K1, V1, K2, V2, S> AsRef<HashMap<K2, V2, S>>
+    for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    fn as_ref(&self) -> &HashMap<K2, V2, S> {
+        self
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> AsMut<HashMap<K2, V2, S>>
+    for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    fn as_mut(&mut self) -> &mut HashMap<K2, V2, S> {
+        self
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> Borrow<HashMap<K2, V2, S>>
+    for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    fn borrow(&self) -> &HashMap<K2, V2, S> {
+        self
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> BorrowMut<HashMap<K2, V2, S>>
+    for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    fn borrow_mut(&mut self) -> &mut HashMap<K2, V2, S> {
+        self
+    }
+}
+
 impl<K1, V1, S> ReusableHashMap<K1, V1, S>
 where
     K1: 'static,
     V1: 'static,
     S: 'static + BuildHasher + Default,
 {
+    /// Creates a `ReusableHashMap` whose table already holds `capacity`
+    /// entries, so the first cycles don't rehash.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_table(
+            HashMap::with_capacity_and_hasher(capacity, S::default()),
+            default_hasher,
+        )
+    }
+
+    /// Creates a `ReusableHashMap` with room for `capacity` entries that
+    /// hashes with `hasher`.
+    ///
+    /// When a [`LoadFactorShrinkPolicy`], an [`AdaptiveCapacityPolicy`] or a
+    /// [maximum retained capacity](Self::set_max_retained_capacity) rebuilds
+    /// the table, the new one hashes with a clone of `hasher`.
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self
+    where
+        S: Clone,
+    {
+        Self::from_table(
+            HashMap::with_capacity_and_hasher(capacity, hasher),
+            S::clone,
+        )
+    }
+
+    /// Wraps the empty `map`, with every policy at its default.
+    fn from_table(map: HashMap<K1, V1, S>, clone_hasher: fn(&S) -> S) -> Self {
+        Self {
+            inner: UnsafeCell::new(map),
+            shrink: None,
+            max_capacity: None,
+            adaptive: None,
+            clear_policy: ClearPolicy::default(),
+            leftovers: Leftovers::None,
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+            hooks: Hooks::default(),
+            clone_hasher,
+            #[cfg(feature = "stats")]
+            stats: RecycleStats::default(),
+        }
+    }
+
+    /// Grows the retained table to hold at least `capacity` entries, without
+    /// taking a guard.
+    ///
+    /// Does nothing if the table is already large enough.
+    pub fn prewarm(&mut self, capacity: usize)
+    where
+        K1: Eq + Hash,
+    {
+        self.clear_leftovers();
+        self.inner.get_mut().reserve(capacity);
+    }
+
+    /// Releases the retained table, without taking a guard.
+    ///
+    /// For a map that shrinks on its own after a run of sparse cycles, see
+    /// [`set_shrink_policy`](Self::set_shrink_policy).
+    pub fn shrink_to_fit(&mut self)
+    where
+        K1: Eq + Hash,
+    {
+        self.clear_leftovers();
+        self.inner.get_mut().shrink_to_fit();
+    }
+
+    /// Shrinks the retained table to hold at least `min_capacity` entries,
+    /// without taking a guard.
+    ///
+    /// Does nothing if the table is already smaller. Use this to let go of
+    /// the memory a one-off batch left behind while keeping room for the
+    /// usual one.
+    pub fn shrink_to(&mut self, min_capacity: usize)
+    where
+        K1: Eq + Hash,
+    {
+        self.clear_leftovers();
+        self.inner.get_mut().shrink_to(min_capacity);
+    }
+
     /// Borrows the `HashMap` for temporary use, returning a guard that allows
     /// access to it.
     ///
@@ -238,6 +823,11 @@ where
     /// 3. The `&mut self` receiver prevents multiple guards from being created
     ///    simultaneously.
     ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the map and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    ///
     /// # Examples
     ///
     /// ```
@@ -259,17 +849,353 @@ where
         K1: ReuseCastInto<K2>,
         V1: ReuseCastInto<V2>,
     {
+        // Cast entries may borrow data that is gone by the next `recycle`,
+        // so they are only dropped there if dropping them does nothing.
+        let droppable = !mem::needs_drop::<(K1, V1)>() && !mem::needs_drop::<(K2, V2)>();
+        // SAFETY: `ReuseCastInto` guarantees that an empty `HashMap<K1, V1>`
+        // can be used as a `HashMap<K2, V2>`, and droppable entries don't
+        // need to be dropped.
+        unsafe { self.guard(droppable) }
+    }
+
+    /// Returns a guard cast to `K2` and `V2`, which may leave its entries
+    /// behind if they are `droppable` and the clear policy asks for it.
+    ///
+    /// # Safety
+    ///
+    /// An empty `HashMap<K1, V1, S>` must be usable as a
+    /// `HashMap<K2, V2, S>`, and if `droppable` is set, dropping the entries
+    /// left behind as `(K1, V1)`s must be sound at any later point.
+    unsafe fn guard<K2, V2>(
+        &mut self,
+        droppable: bool,
+    ) -> ReusableHashMapGuard<'_, K1, V1, K2, V2, S> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.clear_leftovers();
+        // Until the guard is dropped, whatever is in the map is treated as
+        // leaked.
+        self.leftovers = if droppable {
+            Leftovers::Droppable
+        } else {
+            Leftovers::Leaked
+        };
         // SAFETY: We use `get()` to obtain a raw pointer to the hash map.
         // This is safe because we have `&mut self`, guaranteeing exclusive
         // access. This avoids creating an intermediate `&mut` reference that
         // could be invalidated, which was the source of the Miri error.
+        let capacity = self.inner.get_mut().capacity();
+        #[cfg(feature = "stats")]
+        self.stats.begin(capacity);
+        #[cfg(feature = "tracing")]
+        let trace = GuardTrace::recycle(std::any::type_name::<Self>(), capacity);
         let inner_ptr = self.inner.get() as *mut HashMap<K2, V2, S>;
+        let trivial = !mem::needs_drop::<(K1, V1)>() && !mem::needs_drop::<(K2, V2)>();
+        let defer = droppable && self.clear_policy.defers(trivial);
 
         ReusableHashMapGuard {
             inner: inner_ptr,
+            shrink: &mut self.shrink,
+            max_capacity: self.max_capacity,
+            adaptive: &mut self.adaptive,
+            leftovers: &mut self.leftovers,
+            defer,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            hooks: self.hooks.recycle(capacity),
+            clone_hasher: self.clone_hasher,
+            #[cfg(feature = "stats")]
+            stats: &mut self.stats,
+            #[cfg(feature = "tracing")]
+            trace,
             _parent: PhantomData,
         }
     }
+
+    /// Returns a guard for the map without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the map and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableHashMapGuard<'_, K1, V1, K1, V1, S> {
+        // SAFETY: Nothing changes type, and entries left behind are
+        // `(K1, V1)`s, which can be dropped whenever.
+        unsafe { self.guard(true) }
+    }
+
+    /// Reuses the `HashMap`'s allocation like [`recycle`](Self::recycle), but
+    /// returns an error instead of panicking if a panic poisoned it and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<K2, V2>(
+        &mut self,
+    ) -> Result<ReusableHashMapGuard<'_, K1, V1, K2, V2, S>, PoisonError>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Recycles the map for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<K2, V2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableHashMapGuard<'_, K1, V1, K2, V2, S>) -> R,
+    ) -> R
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `HashMap`, which is always empty.
+    pub fn into_inner(mut self) -> HashMap<K1, V1, S> {
+        self.clear_leftovers();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// The number of entries the retained table can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `HashMap` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+
+    /// Exchanges the retained allocation with the one in `other`, without
+    /// copying either.
+    ///
+    /// `other` gets the retained `HashMap`, empty, and its own allocation is kept
+    /// for the next guard. Anything left in `other` is dropped.
+    pub fn swap(&mut self, other: &mut HashMap<K1, V1, S>) {
+        self.clear_leftovers();
+        other.clear();
+        mem::swap(self.inner.get_mut(), other);
+    }
+
+    /// Recycles the map and fills it from `iter`, returning the populated
+    /// guard.
+    ///
+    /// Room for the iterator's lower size hint is reserved up front, so a
+    /// warm map usually fills without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use triple_r::ReusableHashMap;
+    ///
+    /// let mut lengths = ReusableHashMap::<&'static str, usize>::default();
+    /// let words = String::from("reduce reuse recycle");
+    /// let guard = lengths.recycle_collect(words.split(' ').map(|w| (w, w.len())));
+    /// assert_eq!(guard["recycle"], 7);
+    /// ```
+    pub fn recycle_collect<K2, V2, I>(
+        &mut self,
+        iter: I,
+    ) -> ReusableHashMapGuard<'_, K1, V1, K2, V2, S>
+    where
+        K1: ReuseCastInto<K2>,
+        V1: ReuseCastInto<V2>,
+        K2: Eq + Hash,
+        I: IntoIterator<Item = (K2, V2)>,
+    {
+        let iter = iter.into_iter();
+        let mut guard = self.recycle();
+        guard.reserve(iter.size_hint().0);
+        guard.extend(iter);
+        guard
+    }
+
+    /// Sets when the map is cleared: when a guard is dropped, at the next
+    /// `recycle`, or both.
+    ///
+    /// Deferring skips resetting the table on the guard's way out, which
+    /// takes time in proportion to its capacity. See [`ClearPolicy`] for
+    /// which guards can defer clearing.
+    pub fn set_clear_policy(&mut self, policy: ClearPolicy) {
+        self.clear_policy = policy;
+    }
+
+    /// Returns the current clear policy.
+    pub fn clear_policy(&self) -> ClearPolicy {
+        self.clear_policy
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the map was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the map as no longer poisoned, keeping its table.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the map, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.clear_leftovers();
+            let map = self.inner.get_mut();
+            *map = HashMap::with_hasher((self.clone_hasher)(map.hasher()));
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Empties the map of whatever the last guard left behind.
+    ///
+    /// Entries deferred by [`ClearPolicy::OnRecycle`] are dropped. Those of a
+    /// leaked guard that cast them are leaked, table and all, since their
+    /// destructors could read borrowed data that is gone.
+    fn clear_leftovers(&mut self) {
+        let map = self.inner.get_mut();
+        match mem::replace(&mut self.leftovers, Leftovers::None) {
+            Leftovers::None => {}
+            Leftovers::Droppable => map.clear(),
+            Leftovers::Leaked => {
+                let hasher = (self.clone_hasher)(map.hasher());
+                mem::forget(mem::replace(map, HashMap::with_hasher(hasher)));
+            }
+        }
+    }
+
+    /// Installs or removes the [`AdaptiveCapacityPolicy`] consulted every
+    /// time a guard is dropped.
+    ///
+    /// Replacing the policy discards the lengths observed so far. Passing
+    /// `None`, the default, keeps whatever the guards grew to.
+    pub fn set_adaptive_capacity(&mut self, policy: Option<AdaptiveCapacityPolicy>) {
+        self.adaptive = policy.map(AdaptiveCapacity::new);
+    }
+
+    /// Returns the current [`AdaptiveCapacityPolicy`], if one is installed.
+    pub fn adaptive_capacity(&self) -> Option<AdaptiveCapacityPolicy> {
+        self.adaptive.as_ref().map(AdaptiveCapacity::policy)
+    }
+
+    /// Calls `hook` with the capacity of the map every time `recycle`
+    /// hands out a guard.
+    ///
+    /// Replaces the previous recycle hook. See the [`hooks`](crate::hooks)
+    /// module for an example.
+    pub fn on_recycle(&mut self, hook: impl Fn(usize) + Send + Sync + 'static) {
+        self.hooks.on_recycle = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the capacity a guard started with and the one it
+    /// was dropped with, whenever the map outgrew its retained allocation.
+    ///
+    /// Replaces the previous grow hook.
+    pub fn on_grow(&mut self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.hooks.on_grow = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the capacity before and after, whenever a capacity
+    /// policy shrinks the retained allocation as a guard is dropped.
+    ///
+    /// Replaces the previous shrink hook.
+    pub fn on_shrink(&mut self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.hooks.on_shrink = Some(Box::new(hook));
+    }
+
+    /// Removes the hooks installed with [`on_recycle`](Self::on_recycle),
+    /// [`on_grow`](Self::on_grow) and [`on_shrink`](Self::on_shrink).
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    /// Caps the capacity kept between guards at `max` entries.
+    ///
+    /// A guard that is dropped with a larger table rebuilds it with room for
+    /// `max` entries, so one outlier batch doesn't pin its memory for the
+    /// rest of the program. Unlike a [`LoadFactorShrinkPolicy`], the cap
+    /// applies right away, on the first oversized cycle. `None`, the default,
+    /// keeps whatever the guards grew to.
+    ///
+    /// Tables only come in certain sizes, so the cap is rounded up to the
+    /// capacity of a table built for `max` entries.
+    pub fn set_max_retained_capacity(&mut self, max: Option<usize>) {
+        self.max_capacity = max.map(|max| {
+            HashMap::<K1, V1, S>::with_capacity_and_hasher(max, S::default()).capacity()
+        });
+    }
+
+    /// Returns the cap set by
+    /// [`set_max_retained_capacity`](Self::set_max_retained_capacity),
+    /// rounded up to a table size.
+    pub fn max_retained_capacity(&self) -> Option<usize> {
+        self.max_capacity
+    }
+
+    /// Installs or removes the [`LoadFactorShrinkPolicy`] consulted every time
+    /// a guard is dropped.
+    ///
+    /// Replacing the policy discards any streak of underused cycles observed
+    /// so far. Passing `None` restores the default behavior of always keeping
+    /// the largest table the map has grown to.
+    pub fn set_shrink_policy(&mut self, policy: Option<LoadFactorShrinkPolicy>) {
+        self.shrink = policy.map(LoadFactorShrink::new);
+    }
+
+    /// Returns the currently installed [`LoadFactorShrinkPolicy`], if any.
+    pub fn shrink_policy(&self) -> Option<LoadFactorShrinkPolicy> {
+        self.shrink.as_ref().map(|shrink| shrink.policy)
+    }
+
+    /// The statistics recorded so far.
+    ///
+    /// This method is only available with the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &RecycleStats {
+        &self.stats
+    }
+}
+
+impl<K, V, S> Drop for ReusableHashMap<K, V, S>
+where
+    K: 'static,
+    V: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the map.
+    fn drop(&mut self) {
+        self.clear_leftovers();
+    }
 }
 
 impl<'parent, K1, V1, K2, V2, S> Drop for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
@@ -284,6 +1210,13 @@ where
     /// dropping it, we preserve its memory allocation (capacity) for the next
     /// user. This avoids the cost of deallocation and reallocation.
     ///
+    /// If a [`LoadFactorShrinkPolicy`] is installed and decides the table has
+    /// been oversized for long enough, the cleared map is replaced with a
+    /// smaller one that keeps its hasher. The same happens
+    /// if an [`AdaptiveCapacityPolicy`] finds the table oversized, or if it
+    /// has outgrown the
+    /// [maximum retained capacity](ReusableHashMap::set_max_retained_capacity).
+    ///
     /// # Safety
     ///
     /// The pointer `self.inner` is guaranteed to be valid because the lifetime
@@ -293,12 +1226,126 @@ where
         // SAFETY: The pointer `self.inner` is guaranteed to be valid.
         // We get a mutable reference and clear the map, making it ready for
         // the next reuse.
-        unsafe {
-            (*self.inner).clear();
+        let map = unsafe { &mut *self.inner };
+        let len = map.len();
+        let capacity = map.capacity();
+        if !self.defer {
+            map.clear();
+            *self.leftovers = Leftovers::None;
+        }
+        #[cfg(feature = "stats")]
+        self.stats.record(len, capacity);
+
+        if let Some(target) = self
+            .shrink
+            .as_mut()
+            .and_then(|shrink| shrink.observe(len, capacity))
+        {
+            // The map is empty, so nothing needs rehashing. Building a new
+            // table avoids the `K2: Eq + Hash` bound `HashMap::shrink_to`
+            // would require.
+            rebuild(map, target, self.clone_hasher);
+        }
+        if let Some(target) = self
+            .adaptive
+            .as_mut()
+            .and_then(|adaptive| adaptive.observe(len, map.capacity()))
+        {
+            rebuild(map, target, self.clone_hasher);
+        }
+        if let Some(max) = self.max_capacity {
+            if map.capacity() > max {
+                rebuild(map, max, self.clone_hasher);
+            }
+        }
+        self.hooks.finish(capacity, map.capacity());
+        #[cfg(feature = "tracing")]
+        self.trace.finish(len, capacity, map.capacity());
+    }
+}
+
+/// Replaces the empty `map` with a table for `capacity` entries, hashing with
+/// a hasher made from the current one.
+fn rebuild<K, V, S>(map: &mut HashMap<K, V, S>, capacity: usize, clone_hasher: fn(&S) -> S) {
+    *map = HashMap::with_capacity_and_hasher(capacity, clone_hasher(map.hasher()));
+}
+
+/// Makes a new default hasher, for maps that were never given one.
+fn default_hasher<S: Default>(_: &S) -> S {
+    S::default()
+}
+
+/// A draining iterator over the contents of a [`ReusableHashMapGuard`].
+///
+/// Returned by the guard's `IntoIterator` impl. The elements are moved out by
+/// value, and the `HashMap` keeps its allocation for the next guard.
+pub struct ReusableHashMapIntoIter<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: 'parent,
+    V2: 'parent,
+    S: 'static + BuildHasher + Default,
+{
+    drain: Drain<'parent, K2, V2>,
+    // Declared after `drain`, so the guard's cleanup runs once the drain has
+    // finished with the `HashMap`.
+    _guard: ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>,
+}
+
+impl<'parent, K1, V1, K2, V2, S> IntoIterator for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: 'parent,
+    V2: 'parent,
+    S: 'static + BuildHasher + Default,
+{
+    type Item = (K2, V2);
+    type IntoIter = ReusableHashMapIntoIter<'parent, K1, V1, K2, V2, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // SAFETY: `self.inner` is valid and exclusive for `'parent`. The
+        // guard is moved into the iterator and only touches the `HashMap` again
+        // when it is dropped, after the drain.
+        let drain = unsafe { &mut *self.inner }.drain();
+        ReusableHashMapIntoIter {
+            drain,
+            _guard: self,
         }
     }
 }
 
+impl<'parent, K1, V1, K2, V2, S> Iterator for ReusableHashMapIntoIter<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: 'parent,
+    V2: 'parent,
+    S: 'static + BuildHasher + Default,
+{
+    type Item = (K2, V2);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<'parent, K1, V1, K2, V2, S> ExactSizeIterator
+    for ReusableHashMapIntoIter<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: 'parent,
+    V2: 'parent,
+    S: 'static + BuildHasher + Default,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -319,6 +1366,236 @@ mod tests {
         assert!(unsafe { (*map.inner.get()).is_empty() });
     }
 
+    #[test]
+    fn recycle_same_keeps_the_stored_types() {
+        let mut map = ReusableHashMap::<String, i32>::default();
+        let capacity = {
+            let mut r_map = map.recycle_same();
+            r_map.insert("one".to_string(), 1);
+            r_map.capacity()
+        };
+        let r_map = map.recycle_same();
+        assert!(r_map.is_empty());
+        assert_eq!(r_map.capacity(), capacity);
+    }
+
+    #[test]
+    fn recycle_with_infers_the_cast_from_the_closure() {
+        let mut map = ReusableHashMap::<&'static str, i32>::default();
+        for word in ["a", "b"] {
+            let key = word.repeat(3);
+            let len = map.recycle_with(|r_map| {
+                r_map.insert(key.as_str(), 1);
+                r_map.len()
+            });
+            assert_eq!(len, 1);
+        }
+        assert!(unsafe { (*map.inner.get()).is_empty() });
+    }
+
+    #[test]
+    fn into_iter_moves_entries_out() {
+        let mut map = ReusableHashMap::<u32, String>::default();
+        let mut r_map = map.recycle_same();
+        r_map.extend((0..32).map(|i| (i, i.to_string())));
+        let capacity = r_map.capacity();
+
+        let mut entries: Vec<_> = r_map.into_iter().collect();
+        entries.sort();
+        assert_eq!(entries.len(), 32);
+        assert_eq!(entries[7], (7, "7".to_string()));
+
+        let r_map = map.recycle_same();
+        assert!(r_map.is_empty());
+        assert_eq!(r_map.capacity(), capacity);
+    }
+
+    #[test]
+    fn guards_compare_with_maps() {
+        let mut map = ReusableHashMap::<&'static str, u32>::default();
+        let mut guard = map.recycle_same();
+        guard.insert("a", 1);
+
+        let expected: HashMap<_, _, RandomState> = [("a", 1)].into_iter().collect();
+        assert_eq!(guard, expected);
+        assert_eq!(expected, guard);
+        assert_eq!(format!("{:?}", guard), r#"{"a": 1}"#);
+    }
+
+    #[test]
+    fn with_capacity_and_hasher_keeps_the_hasher() {
+        let hasher = BuildHasherDefault::<XxHash64>::default();
+        let mut map = ReusableHashMap::<u32, u32, _>::with_capacity_and_hasher(100, hasher);
+        assert!(map.recycle_same().capacity() >= 100);
+
+        map.prewarm(1000);
+        let mut guard = map.recycle_same();
+        assert!(guard.capacity() >= 1000);
+        guard.insert(1, 1);
+        assert_eq!(guard.get(&1), Some(&1));
+    }
+
+    #[test]
+    fn shrinking_keeps_the_hasher() {
+        #[derive(Clone, Default)]
+        struct Seeded(u64);
+
+        impl BuildHasher for Seeded {
+            type Hasher = XxHash64;
+
+            fn build_hasher(&self) -> XxHash64 {
+                XxHash64::with_seed(self.0)
+            }
+        }
+
+        let mut map = ReusableHashMap::<u32, u32, _>::with_capacity_and_hasher(0, Seeded(7));
+        map.set_max_retained_capacity(Some(16));
+        map.recycle_same().extend((0..1024).map(|i| (i, i)));
+
+        let guard = map.recycle_same();
+        assert!(guard.capacity() < 1024);
+        assert_eq!(guard.hasher().0, 7);
+    }
+
+    #[test]
+    fn shrinking_releases_the_table() {
+        let mut map = ReusableHashMap::<u32, u32>::with_capacity(4096);
+        map.shrink_to(100);
+        assert!(map.capacity() >= 100);
+        assert!(map.capacity() < 4096);
+
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 0);
+    }
+
+    #[test]
+    fn max_retained_capacity_rebuilds_oversized_tables() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        map.set_max_retained_capacity(Some(100));
+        let max = map.max_retained_capacity().unwrap();
+        assert!(max >= 100);
+
+        map.recycle_collect((0..5000).map(|i| (i, i)));
+        assert_eq!(map.capacity(), max);
+
+        // A table at the cap is left alone.
+        map.recycle_collect((0..50).map(|i| (i, i)));
+        assert_eq!(map.capacity(), max);
+    }
+
+    #[test]
+    fn adaptive_capacity_sheds_an_old_spike() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        map.set_adaptive_capacity(Some(AdaptiveCapacityPolicy::new(0.95, 4)));
+        map.recycle_collect((0..4096).map(|i| (i, i)));
+        let peak = map.capacity();
+
+        for _ in 0..3 {
+            map.recycle_collect((0..16).map(|i| (i, i)));
+            assert_eq!(map.capacity(), peak);
+        }
+        map.recycle_collect((0..16).map(|i| (i, i)));
+        assert!(map.capacity() < 64);
+
+        // Once converged, the table is not rebuilt again.
+        let capacity = map.capacity();
+        map.recycle_collect((0..16).map(|i| (i, i)));
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn clear_policy_moves_clearing_to_recycle() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        map.set_clear_policy(ClearPolicy::OnRecycle);
+        map.recycle_collect((0..64).map(|i| (i, i)));
+        assert_eq!(unsafe { (*map.inner.get()).len() }, 64);
+        assert!(map.recycle_same().is_empty());
+
+        map.set_clear_policy(ClearPolicy::Both);
+        let guard = map.recycle_collect((0..64).map(|i| (i, i)));
+        mem::forget(guard);
+        assert!(map.recycle_same().is_empty());
+    }
+
+    #[test]
+    fn from_adopts_a_populated_map() {
+        let startup: HashMap<u32, String, RandomState> =
+            (0..512).map(|i| (i, i.to_string())).collect();
+        let capacity = startup.capacity();
+
+        let mut map = ReusableHashMap::from(startup);
+        let guard = map.recycle_same();
+        assert!(guard.is_empty());
+        assert_eq!(guard.capacity(), capacity);
+    }
+
+    #[test]
+    fn into_inner_keeps_the_table() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        let capacity = map.recycle_collect((0..100).map(|i| (i, i))).capacity();
+        let inner = map.into_inner();
+        assert!(inner.is_empty());
+        assert_eq!(inner.capacity(), capacity);
+    }
+
+    #[test]
+    fn detach_hands_off_the_map() {
+        let mut map = ReusableHashMap::<u32, &'static str>::default();
+        let mut guard = map.recycle_same();
+        guard.insert(1, "one");
+        let owned = guard.detach();
+        assert_eq!(owned.get(&1), Some(&"one"));
+        assert!(map.recycle_same().is_empty());
+    }
+
+    #[test]
+    fn capacity_is_visible_without_a_guard() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        assert_eq!(map.capacity(), 0);
+        let capacity = map.recycle_collect((0..64).map(|i| (i, i))).capacity();
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn cow_values_can_borrow_per_batch_input() {
+        use std::borrow::Cow;
+
+        let mut map = ReusableHashMap::<&'static str, Cow<'static, str>>::default();
+        for batch in ["a=1", "b=2"] {
+            let input = batch.to_string();
+            let (key, value) = input.split_once('=').unwrap();
+            let mut r_map = map.recycle::<&str, Cow<str>>();
+            r_map.insert(key, Cow::Borrowed(value));
+            r_map.insert("owned", Cow::Owned(value.repeat(2)));
+            assert_eq!(r_map.len(), 2);
+        }
+    }
+
+    #[test]
+    fn nested_vec_values_can_be_recycled() {
+        let mut map = ReusableHashMap::<&'static str, Vec<&'static str>>::default();
+        for line in ["a b c", "d e"] {
+            let input = line.to_string();
+            let mut r_map = map.recycle::<&str, Vec<&str>>();
+            r_map.insert(&input[..1], input.split(' ').collect());
+            assert_eq!(r_map[&input[..1]].len(), input.split(' ').count());
+        }
+    }
+
+    #[test]
+    fn nested_collection_values_can_be_recycled() {
+        use std::collections::HashSet;
+
+        let mut map = ReusableHashMap::<&'static str, HashSet<&'static str>>::default();
+        for line in ["a b a", "c"] {
+            let input = line.to_string();
+            let mut r_map = map.recycle::<&str, HashSet<&str>>();
+            let words = r_map.entry(&input[..1]).or_default();
+            words.extend(input.split(' '));
+            assert_eq!(words.len(), input.split(' ').collect::<HashSet<_>>().len());
+        }
+    }
+
     #[test]
     fn string_identity_reuse_works() {
         let mut map = ReusableHashMap::<String, String>::default();
@@ -392,4 +1669,105 @@ mod tests {
         let map_guard = reusable_map.lock().unwrap();
         assert!(unsafe { (*map_guard.inner.get()).is_empty() });
     }
+
+    #[test]
+    fn shrink_policy_shrinks_after_consecutive_low_cycles() {
+        let mut map = ReusableHashMap::<i32, i32>::default();
+        map.set_shrink_policy(Some(LoadFactorShrinkPolicy::new(0.125, 3)));
+        map.recycle::<i32, i32>().extend((0..1000).map(|i| (i, i)));
+        let peak_capacity = unsafe { (*map.inner.get()).capacity() };
+
+        for _ in 0..2 {
+            map.recycle::<i32, i32>().extend((0..10).map(|i| (i, i)));
+            assert_eq!(unsafe { (*map.inner.get()).capacity() }, peak_capacity);
+        }
+        map.recycle::<i32, i32>().extend((0..5).map(|i| (i, i)));
+
+        let shrunk = unsafe { &*map.inner.get() };
+        assert!(shrunk.is_empty());
+        assert!(shrunk.capacity() >= 10);
+        assert!(shrunk.capacity() < peak_capacity);
+    }
+
+    #[test]
+    fn shrink_policy_resets_on_well_loaded_cycle() {
+        let mut map = ReusableHashMap::<i32, i32>::default();
+        map.set_shrink_policy(Some(LoadFactorShrinkPolicy::new(0.125, 2)));
+        map.recycle::<i32, i32>().extend((0..1000).map(|i| (i, i)));
+        let peak_capacity = unsafe { (*map.inner.get()).capacity() };
+
+        map.recycle::<i32, i32>().insert(1, 1);
+        map.recycle::<i32, i32>().extend((0..1000).map(|i| (i, i)));
+        map.recycle::<i32, i32>().insert(1, 1);
+        assert_eq!(unsafe { (*map.inner.get()).capacity() }, peak_capacity);
+
+        map.set_shrink_policy(None);
+        for _ in 0..4 {
+            map.recycle::<i32, i32>().insert(1, 1);
+        }
+        assert_eq!(unsafe { (*map.inner.get()).capacity() }, peak_capacity);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn shrink_policy_deserializes_with_validation() {
+        let policy: LoadFactorShrinkPolicy = serde_json::from_str(r#"{ "cycles": 4 }"#).unwrap();
+        assert_eq!(policy, LoadFactorShrinkPolicy::new(0.125, 4));
+
+        let err = serde_json::from_str::<LoadFactorShrinkPolicy>(r#"{ "min_load_factor": 2.0 }"#)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("min_load_factor must be between 0.0 and 1.0"));
+    }
+
+    #[test]
+    fn frozen_maps_are_cleared_after_the_last_reader() {
+        let mut map = ReusableHashMap::<u32, String>::default();
+        let mut guard = map.recycle_same();
+        guard.extend((0..32).map(|i| (i, i.to_string())));
+
+        let frozen = guard.freeze();
+        let readers: Vec<Box<dyn Fn(u32) -> usize + '_>> = vec![
+            Box::new(|key| frozen[&key].len()),
+            Box::new(|key| frozen.get(&(key + 1)).map_or(0, String::len)),
+        ];
+        assert_eq!(readers.iter().map(|read| read(9)).sum::<usize>(), 3);
+        drop(readers);
+
+        let lengths = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|reader| {
+                    let frozen = frozen.clone();
+                    scope.spawn(move || frozen[&(reader * 8)].len())
+                })
+                .collect();
+            // The clones keep the map readable after the original is gone.
+            drop(frozen);
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(lengths, [1, 1, 2, 2]);
+
+        assert!(map.recycle_same().is_empty());
+        assert!(map.capacity() >= 32);
+    }
+
+    #[test]
+    fn a_panic_poisons_the_map_until_it_is_reset() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.recycle_collect((0..64).map(|i| (i, i)));
+            let _guard = map.recycle_same();
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(map.is_poisoned());
+
+        // The default policy starts over with an empty table.
+        assert_eq!(map.recycle_same().capacity(), 0);
+        assert!(!map.is_poisoned());
+    }
 }
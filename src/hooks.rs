@@ -0,0 +1,117 @@
+//! Callbacks on the capacity of reusable containers.
+//!
+//! A buffer that was sized up front is only allocation-free as long as it
+//! stays large enough. [`ReusableVec`](crate::ReusableVec),
+//! [`ReusableString`](crate::ReusableString) and
+//! [`ReusableHashMap`](crate::ReusableHashMap) accept hooks that run when
+//! `recycle` hands out a guard (`on_recycle`), when a guard outgrew the
+//! capacity it started with (`on_grow`), and when a capacity policy shrank
+//! the retained allocation as a guard was dropped (`on_shrink`).
+//!
+//! Hooks run on the thread that recycles or drops the guard, so they should
+//! be cheap. They must be `Send` and `Sync`, like the containers holding
+//! them.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::{
+//!     atomic::{AtomicUsize, Ordering},
+//!     Arc,
+//! };
+//! use triple_r::ReusableVec;
+//!
+//! let regrowths = Arc::new(AtomicUsize::new(0));
+//! let mut frame = ReusableVec::<u8>::with_capacity(64);
+//! let counter = Arc::clone(&regrowths);
+//! frame.on_grow(move |from, to| {
+//!     eprintln!(
+//!         "pre-sized frame grew from {} to {}:\n{}",
+//!         from,
+//!         to,
+//!         std::backtrace::Backtrace::capture()
+//!     );
+//!     counter.fetch_add(1, Ordering::Relaxed);
+//! });
+//!
+//! frame.recycle_same().extend_from_slice(&[0; 32]);
+//! frame.recycle_same().extend_from_slice(&[0; 128]);
+//! assert_eq!(regrowths.load(Ordering::Relaxed), 1);
+//! ```
+
+type CapacityHook = Box<dyn Fn(usize) + Send + Sync>;
+type ResizeHook = Box<dyn Fn(usize, usize) + Send + Sync>;
+
+/// The hooks installed in a container.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub(crate) on_recycle: Option<CapacityHook>,
+    pub(crate) on_grow: Option<ResizeHook>,
+    pub(crate) on_shrink: Option<ResizeHook>,
+}
+
+impl Hooks {
+    /// Runs the recycle hook for a guard starting with `capacity`, returning
+    /// what the guard needs to run the others when it is dropped.
+    pub(crate) fn recycle(&self, capacity: usize) -> HookWatch<'_> {
+        if let Some(hook) = &self.on_recycle {
+            hook(capacity);
+        }
+        HookWatch {
+            hooks: self,
+            start_capacity: capacity,
+        }
+    }
+}
+
+/// The hooks of the container a guard came from, and the capacity it
+/// started with.
+pub(crate) struct HookWatch<'a> {
+    hooks: &'a Hooks,
+    start_capacity: usize,
+}
+
+impl HookWatch<'_> {
+    /// Runs the grow and shrink hooks for a guard dropped with `capacity`,
+    /// of which `retained` is kept for the next guard.
+    pub(crate) fn finish(&self, capacity: usize, retained: usize) {
+        if capacity > self.start_capacity {
+            if let Some(hook) = &self.hooks.on_grow {
+                hook(self.start_capacity, capacity);
+            }
+        }
+        if retained < capacity {
+            if let Some(hook) = &self.hooks.on_shrink {
+                hook(capacity, retained);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ReusableString;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn hooks_see_recycles_and_shrinks() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut string = ReusableString::default();
+        string.set_max_retained_capacity(Some(8));
+        let recycles = Arc::clone(&events);
+        string.on_recycle(move |capacity| recycles.lock().unwrap().push(("recycle", capacity)));
+        let shrinks = Arc::clone(&events);
+        string.on_shrink(move |_, to| shrinks.lock().unwrap().push(("shrink", to)));
+
+        string.recycle().push_str("more than eight bytes");
+        string.recycle().push_str("short");
+        assert_eq!(
+            *events.lock().unwrap(),
+            [("recycle", 0), ("shrink", 8), ("recycle", 8)]
+        );
+
+        string.clear_hooks();
+        string.recycle();
+        assert_eq!(events.lock().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,240 @@
+//! Request body aggregation into recycled buffers for
+//! [hyper](https://docs.rs/hyper) and other [`http_body`] users.
+//!
+//! Collecting a request body with `to_bytes()`-style helpers allocates a new
+//! buffer for every request. [`collect_body_reusing`] aggregates the body into
+//! a [`ReusableVec<u8>`] instead, so a connection handling one request after
+//! another keeps a single buffer sized for its largest body.
+//!
+//! Because the whole body ends up in memory, every call takes a size limit and
+//! fails with [`CollectBodyError::TooLarge`] as soon as the body is known to
+//! exceed it.
+//!
+//! This module is only available with the `http-body` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # use bytes::Bytes;
+//! # use http_body::{Body, Frame, SizeHint};
+//! # use std::{convert::Infallible, pin::Pin, task::{Context, Poll}};
+//! # struct Full(Option<Bytes>);
+//! # impl Body for Full {
+//! #     type Data = Bytes;
+//! #     type Error = Infallible;
+//! #     fn poll_frame(mut self: Pin<&mut Self>, _: &mut Context<'_>)
+//! #         -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+//! #         Poll::Ready(self.0.take().map(|data| Ok(Frame::data(data))))
+//! #     }
+//! # }
+//! use triple_r::{http_body::collect_body_reusing, ReusableVec};
+//!
+//! async fn handle(body: impl Body<Error = Infallible>, buffer: &mut ReusableVec<u8>) -> usize {
+//!     let bytes = collect_body_reusing(body, buffer, 1024 * 1024).await.unwrap();
+//!     // ... parse `bytes` ...
+//!     bytes.len()
+//! }
+//! # let _ = handle(Full(Some(Bytes::from_static(b"hello"))), &mut ReusableVec::default());
+//! ```
+use crate::{ReusableVec, ReusableVecGuard};
+use bytes::Buf;
+use http_body::Body;
+use std::{error::Error, fmt, future::poll_fn, pin::Pin};
+
+/// The error returned by [`collect_body_reusing`].
+#[derive(Debug)]
+pub enum CollectBodyError<E> {
+    /// The body itself failed to produce a frame.
+    Body(E),
+    /// The body is larger than the configured limit.
+    TooLarge {
+        /// The limit, in bytes, that was exceeded.
+        limit: usize,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for CollectBodyError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectBodyError::Body(err) => write!(f, "failed to read body: {}", err),
+            CollectBodyError::TooLarge { limit } => {
+                write!(f, "body is larger than the limit of {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CollectBodyError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CollectBodyError::Body(err) => Some(err),
+            CollectBodyError::TooLarge { .. } => None,
+        }
+    }
+}
+
+/// Aggregates every data frame of `body` into the recycled `buffer`,
+/// returning a guard over the collected bytes.
+///
+/// The body's [size hint](Body::size_hint) is used to reserve room up front
+/// and to reject bodies that announce more than `limit` bytes without reading
+/// them. Bodies without an exact size are checked as their frames arrive.
+/// Trailer frames are ignored.
+pub async fn collect_body_reusing<'parent, B: Body>(
+    body: B,
+    buffer: &'parent mut ReusableVec<u8>,
+    limit: usize,
+) -> Result<ReusableVecGuard<'parent, u8, u8>, CollectBodyError<B::Error>> {
+    let mut body = body;
+    // SAFETY: The body is shadowed right away, so it can't be moved again
+    // while it is pinned. `pin!` would do this, but it needs Rust 1.68.
+    let mut body = unsafe { Pin::new_unchecked(&mut body) };
+    let hint = body.size_hint();
+    if hint.lower() > limit as u64 {
+        return Err(CollectBodyError::TooLarge { limit });
+    }
+
+    let mut guard = buffer.recycle();
+    guard.reserve(hint.lower() as usize);
+
+    while let Some(frame) = poll_fn(|cx| body.as_mut().poll_frame(cx)).await {
+        let mut data = match frame.map_err(CollectBodyError::Body)?.into_data() {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        if data.remaining() > limit - guard.len() {
+            return Err(CollectBodyError::TooLarge { limit });
+        }
+        while data.has_remaining() {
+            let chunk = data.chunk();
+            let len = chunk.len();
+            guard.extend_from_slice(chunk);
+            data.advance(len);
+        }
+    }
+
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use http_body::{Frame, SizeHint};
+    use std::{
+        collections::VecDeque,
+        convert::Infallible,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+
+    struct Chunks {
+        frames: VecDeque<Frame<Bytes>>,
+        hint: SizeHint,
+    }
+
+    impl Chunks {
+        fn new(chunks: &[&'static [u8]]) -> Self {
+            Self {
+                frames: chunks
+                    .iter()
+                    .map(|chunk| Frame::data(Bytes::from_static(chunk)))
+                    .collect(),
+                hint: SizeHint::default(),
+            }
+        }
+    }
+
+    impl Body for Chunks {
+        type Data = Bytes;
+        type Error = Infallible;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+            Poll::Ready(self.frames.pop_front().map(Ok))
+        }
+
+        fn size_hint(&self) -> SizeHint {
+            self.hint
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        fn noop_raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                noop_raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+
+        // SAFETY: The vtable functions do nothing and never touch the data
+        // pointer.
+        let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+        let mut cx = Context::from_waker(&waker);
+        let mut future = future;
+        // SAFETY: The future is shadowed right away, so it can't be moved
+        // again while it is pinned.
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn frames_are_collected_in_order() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let body = Chunks::new(&[b"hello", b" ", b"world"]);
+        let bytes = block_on(collect_body_reusing(body, &mut buffer, 64)).unwrap();
+        assert_eq!(bytes.as_slice(), b"hello world");
+    }
+
+    #[test]
+    fn capacity_is_retained_between_bodies() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let body = Chunks::new(&[&[0; 512], &[1; 512]]);
+        let capacity = block_on(collect_body_reusing(body, &mut buffer, 1024))
+            .unwrap()
+            .capacity();
+
+        let bytes = block_on(collect_body_reusing(
+            Chunks::new(&[b"small"]),
+            &mut buffer,
+            1024,
+        ))
+        .unwrap();
+        assert_eq!(bytes.as_slice(), b"small");
+        assert_eq!(bytes.capacity(), capacity);
+    }
+
+    #[test]
+    fn streamed_bodies_over_the_limit_are_rejected() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let body = Chunks::new(&[b"1234", b"5678"]);
+        let result = block_on(collect_body_reusing(body, &mut buffer, 6));
+        assert!(matches!(
+            result,
+            Err(CollectBodyError::TooLarge { limit: 6 })
+        ));
+    }
+
+    #[test]
+    fn announced_bodies_over_the_limit_are_rejected_without_reading() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let mut body = Chunks::new(&[b"1234", b"5678"]);
+        body.hint = SizeHint::with_exact(8);
+        let result = block_on(collect_body_reusing(body, &mut buffer, 6));
+        assert!(matches!(
+            result,
+            Err(CollectBodyError::TooLarge { limit: 6 })
+        ));
+        drop(result);
+        assert_eq!(buffer.recycle::<u8>().capacity(), 0);
+    }
+}
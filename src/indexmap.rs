@@ -0,0 +1,395 @@
+//! Insertion-ordered reusable collections backed by
+//! [indexmap](https://docs.rs/indexmap).
+//!
+//! [`ReusableIndexSet`] is for deduplicating scratch data where the order of
+//! first appearance matters, such as collecting the distinct tokens of a
+//! line in the order they were seen. It follows the same recycling pattern
+//! as [`ReusableHashMap`](crate::ReusableHashMap): the set is cleared when the
+//! guard is dropped, but its entries `Vec` and hash table keep their
+//! capacity.
+//!
+//! This module is only available with the `indexmap` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::indexmap::ReusableIndexSet;
+//!
+//! let mut distinct = ReusableIndexSet::<&'static str>::default();
+//!
+//! for line in [String::from("b a b c a"), String::from("z z y")] {
+//!     let mut tokens = distinct.recycle::<&str>();
+//!     tokens.extend(line.split(' '));
+//!     let first = tokens[0];
+//!     assert!(first == "b" || first == "z");
+//! }
+//! ```
+use crate::{
+    footprint::table_footprint, poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy,
+    ReuseCastInto,
+};
+use indexmap::IndexSet;
+use std::{
+    cell::UnsafeCell,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A wrapper around [`IndexSet`] that allows for reusing its allocation
+/// across compatible element types.
+pub struct ReusableIndexSet<T: 'static, S: 'static + BuildHasher + Default = RandomState> {
+    inner: UnsafeCell<IndexSet<T, S>>,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableIndexSet` is safe to send across threads if its contents are
+// `Send`.
+unsafe impl<T: Send, S: 'static + Send + BuildHasher + Default> Send for ReusableIndexSet<T, S> {}
+
+// The `ReusableIndexSet` is safe to share across threads if its contents are
+// `Send`, because `recycle` requires `&mut self`.
+unsafe impl<T: Send, S: 'static + Send + BuildHasher + Default> Sync for ReusableIndexSet<T, S> {}
+
+impl<T: 'static, S: 'static + BuildHasher + Default> fmt::Debug for ReusableIndexSet<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableIndexSet")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<T: 'static, S: 'static + BuildHasher + Default> Default for ReusableIndexSet<T, S> {
+    /// Creates a new, empty `ReusableIndexSet` with the default hasher.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(IndexSet::default()),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to an `IndexSet`
+/// retrieved from a [`ReusableIndexSet`].
+///
+/// When the guard is dropped, it clears the `IndexSet`, preserving its
+/// allocation for the next cycle.
+pub struct ReusableIndexSetGuard<'parent, T1, T2, S>
+where
+    T1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    inner: *mut IndexSet<T2, S>,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableIndexSet<T1, S>>,
+}
+
+impl<'parent, T1, T2, S> Deref for ReusableIndexSetGuard<'parent, T1, T2, S>
+where
+    T1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    type Target = IndexSet<T2, S>;
+
+    /// Provides immutable access to the underlying `IndexSet`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, T1, T2, S> DerefMut for ReusableIndexSetGuard<'parent, T1, T2, S>
+where
+    T1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Provides mutable access to the underlying `IndexSet`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'parent, T1, T2, S> ReusableIndexSetGuard<'parent, T1, T2, S>
+where
+    T1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// The estimated number of heap bytes the `IndexSet` holds on to,
+    /// counting its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+}
+
+impl<T1, S> ReusableIndexSet<T1, S>
+where
+    T1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Reuses the `IndexSet`'s allocation, returning a guard cast to the
+    /// element type `T2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the set and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, T2>(&'parent mut self) -> ReusableIndexSetGuard<'parent, T1, T2, S>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the set is
+        // always empty between guards, so no `T1` value is ever read as a
+        // `T2`.
+        let inner_ptr = self.inner.get() as *mut IndexSet<T2, S>;
+
+        ReusableIndexSetGuard {
+            inner: inner_ptr,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the set without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the set and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableIndexSetGuard<'_, T1, T1, S> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableIndexSetGuard {
+            inner: self.inner.get(),
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the set and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<T2>(&mut self) -> Result<ReusableIndexSetGuard<'_, T1, T2, S>, PoisonError>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the set was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the set as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the set, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.forget_leaked();
+            *self.inner.get_mut() = IndexSet::default();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the set for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableIndexSetGuard<'_, T1, T2, S>) -> R,
+    ) -> R
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `IndexSet`, which is always empty.
+    pub fn into_inner(mut self) -> IndexSet<T1, S> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// Leaks anything a leaked guard left in the set.
+    ///
+    /// Guards leave the set empty, so any elements in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let set = self.inner.get_mut();
+        if !set.is_empty() {
+            mem::forget(mem::take(set));
+        }
+    }
+
+    /// The number of elements the retained `IndexSet` can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `IndexSet` holds on
+    /// to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+
+    /// Recycles the set and fills it from `iter`, returning the populated
+    /// guard.
+    ///
+    /// Room for the iterator's lower size hint is reserved up front, so a
+    /// warm set usually fills without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use triple_r::indexmap::ReusableIndexSet;
+    ///
+    /// let mut seen = ReusableIndexSet::<u32>::default();
+    /// let guard = seen.recycle_collect([3, 1, 3, 2]);
+    /// assert_eq!(guard.as_slice(), &[3, 1, 2]);
+    /// ```
+    pub fn recycle_collect<T2, I>(&mut self, iter: I) -> ReusableIndexSetGuard<'_, T1, T2, S>
+    where
+        T1: ReuseCastInto<T2>,
+        T2: Eq + Hash,
+        I: IntoIterator<Item = T2>,
+    {
+        let iter = iter.into_iter();
+        let mut guard = self.recycle();
+        guard.reserve(iter.size_hint().0);
+        guard.extend(iter);
+        guard
+    }
+}
+
+impl<T: 'static, S: 'static + BuildHasher + Default> Drop for ReusableIndexSet<T, S> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the set.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, T1, T2, S> Drop for ReusableIndexSetGuard<'parent, T1, T2, S>
+where
+    T1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Clears the `IndexSet` when the guard is dropped, keeping its
+    /// allocation.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let set = unsafe { &mut *self.inner };
+        set.clear();
+    }
+}
+
+// Each entry stores its hash next to the value, and the hash table holds
+// indexes into the entries.
+impl<T, S> MemoryFootprint for IndexSet<T, S> {
+    fn memory_footprint(&self) -> usize {
+        self.capacity() * (mem::size_of::<T>() + mem::size_of::<usize>())
+            + table_footprint::<usize>(self.capacity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insertion_order_and_capacity_are_kept() {
+        let mut set = ReusableIndexSet::<u32>::default();
+        let capacity = {
+            let mut guard = set.recycle::<u32>();
+            guard.extend([3, 1, 3, 2, 1]);
+            assert_eq!(guard.iter().copied().collect::<Vec<_>>(), [3, 1, 2]);
+            guard.capacity()
+        };
+
+        let guard = set.recycle::<u32>();
+        assert!(guard.is_empty());
+        assert_eq!(guard.capacity(), capacity);
+    }
+
+    #[test]
+    fn borrowed_elements_can_be_recycled() {
+        let mut set = ReusableIndexSet::<&'static str>::default();
+        for round in 0..2 {
+            let owned = format!("token {}", round);
+            let mut guard = set.recycle::<&str>();
+            assert!(guard.insert(owned.as_str()));
+            assert!(!guard.insert(owned.as_str()));
+            assert_eq!(guard.get_index_of(owned.as_str()), Some(0));
+        }
+    }
+
+    #[test]
+    fn a_panic_poisons_the_set_until_it_is_reset() {
+        let mut set = ReusableIndexSet::<u32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = set.recycle_same();
+            guard.extend(0..64);
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(set.is_poisoned());
+
+        // The default policy starts over without an allocation.
+        assert_eq!(set.recycle_same().capacity(), 0);
+        assert!(!set.is_poisoned());
+    }
+}
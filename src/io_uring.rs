@@ -0,0 +1,355 @@
+//! A pool of recycled buffers that double as [io_uring] fixed buffers.
+//!
+//! io_uring can skip mapping user memory on every read or write if the
+//! buffers are registered with the ring up front, via
+//! [`Submitter::register_buffers`], and then referred to by their index in
+//! [`ReadFixed`](io_uring::opcode::ReadFixed) and
+//! [`WriteFixed`](io_uring::opcode::WriteFixed) operations. Registering and
+//! unregistering is expensive, so the buffers have to stay put for the life
+//! of the ring.
+//!
+//! [`FixedBufferPool`] owns a set of byte buffers that are allocated once and
+//! never reallocated. They are registered with a ring in one call, and checked
+//! out as [`FixedBufferGuard`]s that know their registration index. Dropping a
+//! guard clears the buffer and returns it to the pool, so the same kernel
+//! registration serves every checkout.
+//!
+//! This module is only available on Linux with the `io-uring` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use io_uring::{opcode, types, IoUring};
+//! use std::{fs::File, os::unix::io::AsRawFd};
+//! use triple_r::io_uring::FixedBufferPool;
+//!
+//! let mut ring = IoUring::new(8)?;
+//! let pool = FixedBufferPool::new(4, 4096);
+//! // SAFETY: `pool` outlives `ring`, so the registered buffers stay valid.
+//! unsafe { pool.register(&ring.submitter())? };
+//!
+//! let file = File::open("Cargo.toml")?;
+//! let mut buffer = pool.checkout().expect("a free buffer");
+//! let read = opcode::ReadFixed::new(
+//!     types::Fd(file.as_raw_fd()),
+//!     buffer.as_mut_ptr(),
+//!     buffer.capacity() as u32,
+//!     buffer.index(),
+//! )
+//! .build();
+//!
+//! // SAFETY: `buffer` is kept alive until the operation completes.
+//! unsafe { ring.submission().push(&read).expect("submission queue is full") };
+//! ring.submit_and_wait(1)?;
+//! let read = ring.completion().next().expect("a completion").result();
+//! // SAFETY: the kernel initialized `read` bytes of the buffer.
+//! unsafe { buffer.set_len(read as usize) };
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [io_uring]: https://docs.rs/io-uring
+//! [`Submitter::register_buffers`]: io_uring::Submitter::register_buffers
+use io_uring::Submitter;
+use std::{
+    cell::UnsafeCell,
+    io,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard},
+};
+
+/// A fixed set of byte buffers that can be registered with an io_uring and
+/// checked out by their registration index.
+///
+/// Every buffer is allocated with the same capacity when the pool is created
+/// and is never grown or reallocated afterwards, so a registration made with
+/// [`register`](FixedBufferPool::register) stays valid for as long as the
+/// pool is alive.
+///
+/// Unlike the other containers in this crate, buffers are checked out through
+/// `&self`. Any number of buffers can be in flight at the same time, which is
+/// what io_uring's asynchronous submissions need.
+pub struct FixedBufferPool {
+    buffers: Box<[UnsafeCell<Vec<u8>>]>,
+    iovecs: Box<[libc::iovec]>,
+    free: Mutex<Vec<u16>>,
+}
+
+// SAFETY: The buffers are plain bytes. The `iovecs` only hold pointers into
+// those buffers, which are never reallocated.
+unsafe impl Send for FixedBufferPool {}
+// SAFETY: A buffer is only ever accessed through the single guard that
+// removed its index from the `free` list, which is protected by a `Mutex`.
+unsafe impl Sync for FixedBufferPool {}
+
+impl FixedBufferPool {
+    /// Allocates `count` buffers of at least `buffer_capacity` bytes each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is zero or `buffer_capacity` is zero.
+    pub fn new(count: u16, buffer_capacity: usize) -> Self {
+        assert!(count > 0, "a pool needs at least one buffer");
+        assert!(buffer_capacity > 0, "buffers need a non-zero capacity");
+
+        let mut buffers: Vec<Vec<u8>> = (0..count)
+            .map(|_| Vec::with_capacity(buffer_capacity))
+            .collect();
+        let iovecs = buffers
+            .iter_mut()
+            .map(|buffer| libc::iovec {
+                iov_base: buffer.as_mut_ptr().cast(),
+                iov_len: buffer.capacity(),
+            })
+            .collect();
+
+        Self {
+            buffers: buffers.into_iter().map(UnsafeCell::new).collect(),
+            iovecs,
+            // Hand out the lowest indexes first.
+            free: Mutex::new((0..count).rev().collect()),
+        }
+    }
+
+    /// Registers every buffer in the pool as a fixed buffer of the ring that
+    /// `submitter` belongs to.
+    ///
+    /// The registration index of each buffer is its
+    /// [`index`](FixedBufferGuard::index).
+    ///
+    /// # Safety
+    ///
+    /// The pool must outlive the registration: either the ring is dropped or
+    /// its buffers are unregistered before the pool is dropped. Otherwise the
+    /// kernel would keep referring to freed memory.
+    pub unsafe fn register(&self, submitter: &Submitter<'_>) -> io::Result<()> {
+        submitter.register_buffers(&self.iovecs)
+    }
+
+    /// Checks out a free buffer, or returns `None` if all of them are in use.
+    ///
+    /// The buffer is empty, but has the full capacity it was registered with.
+    pub fn checkout(&self) -> Option<FixedBufferGuard<'_>> {
+        let index = self.lock().pop()?;
+        Some(FixedBufferGuard {
+            pool: self,
+            index,
+            inner: self.buffers[index as usize].get(),
+            _not_send: PhantomData,
+        })
+    }
+
+    /// The number of buffers in the pool.
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// The number of buffers that are currently free.
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<u16>> {
+        // The free list is always in a consistent state, even if a thread
+        // panicked while holding the lock.
+        self.free
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A RAII guard over a buffer checked out from a [`FixedBufferPool`].
+///
+/// The guard dereferences to the initialized part of the buffer. Because the
+/// buffer is registered with the kernel, it can never be grown: writes beyond
+/// its [`capacity`](FixedBufferGuard::capacity) panic instead of
+/// reallocating.
+///
+/// When the guard is dropped, the buffer is cleared and returned to the pool.
+/// The guard must therefore be kept alive until every io_uring operation that
+/// refers to it has completed.
+pub struct FixedBufferGuard<'pool> {
+    pool: &'pool FixedBufferPool,
+    index: u16,
+    inner: *mut Vec<u8>,
+    _not_send: PhantomData<*mut Vec<u8>>,
+}
+
+impl<'pool> FixedBufferGuard<'pool> {
+    /// The index this buffer was registered under, to be passed as the
+    /// `buf_index` of fixed-buffer operations.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// The registered capacity of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.pool.iovecs[self.index as usize].iov_len
+    }
+
+    /// Returns a pointer to the start of the buffer, for use in fixed-buffer
+    /// operations.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.pool.iovecs[self.index as usize].iov_base.cast()
+    }
+
+    /// Sets the length of the initialized part of the buffer, typically to
+    /// the number of bytes a completed read produced.
+    ///
+    /// # Safety
+    ///
+    /// `len` must not exceed [`capacity`](FixedBufferGuard::capacity), and the
+    /// first `len` bytes of the buffer must be initialized.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.capacity());
+        (*self.inner).set_len(len);
+    }
+
+    /// Appends `data` to the initialized part of the buffer, typically to
+    /// prepare a write.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer does not have room for `data`, since growing it
+    /// would invalidate its registration.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        assert!(
+            data.len() <= self.capacity() - self.len(),
+            "fixed buffers cannot grow beyond their registered capacity"
+        );
+        // SAFETY: The capacity check above guarantees that this does not
+        // reallocate.
+        unsafe { (*self.inner).extend_from_slice(data) }
+    }
+
+    /// Clears the initialized part of the buffer, keeping its capacity.
+    pub fn clear(&mut self) {
+        // SAFETY: Clearing never reallocates.
+        unsafe { (*self.inner).clear() }
+    }
+}
+
+impl<'pool> Deref for FixedBufferGuard<'pool> {
+    type Target = [u8];
+
+    /// Provides immutable access to the initialized part of the buffer.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The index was removed from the free list when this guard was
+        // created, so no other guard can access the same buffer.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'pool> DerefMut for FixedBufferGuard<'pool> {
+    /// Provides mutable access to the initialized part of the buffer.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. Only a slice is handed
+        // out, so the `Vec` itself can never be reallocated through it.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'pool> Drop for FixedBufferGuard<'pool> {
+    /// Clears the buffer and returns it to the pool.
+    fn drop(&mut self) {
+        self.clear();
+        self.pool.lock().push(self.index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkouts_hand_out_distinct_indexes() {
+        let pool = FixedBufferPool::new(2, 64);
+        let first = pool.checkout().unwrap();
+        let second = pool.checkout().unwrap();
+        assert_eq!((first.index(), second.index()), (0, 1));
+        assert!(pool.checkout().is_none());
+        assert_ne!(
+            first.pool.iovecs[0].iov_base,
+            second.pool.iovecs[1].iov_base
+        );
+
+        drop(first);
+        assert_eq!(pool.available(), 1);
+        assert_eq!(pool.checkout().unwrap().index(), 0);
+    }
+
+    #[test]
+    fn a_poisoned_free_list_keeps_working() {
+        let pool = FixedBufferPool::new(1, 16);
+        let buffer = pool.checkout().unwrap();
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _free = pool.free.lock();
+            panic!("poisoning the free list");
+        }));
+        drop(buffer);
+        assert_eq!(pool.available(), 1);
+        assert!(pool.checkout().is_some());
+    }
+
+    #[test]
+    fn buffers_are_cleared_and_never_reallocated() {
+        let pool = FixedBufferPool::new(1, 16);
+        let ptr = {
+            let mut buffer = pool.checkout().unwrap();
+            buffer.extend_from_slice(b"hello");
+            assert_eq!(&*buffer, b"hello");
+            buffer.as_mut_ptr()
+        };
+
+        let mut buffer = pool.checkout().unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_mut_ptr(), ptr);
+        assert!(buffer.capacity() >= 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "registered capacity")]
+    fn writes_beyond_the_capacity_panic() {
+        let pool = FixedBufferPool::new(1, 4);
+        let mut buffer = pool.checkout().unwrap();
+        let too_long = vec![0; buffer.capacity() + 1];
+        buffer.extend_from_slice(&too_long);
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn fixed_reads_land_in_checked_out_buffers() {
+        use io_uring::{opcode, types, IoUring};
+        use std::{fs::File, os::unix::io::AsRawFd};
+
+        // Sandboxes and old kernels may not allow creating a ring at all.
+        let mut ring = match IoUring::new(4) {
+            Ok(ring) => ring,
+            Err(_) => return,
+        };
+        let pool = FixedBufferPool::new(2, 64);
+        // SAFETY: `pool` outlives `ring`.
+        if unsafe { pool.register(&ring.submitter()) }.is_err() {
+            return;
+        }
+
+        let file = File::open("tests/random.txt").unwrap();
+        let _unused = pool.checkout().unwrap();
+        let mut buffer = pool.checkout().unwrap();
+        let read = opcode::ReadFixed::new(
+            types::Fd(file.as_raw_fd()),
+            buffer.as_mut_ptr(),
+            5,
+            buffer.index(),
+        )
+        .build();
+        // SAFETY: `buffer` stays alive until the read has completed.
+        unsafe { ring.submission().push(&read).unwrap() };
+        ring.submit_and_wait(1).unwrap();
+        let result = ring.completion().next().unwrap().result();
+        assert_eq!(result, 5);
+        // SAFETY: The kernel initialized `result` bytes.
+        unsafe { buffer.set_len(result as usize) };
+        assert_eq!(&*buffer, b"HELLO");
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{PooledVec, ReusableVecGuard};
+use std::{error::Error, fmt};
+
+/// A recycled byte buffer lent out to foreign code as a raw pointer and a
+/// capacity.
+///
+/// Many C libraries fill a caller-provided buffer and report how many bytes
+/// they wrote. A lease, obtained with [`ReusableVecGuard::lease`] or
+/// [`PooledVec::lease`], formalizes that pattern: the buffer is emptied and
+/// grown to the requested capacity, its pointer and capacity are handed out,
+/// and [`reclaim`](BufferLease::reclaim) re-establishes the `Vec` with the
+/// reported length once the foreign code is done.
+///
+/// The lease mutably borrows the guard, so the `Vec` cannot be touched, and in
+/// particular cannot reallocate, while the pointer is out. The lease itself is
+/// the token that has to be handed back to reclaim the buffer. If it is
+/// dropped without being reclaimed, the buffer simply stays empty.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableVec;
+///
+/// /// Stands in for a C function that writes up to `cap` bytes into `buf`
+/// /// and returns how many it wrote.
+/// unsafe extern "C" fn fill(buf: *mut u8, cap: usize) -> usize {
+///     let message = b"hello from C";
+///     let len = message.len().min(cap);
+///     std::ptr::copy_nonoverlapping(message.as_ptr(), buf, len);
+///     len
+/// }
+///
+/// let mut reusable = ReusableVec::<u8>::default();
+/// let mut buffer = reusable.recycle();
+///
+/// let mut lease = buffer.lease(64);
+/// // SAFETY: `fill` writes at most `capacity` bytes to the pointer.
+/// let written = unsafe { fill(lease.as_mut_ptr(), lease.capacity()) };
+/// // SAFETY: `fill` initialized the first `written` bytes.
+/// unsafe { lease.reclaim(written) }.unwrap();
+///
+/// assert_eq!(buffer.as_slice(), b"hello from C");
+/// ```
+#[derive(Debug)]
+pub struct BufferLease<'guard> {
+    vec: &'guard mut Vec<u8>,
+}
+
+impl<'guard> BufferLease<'guard> {
+    fn new(vec: &'guard mut Vec<u8>, min_capacity: usize) -> Self {
+        vec.clear();
+        vec.reserve(min_capacity);
+        Self { vec }
+    }
+
+    /// Returns the start of the lent buffer.
+    ///
+    /// The pointer stays valid, and the buffer is not touched by Rust, until
+    /// the lease is reclaimed or dropped.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.vec.as_mut_ptr()
+    }
+
+    /// The number of bytes that may be written to the lent buffer.
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    /// Ends the lease, making the first `len` bytes of the buffer the
+    /// contents of the `Vec`.
+    ///
+    /// Returns an error, and leaves the buffer empty, if `len` exceeds the
+    /// lent [`capacity`](BufferLease::capacity).
+    ///
+    /// # Safety
+    ///
+    /// If `len` does not exceed the capacity, the first `len` bytes of the
+    /// buffer must have been initialized, typically by the foreign code the
+    /// buffer was lent to.
+    pub unsafe fn reclaim(self, len: usize) -> Result<(), LeaseError> {
+        let capacity = self.vec.capacity();
+        if len > capacity {
+            return Err(LeaseError { len, capacity });
+        }
+        self.vec.set_len(len);
+        Ok(())
+    }
+}
+
+/// The error returned by [`BufferLease::reclaim`] when the reported length
+/// does not fit in the lent buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaseError {
+    len: usize,
+    capacity: usize,
+}
+
+impl LeaseError {
+    /// The length that was passed to [`reclaim`](BufferLease::reclaim).
+    pub fn reclaimed_len(&self) -> usize {
+        self.len
+    }
+
+    /// The capacity of the lent buffer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl fmt::Display for LeaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "reclaimed length {} exceeds the lent capacity of {} bytes",
+            self.len, self.capacity
+        )
+    }
+}
+
+impl Error for LeaseError {}
+
+impl<'parent, T1> ReusableVecGuard<'parent, T1, u8>
+where
+    T1: 'static,
+{
+    /// Lends the buffer out as a raw pointer with room for at least
+    /// `min_capacity` bytes.
+    ///
+    /// The buffer is emptied first. See [`BufferLease`] for how to get the
+    /// written bytes back.
+    pub fn lease(&mut self, min_capacity: usize) -> BufferLease<'_> {
+        BufferLease::new(self, min_capacity)
+    }
+}
+
+impl<'pool> PooledVec<'pool, u8> {
+    /// Lends the buffer out as a raw pointer with room for at least
+    /// `min_capacity` bytes.
+    ///
+    /// The buffer is emptied first. See [`BufferLease`] for how to get the
+    /// written bytes back.
+    pub fn lease(&mut self, min_capacity: usize) -> BufferLease<'_> {
+        BufferLease::new(self, min_capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ReusableVec, VecPool};
+    use std::ptr;
+
+    #[test]
+    fn reclaimed_bytes_become_the_contents() {
+        let mut reusable = ReusableVec::<u8>::default();
+        let mut buffer = reusable.recycle();
+        buffer.extend_from_slice(b"stale");
+
+        let mut lease = buffer.lease(16);
+        assert!(lease.capacity() >= 16);
+        unsafe {
+            ptr::copy_nonoverlapping(b"fresh".as_ptr(), lease.as_mut_ptr(), 5);
+            lease.reclaim(5).unwrap();
+        }
+        assert_eq!(buffer.as_slice(), b"fresh");
+    }
+
+    #[test]
+    fn lengths_beyond_the_capacity_are_rejected() {
+        let pool = VecPool::<u8>::new();
+        let mut buffer = pool.get();
+        let lease = buffer.lease(8);
+        let capacity = lease.capacity();
+
+        let err = unsafe { lease.reclaim(capacity + 1) }.unwrap_err();
+        assert_eq!(err.reclaimed_len(), capacity + 1);
+        assert_eq!(err.capacity(), capacity);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn dropped_leases_leave_the_buffer_empty() {
+        let mut reusable = ReusableVec::<u8>::default();
+        let mut buffer = reusable.recycle();
+        buffer.push(1);
+        buffer.lease(4);
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 4);
+    }
+}
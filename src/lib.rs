@@ -46,11 +46,46 @@
 //!
 //! ## Key Features
 //!
-//! - **Allocation Reuse:** Provides [`ReusableHashMap`], [`ReusableVec`], and [`ReusableString`] to avoid repeated memory allocations.
-//! - **Type Casting:** Safely cast the types of the stored elements between uses. For example, a `ReusableHashMap<&'static str, _>` can be recycled into a guard for a `HashMap<&'a str, _>`.
+//! - **Allocation Reuse:** Provides [`ReusableHashMap`], [`ReusableVec`], [`ReusableVecDeque`], [`ReusableBinaryHeap`], and [`ReusableString`] to avoid repeated memory allocations. [`ReusableBTreeMap`] and [`ReusableBTreeSet`] bring the same recycling pattern to ordered collections, and [`ReusableNestedVec`] keeps the inner allocations of a `Vec<Vec<T>>` or `Vec<String>` as well as the outer one. Elements that implement [`Reset`] can also be kept alive and reset in place with `ReusableVec::recycle_resettable`. A `ReusableString` can lend its allocation out as a `Vec<u8>` with `recycle_as_bytes`, for phases that run binary encoders. Byte guards can also hand out their spare capacity through a [`SpareCapacity`] cursor with `fill_spare_capacity`, so readers fill recycled buffers without zeroing them first. [`ReusableBuffer`] keeps that one allocation behind a `recycle_str` and a `recycle_bytes` view, of which only one exists at a time. Custom containers that implement [`Recyclable`] get the same treatment through [`Reusable`]. [`BytePool`] serves byte buffers, strings, and aligned buffers from one retained slab, and [`ReusableRawBuffer`] reuses one aligned allocation as scratch of any size and alignment. [`ReusableAnyVec`] goes further and recycles one allocation as a `Vec` of a different element type every cycle. [`ReusableSmallString`] keeps short strings inline and only recycles a heap allocation for long ones. [`ReusableCStringBuf`] builds NUL-terminated strings for C without a new `CString` per call. [`ScratchSet`] bundles the `String`, `Vec<u8>`, and header map that most request handlers need behind a single `recycle`. A [`ClearPolicy`] can move the clearing of a `ReusableVec` or `ReusableHashMap` from the end of a guard's use to the next `recycle`.
+//! - **Shared Pools:** [`VecPool`] hands out recycled `Vec` allocations through `&self`, so threads and components that need their own buffers at the same time can share one set of allocations. [`ReusablePool`] does the same for any `Recyclable` container, such as a scratch `HashMap` per worker, and its guards own a handle to the pool, so they can be moved into spawned threads and tasks. [`AnyPool`] keeps a `ReusablePool` per container type, so framework code can reuse buffers of types only its callers know, with `get::<Vec<MyRow>>()`. [`GlobalPool`] is a `ReusablePool` that can be declared as a `static`, so a library can reuse scratch buffers internally without taking one as a parameter in every function. [`SharedReusable`] retains a single container behind an `Arc` and hands it out as an owned, `Send` guard that can be held across `.await` points. [`RecycleCell`] hands a single container out through `&self` and reports where it is held when two callers collide, and [`LocalReusable`] recycles one through `&self` the same way, like a `RefCell`. [`SyncRecycleCell`] does the same across threads with an atomic flag, also reporting where it is held, and its `try_recycle` returns `None` instead of blocking when the container is taken.
+//! - **Warm Starts:** A [`PoolProfile`] records how many buffers a pool retains and at which capacities, so a new pool can be warmed to steady state right after startup. A single `ReusableVec`, `ReusableString`, or `ReusableHashMap` can be sized up front with `with_capacity` or grown between cycles with `prewarm`, and shrunk again with `shrink_to` or `shrink_to_fit` after an unusually large batch, or capped with `set_max_retained_capacity` so that happens on its own. An [`AdaptiveCapacityPolicy`] does it without a fixed number, converging on a percentile of recent usage. A collection populated at startup can be retired into one with `From`, keeping its allocation. Every reusable type and guard reports an estimate of the heap bytes it retains through `memory_footprint`, for capacity planning. A [`BufferRegistry`] owns the scratch buffers of a whole application under names like `"parser.tokens"`, so they can be reported on and trimmed together. `on_recycle`, `on_grow`, and `on_shrink` [hooks] run a callback whenever a guard is handed out, outgrows the retained capacity, or is shrunk, for example to log a pre-sized buffer that reallocated.
+//! - **Type Casting:** Safely cast the types of the stored elements between uses. For example, a `ReusableHashMap<&'static str, _>` can be recycled into a guard for a `HashMap<&'a str, _>`. With the `derive` feature, `#[derive(ReuseCast)]` enables casting for newtypes and for structs that borrow with a lifetime, such as `Span<'a>`.
 //! - **Compile-Time Safety:** The API is designed to prevent common misuses at compile time, such as having multiple mutable references to the same underlying collection.
-//! - **Safety Assured:** The internal use of `unsafe` code is minimal and has been carefully designed and verified with `cargo miri` to ensure it is free of undefined behavior.
-//! - **Drop-In Replacement:** The guard objects implement `Deref` and `DerefMut`, so you can use them just like standard `HashMap`, `Vec`, and `String`.
+//! - **Safety Assured:** The internal use of `unsafe` code is minimal and has been carefully designed and verified with `cargo miri` to ensure it is free of undefined behavior. If a panic unwinds through a guard, the container is poisoned, like a `Mutex`, and the next `recycle` either resets it or refuses with a [`PoisonError`].
+//! - **Drop-In Replacement:** The guard objects implement `Deref` and `DerefMut`, so you can use them just like standard `HashMap`, `Vec`, and `String`. They also implement `Debug`, `AsRef`, and `Borrow`, and compare and hash like the collections they hold.
+//!
+//! ## Optional Features
+//!
+//! Integrations with other crates are available behind cargo features:
+//!
+//! | Feature | Description |
+//! | ------- | ----------- |
+//! | `arrow` | Recycled value and offset buffers for Arrow array builders (`triple_r::arrow`). |
+//! | `prost` | Protobuf encoding into recycled byte buffers (`triple_r::prost`). |
+//! | `bincode` | Serialization into recycled byte buffers with bincode (`triple_r::bincode`). |
+//! | `postcard` | Serialization into recycled byte buffers with postcard (`triple_r::postcard`). |
+//! | `quick-xml` | Streaming XML reading over recycled event and attribute buffers (`triple_r::quick_xml`). |
+//! | `http-body` | Aggregation of `http-body` (hyper) request bodies into recycled buffers (`triple_r::http_body`). |
+//! | `io-uring` | Pool of recycled buffers registered as `io_uring` fixed buffers, Linux only (`triple_r::io_uring`). |
+//! | `zeroize` | Drop policy that zeroes recycled `Vec` and `String` allocations (`triple_r::zeroize`). |
+//! | `extendr` | Keep recyclers alive across R calls in external pointers, requires R (`triple_r::extendr`). |
+//! | `ffi` | C interface for checking byte buffers out of a shared `VecPool`, and for reusing a single byte buffer across calls (`triple_r::ffi`). |
+//! | `stats` | Usage statistics on recyclers, including reallocations and an estimate of the allocations avoided, and a report of the ones retaining memory they do not use. |
+//! | `tracing` | Emits `tracing` events, under the `triple_r` target, when `ReusableVec`, `ReusableString`, and `ReusableHashMap` guards are handed out, outgrow the retained capacity, or are shrunk. |
+//! | `parking_lot` | Backs `VecPool`, `ReusablePool` and `SharedReusable` with a `parking_lot` mutex instead of the standard library one. Pools built with `PoolBuilder::parking_lot(false)` keep the standard one. |
+//! | `rayon` | Collects parallel iterators into pooled `Vec`s with `par_collect_reusing`, and gives each rayon job a scratch container from a `ReusablePool` with `for_each_with_scratch`. |
+//! | `serde` | Deserializes `PoolConfig`, `LoadFactorShrinkPolicy`, `PoolProfile`, and `DropPolicy` from configuration files, deserializes straight into recycled `Vec`s and `HashMap`s, and serializes guards (`triple_r::serde`). |
+//! | `alloc-tracking` | A counting global allocator with `measure_allocs` and `assert_no_alloc!` for proving hot paths allocation-free in tests. |
+//! | `indexmap` | Adds `ReusableIndexSet`, an insertion-ordered set backed by `indexmap::IndexSet`. |
+//! | `hashbrown` | Adds a `ReusableHashMap` backed by `hashbrown::HashMap`, whose guards expose `hashbrown`-only APIs like `entry_ref`, next to the standard one (`triple_r::hashbrown`). |
+//! | `allocator_api` | Adds `ReusableAllocVec`, a `ReusableVec` over a custom allocator. Requires a nightly compiler. |
+//! | `derive` | Adds `#[derive(Recycle)]` for structs of reusable containers and `#[derive(ReuseCast)]` for newtypes and lifetime-generic structs. |
+//! | `bytemuck` | Adds `ReusableVec::try_recycle_cast`, which recycles a `Vec` of one `Pod` type as another after checking their layouts. |
+//! | `bytes` | Implements `bytes::BufMut` for `ReusableVecGuard<_, u8>`, so tokio, prost, and other `BufMut` writers can fill recycled buffers directly, and `Recyclable` for `BytesMut`. |
+//! | `defer-drop` | Drops the elements of cleared `ReusableVec` guards on a background thread (`triple_r::defer_drop`). |
+//! | `crossbeam-queue` | Adds `ConcurrentPool`, a lock-free pool of recycled containers that can be cloned into every thread and task (`triple_r::crossbeam_queue`). |
+//! | `tokio` | Async reads into recycled buffers, and `AsyncPool`, a pool whose checkout waits while too many buffers are out (`triple_r::tokio`). Enables `bytes`. |
+//! | `futures` | Batches the items of a `Stream` into pooled `Vec`s with `recycled_chunks` (`triple_r::futures`). |
 //!
 //! ## Usage
 //!
@@ -158,12 +193,131 @@
 //! 1.  **Exclusive Access:** The `recycle()` method requires a mutable reference (`&mut self`) to the [`ReusableHashMap`], [`ReusableVec`], or [`ReusableString`]. This statically guarantees that only one guard can be active at a time, preventing data races.
 //! 2.  **Lifetime Management:** The returned guard is tied to the lifetime of the `&mut self` borrow, ensuring it cannot outlive the container it references.
 //! 3.  **Miri Verification:** The entire codebase is tested with `cargo miri`, a tool that detects undefined behavior in `unsafe` Rust code. All tests pass under Miri, giving strong confidence in the library's soundness.
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+pub mod adaptive;
+#[cfg(feature = "alloc-tracking")]
+pub mod alloc_tracking;
+#[cfg(feature = "allocator_api")]
+pub mod allocator_api;
+pub mod any_pool;
+pub mod any_vec;
+pub mod arena;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod binary_heap;
+#[cfg(feature = "bincode")]
+pub mod bincode;
+pub mod btreemap;
+pub mod btreeset;
+pub mod buffer;
+pub mod byte_pool;
+#[cfg(feature = "bytemuck")]
+pub mod bytemuck;
+#[cfg(feature = "bytes")]
+pub mod bytes;
+pub mod cell;
+pub mod clear_policy;
+#[cfg(feature = "crossbeam-queue")]
+pub mod crossbeam_queue;
+pub mod cstring;
+#[cfg(feature = "defer-drop")]
+pub mod defer_drop;
+#[cfg(feature = "extendr")]
+pub mod extendr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod footprint;
+#[cfg(feature = "futures")]
+pub mod futures;
+pub mod global_pool;
+#[cfg(feature = "hashbrown")]
+pub mod hashbrown;
 pub mod hashmap;
+pub mod hooks;
+#[cfg(feature = "http-body")]
+pub mod http_body;
+#[cfg(feature = "indexmap")]
+pub mod indexmap;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod io_uring;
+pub mod lease;
+pub mod nested_vec;
+pub mod poison;
+pub mod pool;
+#[cfg(feature = "postcard")]
+pub mod postcard;
+pub mod profile;
+#[cfg(feature = "prost")]
+pub mod prost;
+#[cfg(feature = "quick-xml")]
+pub mod quick_xml;
+pub mod raw_buffer;
+#[cfg(feature = "rayon")]
+pub mod rayon;
+pub mod recycle;
+pub mod registry;
+pub mod reset;
+pub mod reusable;
+pub mod reusable_pool;
+pub mod scratch;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod shared;
+pub mod small_string;
+pub mod spare;
+#[cfg(feature = "stats")]
+pub mod stats;
 pub mod string;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+#[cfg(feature = "tracing")]
+mod trace;
 pub mod vec;
-pub use hashmap::{ReusableHashMap, ReusableHashMapGuard};
-pub use string::{ReusableString, ReusableStringGuard};
-pub use vec::{ReusableVec, ReusableVecGuard};
+pub mod vecdeque;
+#[cfg(feature = "zeroize")]
+pub mod zeroize;
+pub use adaptive::AdaptiveCapacityPolicy;
+pub use any_pool::AnyPool;
+pub use any_vec::{ReusableAnyVec, ReusableAnyVecGuard};
+pub use arena::{ArenaId, ReusableGenerationalArena, ReusableGenerationalArenaGuard};
+pub use binary_heap::{ReusableBinaryHeap, ReusableBinaryHeapGuard, ReusableBinaryHeapIntoIter};
+pub use btreemap::{ReusableBTreeMap, ReusableBTreeMapGuard};
+pub use btreeset::{ReusableBTreeSet, ReusableBTreeSetGuard};
+pub use buffer::ReusableBuffer;
+pub use byte_pool::{BytePool, BytePoolGuard, SlabBytes, SlabString};
+pub use cell::{
+    AlreadyRecycled, LocalReusable, LocalReusableGuard, RecycleCell, RecycleCellGuard,
+    SyncRecycleCell, SyncRecycleCellGuard, SyncRecycleGuard,
+};
+pub use clear_policy::ClearPolicy;
+pub use cstring::{InteriorNulError, ReusableCStringBuf, ReusableCStringBufGuard};
+pub use footprint::MemoryFootprint;
+pub use global_pool::{GlobalPool, GlobalPoolGuard};
+pub use hashmap::{
+    LoadFactorShrinkPolicy, ReusableHashMap, ReusableHashMapFrozen, ReusableHashMapGuard,
+    ReusableHashMapIntoIter,
+};
+pub use lease::{BufferLease, LeaseError};
+pub use nested_vec::{ReusableNestedVec, ReusableNestedVecGuard};
+pub use poison::{PoisonError, PoisonPolicy};
+pub use pool::{PoolBuilder, PoolConfig, PooledVec, VecPool};
+pub use profile::{ParseProfileError, PoolProfile};
+pub use raw_buffer::{ReusableRawBuffer, ReusableRawBufferGuard};
+pub use recycle::Recycle;
+pub use registry::{BufferRegistry, RegistryBuffer};
+pub use reset::Reset;
+pub use reusable::{Recyclable, Reusable, ReusableGuard};
+pub use reusable_pool::{ReusablePool, ReusablePoolGuard};
+pub use scratch::{ScratchSet, ScratchSetGuard};
+pub use shared::SharedReusable;
+pub use small_string::{ReusableSmallString, ReusableSmallStringGuard};
+pub use spare::SpareCapacity;
+pub use string::{ReusableString, ReusableStringBytesGuard, ReusableStringGuard};
+#[cfg(feature = "derive")]
+pub use triple_r_derive::{Recycle, ReuseCast};
+pub use vec::{ReusableVec, ReusableVecGuard, ReusableVecIntoIter, ReusableVecResetGuard};
+pub use vecdeque::{ReusableVecDeque, ReusableVecDequeGuard, ReusableVecDequeIntoIter};
 
 /// A trait that indicates that a type can be safely cast into another type for the
 /// purpose of reusing a collection's allocation.
@@ -174,20 +328,115 @@ pub use vec::{ReusableVec, ReusableVecGuard};
 /// through pointer casting. Implementers must guarantee that it is safe to
 /// transmute a container of `Self` (e.g., `Vec<Self>`) into a container of `T`
 /// (e.g., `Vec<T>`).
+/// Since the casts are applied component-wise, this includes wrappers such
+/// as `Option<Self>`, so `Self` and `T` must agree on their niches as well as
+/// their size and alignment.
 ///
 /// For this library, this is primarily used to change the lifetimes of references
 /// (e.g., from `&'static str` to `&'a str`), which is safe because the
 /// collection is cleared before it is used with the new type, and the new
 /// lifetimes are constrained by the guard's lifetime.
+/// Nothing depends on the guard's `Drop` running: if a guard is leaked with
+/// [`mem::forget`](std::mem::forget), the next `recycle` leaks the elements
+/// it left behind instead of dropping them, since they may borrow data that
+/// is gone by then.
 ///
 /// For types with the same memory layout (e.g., primitive integers), this is
 /// also safe.
+///
+/// Prefer [`impl_reuse_cast!`] over writing implementations by hand, since it
+/// checks the layouts of both types at compile time.
 pub unsafe trait ReuseCastInto<T> {}
 
+/// Implements [`ReuseCastInto`] between two types, checking at compile time
+/// that their layouts match.
+///
+/// `impl_reuse_cast!(A => B)` emits `unsafe impl ReuseCastInto<B> for A`
+/// together with constant assertions that `A` and `B` have the same size and
+/// alignment, that `Option<A>` and `Option<B>` have the same size, and that
+/// they agree on whether they need to be dropped. A
+/// mismatch is a compile error instead of undefined behavior. Several pairs
+/// can be given at once, separated by commas.
+///
+/// The assertions catch the most common mistakes, but they cannot prove that
+/// the field layouts of two types line up, so the types should still be
+/// layout-compatible by construction, for example through
+/// `#[repr(transparent)]` or `#[repr(C)]` with matching fields.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{impl_reuse_cast, ReusableVec};
+///
+/// #[repr(transparent)]
+/// struct Meters(f64);
+///
+/// #[repr(transparent)]
+/// struct Feet(f64);
+///
+/// impl_reuse_cast!(Meters => Feet, Feet => Meters);
+///
+/// let mut distances = ReusableVec::<Meters>::default();
+/// distances.recycle::<Feet>().push(Feet(3.0));
+/// ```
+///
+/// Types of different sizes are rejected:
+///
+/// ```compile_fail
+/// triple_r::impl_reuse_cast!(u32 => u64);
+/// ```
+#[macro_export]
+macro_rules! impl_reuse_cast {
+    ($($from:ty => $to:ty),+ $(,)?) => {
+        $(
+            const _: () = {
+                assert!(
+                    ::core::mem::size_of::<$from>() == ::core::mem::size_of::<$to>(),
+                    concat!(
+                        "`", stringify!($from), "` and `", stringify!($to),
+                        "` have different sizes"
+                    ),
+                );
+                assert!(
+                    ::core::mem::align_of::<$from>() == ::core::mem::align_of::<$to>(),
+                    concat!(
+                        "`", stringify!($from), "` and `", stringify!($to),
+                        "` have different alignments"
+                    ),
+                );
+                assert!(
+                    ::core::mem::size_of::<::core::option::Option<$from>>()
+                        == ::core::mem::size_of::<::core::option::Option<$to>>(),
+                    concat!(
+                        "`Option<", stringify!($from), ">` and `Option<", stringify!($to),
+                        ">` have different sizes"
+                    ),
+                );
+                assert!(
+                    ::core::mem::needs_drop::<$from>() == ::core::mem::needs_drop::<$to>(),
+                    concat!(
+                        "only one of `", stringify!($from), "` and `", stringify!($to),
+                        "` needs to be dropped"
+                    ),
+                );
+            };
+
+            // SAFETY: The assertions above check that both types have the
+            // same layout, and the caller vouches for their fields.
+            unsafe impl $crate::ReuseCastInto<$to> for $from {}
+        )+
+    };
+}
+
 // This implementation allows reusing a map of references with a shorter lifetime.
 // For example, a `HashMap<&'static str, _>` can be reused as a `HashMap<&'a str, _>`.
 unsafe impl<T: ?Sized> ReuseCastInto<&T> for &T {}
 
+// Mutable references can have their lifetime changed the same way. Only the
+// lifetime changes: the referenced type stays the same, since `&mut T` is
+// invariant in `T`.
+unsafe impl<T: ?Sized> ReuseCastInto<&mut T> for &mut T {}
+
 macro_rules! impl_reuse_cast_into_for_primitive {
     ($($t:ty),*) => {
         $(
@@ -196,12 +445,178 @@ macro_rules! impl_reuse_cast_into_for_primitive {
     };
 }
 
-unsafe impl<T> ReuseCastInto<Vec<T>> for Vec<T> {}
+// A `Vec` of castable elements has the same layout, and frees its buffer with
+// the same layout, whichever element type it is used as. This lets nested
+// values such as a `Vec<&'static str>` stored in a map be reused as a
+// `Vec<&'a str>`.
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<Vec<T2>> for Vec<T1> {}
+
+// The other standard collections can be cast the same way, so they can be
+// nested as the values of a reusable map. The hasher is kept as it is, since
+// it does not depend on the element types.
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<std::collections::VecDeque<T2>>
+    for std::collections::VecDeque<T1>
+{
+}
+unsafe impl<K1, V1, K2, V2, S> ReuseCastInto<std::collections::HashMap<K2, V2, S>>
+    for std::collections::HashMap<K1, V1, S>
+where
+    K1: ReuseCastInto<K2>,
+    V1: ReuseCastInto<V2>,
+{
+}
+unsafe impl<T1: ReuseCastInto<T2>, T2, S> ReuseCastInto<std::collections::HashSet<T2, S>>
+    for std::collections::HashSet<T1, S>
+{
+}
+unsafe impl<K1, V1, K2, V2> ReuseCastInto<std::collections::BTreeMap<K2, V2>>
+    for std::collections::BTreeMap<K1, V1>
+where
+    K1: ReuseCastInto<K2>,
+    V1: ReuseCastInto<V2>,
+{
+}
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<std::collections::BTreeSet<T2>>
+    for std::collections::BTreeSet<T1>
+{
+}
+
+// `Reverse` is `repr(transparent)`, so it can be cast whenever its contents
+// can. This lets a `ReusableBinaryHeap` be used as a min-heap.
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<std::cmp::Reverse<T2>>
+    for std::cmp::Reverse<T1>
+{
+}
+
+// `Option<T1>` and `Option<T2>` share a layout as long as `T1` and `T2` do,
+// including any niche, which `ReuseCastInto` implementations promise. This
+// lets a `Vec<Option<&'static str>>` be reused as a `Vec<Option<&'a str>>`.
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<Option<T2>> for Option<T1> {}
+
+// A `Box` is a pointer to its contents, and `T1` and `T2` share a layout, so
+// the box is freed with the same layout whichever type it is dropped as.
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<Box<T2>> for Box<T1> {}
+
+// Like `Option`, a `Result` keeps its layout when both of its components do.
+unsafe impl<T1, T2, E1, E2> ReuseCastInto<Result<T2, E2>> for Result<T1, E1>
+where
+    T1: ReuseCastInto<T2>,
+    E1: ReuseCastInto<E2>,
+{
+}
+
+// A `Cow` can have its lifetime changed like a shared reference, whether it
+// borrows a `str`, a slice, or anything else.
+unsafe impl<B: ?Sized + ToOwned> ReuseCastInto<std::borrow::Cow<'_, B>>
+    for std::borrow::Cow<'_, B>
+{
+}
+
+// Arrays are laid out as `N` consecutive elements, so they can be cast
+// element-wise, for example from `[&'static str; 4]` to `[&'a str; 4]`.
+unsafe impl<T1: ReuseCastInto<T2>, T2, const N: usize> ReuseCastInto<[T2; N]> for [T1; N] {}
+
+// Raw pointers and function pointers are plain addresses, so they are cast to
+// themselves, letting FFI tables such as `Vec<*const c_void>` be recycled.
+unsafe impl<T: ?Sized> ReuseCastInto<*const T> for *const T {}
+unsafe impl<T: ?Sized> ReuseCastInto<*mut T> for *mut T {}
+unsafe impl<T: ?Sized> ReuseCastInto<std::ptr::NonNull<T>> for std::ptr::NonNull<T> {}
+
+macro_rules! impl_reuse_cast_into_for_fn_pointers {
+    ($($arg:ident),*) => {
+        unsafe impl<R, $($arg),*> ReuseCastInto<fn($($arg),*) -> R> for fn($($arg),*) -> R {}
+        unsafe impl<R, $($arg),*> ReuseCastInto<unsafe fn($($arg),*) -> R>
+            for unsafe fn($($arg),*) -> R
+        {
+        }
+        unsafe impl<R, $($arg),*> ReuseCastInto<extern "C" fn($($arg),*) -> R>
+            for extern "C" fn($($arg),*) -> R
+        {
+        }
+        unsafe impl<R, $($arg),*> ReuseCastInto<unsafe extern "C" fn($($arg),*) -> R>
+            for unsafe extern "C" fn($($arg),*) -> R
+        {
+        }
+    };
+}
+
+impl_reuse_cast_into_for_fn_pointers!();
+impl_reuse_cast_into_for_fn_pointers!(A);
+impl_reuse_cast_into_for_fn_pointers!(A, B);
+impl_reuse_cast_into_for_fn_pointers!(A, B, C);
+impl_reuse_cast_into_for_fn_pointers!(A, B, C, D);
+impl_reuse_cast_into_for_fn_pointers!(A, B, C, D, E);
+impl_reuse_cast_into_for_fn_pointers!(A, B, C, D, E, F);
+
+// Tuples are only cast to themselves, since the layout of two different
+// tuple types is not guaranteed to match even if their fields' layouts do.
+unsafe impl<A, B> ReuseCastInto<(A, B)> for (A, B) {}
+unsafe impl<A, B, C> ReuseCastInto<(A, B, C)> for (A, B, C) {}
 
 impl_reuse_cast_into_for_primitive!(
     // Signed integers
-    i8, i16, i32, i64, i128, isize, // Unsigned integers
-    u8, u16, u32, u64, u128, usize, // Floating point numbers
-    f32, f64, // Other primitives
-    bool, char, String
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    // Unsigned integers
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    // Floating point numbers
+    f32,
+    f64,
+    // Other primitives
+    bool,
+    char,
+    (),
+    String
 );
+
+// Common standard library types without lifetimes or type parameters. A
+// blanket impl for every `'static` type would overlap with the component-wise
+// impls above, so they are listed one by one.
+impl_reuse_cast_into_for_primitive!(
+    std::num::NonZeroI8,
+    std::num::NonZeroI16,
+    std::num::NonZeroI32,
+    std::num::NonZeroI64,
+    std::num::NonZeroI128,
+    std::num::NonZeroIsize,
+    std::num::NonZeroU8,
+    std::num::NonZeroU16,
+    std::num::NonZeroU32,
+    std::num::NonZeroU64,
+    std::num::NonZeroU128,
+    std::num::NonZeroUsize,
+    std::cmp::Ordering,
+    std::time::Duration,
+    std::time::Instant,
+    std::time::SystemTime,
+    std::net::IpAddr,
+    std::net::Ipv4Addr,
+    std::net::Ipv6Addr,
+    std::net::SocketAddr,
+    std::net::SocketAddrV4,
+    std::net::SocketAddrV6,
+    std::path::PathBuf,
+    std::ffi::OsString,
+    std::ffi::CString,
+    std::any::TypeId,
+    std::thread::ThreadId
+);
+
+// `Wrapping` is `repr(transparent)`, like `Reverse`.
+unsafe impl<T1: ReuseCastInto<T2>, T2> ReuseCastInto<std::num::Wrapping<T2>>
+    for std::num::Wrapping<T1>
+{
+}
+
+// Ranges are only cast to themselves, for the same reason as tuples.
+unsafe impl<T> ReuseCastInto<std::ops::Range<T>> for std::ops::Range<T> {}
+unsafe impl<T> ReuseCastInto<std::ops::RangeInclusive<T>> for std::ops::RangeInclusive<T> {}
@@ -0,0 +1,458 @@
+use crate::{
+    poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, Reset, ReuseCastInto,
+};
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A reusable `Vec` of buffers that keeps the inner allocations as well as
+/// the outer one.
+///
+/// Recycling a [`ReusableVec<Vec<T>>`](crate::ReusableVec) only saves the
+/// outer allocation: clearing the guard drops every inner `Vec`.
+/// `ReusableNestedVec` keeps the inner buffers instead. Its guard hands out
+/// rows with [`push_row`](ReusableNestedVecGuard::push_row), which reuses a
+/// retained buffer whenever one is left, and when the guard is dropped, every
+/// row is cleared and kept for the next cycle. A row/column scratch structure
+/// that is rebuilt each iteration therefore stops allocating once it has
+/// reached its largest shape.
+///
+/// The inner buffer type `B` is any [`Reset`] type with a `Default`, such as
+/// `Vec<T>` or `String`, and can be cast through [`ReuseCastInto`] like the element type
+/// of a `ReusableVec`.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableNestedVec;
+///
+/// let mut reusable = ReusableNestedVec::<Vec<f64>>::default();
+///
+/// for width in [3, 2] {
+///     let mut rows = reusable.recycle();
+///     for row in 0..2 {
+///         rows.push_row().extend((0..width).map(|col| (row * width + col) as f64));
+///     }
+///     assert_eq!(rows.len(), 2);
+/// }
+///
+/// // Both rows are kept, along with their allocations.
+/// assert_eq!(reusable.retained_rows(), 2);
+/// assert!(reusable.recycle().push_row().capacity() >= 3);
+/// ```
+pub struct ReusableNestedVec<B: 'static> {
+    inner: UnsafeCell<Vec<B>>,
+    /// Set while a guard is alive, and left set if it is leaked.
+    lent: bool,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableNestedVec` is safe to send across threads if `B` is `Send`.
+unsafe impl<B: Send> Send for ReusableNestedVec<B> {}
+
+// The `ReusableNestedVec` is safe to share across threads if `B` is `Send`,
+// because `recycle` requires `&mut self`.
+unsafe impl<B: Send> Sync for ReusableNestedVec<B> {}
+
+impl<B: 'static> fmt::Debug for ReusableNestedVec<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableNestedVec")
+            .field("retained_rows", &self.retained_rows())
+            .finish()
+    }
+}
+
+impl<B: 'static> Default for ReusableNestedVec<B> {
+    /// Creates a new, empty `ReusableNestedVec` with no allocation.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(Vec::new()),
+            lent: false,
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+impl<B: 'static> ReusableNestedVec<B> {
+    /// The number of inner buffers kept for reuse.
+    pub fn retained_rows(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).len() }
+    }
+
+    /// Leaks the rows of a guard that was never dropped.
+    ///
+    /// The rows weren't reset, and may hold cast values that borrow data
+    /// that is gone, so neither resetting nor dropping them is safe.
+    fn forget_leaked(&mut self) {
+        if mem::take(&mut self.lent) {
+            // SAFETY: Leaking the rows is always sound, and the outer `Vec`
+            // keeps its allocation.
+            unsafe { self.inner.get_mut().set_len(0) };
+        }
+    }
+}
+
+impl<B: 'static> Drop for ReusableNestedVec<B> {
+    /// Leaks the rows of a leaked guard, rather than dropping them with the
+    /// outer `Vec`.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<B1> ReusableNestedVec<B1>
+where
+    B1: Reset + Default + 'static,
+{
+    /// Returns a guard with no rows, cast to the inner buffer type `B2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the rows and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, B2>(&'parent mut self) -> ReusableNestedVecGuard<'parent, B1, B2>
+    where
+        B1: ReuseCastInto<B2>,
+        B2: Reset + Default,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        self.lent = true;
+        // SAFETY: `&mut self` guarantees exclusive access. The retained
+        // buffers are always empty between guards, and `ReuseCastInto`
+        // guarantees that an empty `B1` can be used as a `B2`.
+        let inner_ptr = self.inner.get() as *mut Vec<B2>;
+
+        ReusableNestedVecGuard {
+            inner: inner_ptr,
+            len: 0,
+            lent: &mut self.lent,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the rows without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the rows and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableNestedVecGuard<'_, B1, B1>
+    where
+        B1: Reset + Default,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        self.lent = true;
+        ReusableNestedVecGuard {
+            inner: self.inner.get(),
+            len: 0,
+            lent: &mut self.lent,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the rows and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<B2>(&mut self) -> Result<ReusableNestedVecGuard<'_, B1, B2>, PoisonError>
+    where
+        B1: ReuseCastInto<B2>,
+        B2: Reset + Default,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the rows were
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the rows as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the rows, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.forget_leaked();
+            *self.inner.get_mut() = Vec::new();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the rows for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<B2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableNestedVecGuard<'_, B1, B2>) -> R,
+    ) -> R
+    where
+        B1: ReuseCastInto<B2>,
+        B2: Reset + Default,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained buffers, each of them empty, in a `Vec` of their own.
+    pub fn into_inner(mut self) -> Vec<B1> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// The estimated number of heap bytes held on to by the outer `Vec` and
+    /// every retained buffer.
+    pub fn memory_footprint(&self) -> usize
+    where
+        B1: MemoryFootprint,
+    {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        let rows = unsafe { &*self.inner.get() };
+        rows_footprint(rows)
+    }
+}
+
+/// A RAII guard over the rows of a [`ReusableNestedVec`].
+///
+/// The guard dereferences to the slice of rows pushed so far. When it is
+/// dropped, every row is cleared, and all of them are kept, with their
+/// allocations, for the next guard.
+pub struct ReusableNestedVecGuard<'parent, B1, B2>
+where
+    B1: 'static,
+    B2: Reset + Default,
+{
+    inner: *mut Vec<B2>,
+    len: usize,
+    lent: &'parent mut bool,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableNestedVec<B1>>,
+}
+
+impl<'parent, B1, B2> ReusableNestedVecGuard<'parent, B1, B2>
+where
+    B1: 'static,
+    B2: Reset + Default,
+{
+    fn rows(&mut self) -> &mut Vec<B2> {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`,
+        // and the parent is mutably borrowed for that lifetime.
+        unsafe { &mut *self.inner }
+    }
+
+    /// Appends an empty row and returns it, reusing a retained buffer if
+    /// there is one.
+    pub fn push_row(&mut self) -> &mut B2 {
+        let len = self.len;
+        let rows = self.rows();
+        if len == rows.len() {
+            rows.push(B2::default());
+        }
+        self.len += 1;
+        &mut self.rows()[len]
+    }
+
+    /// Shortens the guard to `len` rows. The removed rows are cleared and
+    /// kept for reuse.
+    pub fn truncate(&mut self, len: usize) {
+        let old_len = self.len;
+        if len < old_len {
+            self.rows()[len..old_len].iter_mut().for_each(B2::reset);
+            self.len = len;
+        }
+    }
+
+    /// Removes every row, keeping all of them for reuse.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// The estimated number of heap bytes held on to by the outer `Vec` and
+    /// every buffer, including the retained ones past the last row.
+    pub fn memory_footprint(&self) -> usize
+    where
+        B2: MemoryFootprint,
+    {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        let rows = unsafe { &*self.inner };
+        rows_footprint(rows)
+    }
+}
+
+impl<'parent, B1, B2> Deref for ReusableNestedVecGuard<'parent, B1, B2>
+where
+    B1: 'static,
+    B2: Reset + Default,
+{
+    type Target = [B2];
+
+    /// Provides immutable access to the rows pushed so far.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        let rows = unsafe { &*self.inner };
+        &rows[..self.len]
+    }
+}
+
+impl<'parent, B1, B2> DerefMut for ReusableNestedVecGuard<'parent, B1, B2>
+where
+    B1: 'static,
+    B2: Reset + Default,
+{
+    /// Provides mutable access to the rows pushed so far.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let len = self.len;
+        &mut self.rows()[..len]
+    }
+}
+
+impl<'parent, B1, B2> Drop for ReusableNestedVecGuard<'parent, B1, B2>
+where
+    B1: 'static,
+    B2: Reset + Default,
+{
+    /// Clears every row, preserving the inner and outer allocations.
+    fn drop(&mut self) {
+        self.clear();
+        *self.lent = false;
+    }
+}
+
+fn rows_footprint<B: MemoryFootprint>(rows: &Vec<B>) -> usize {
+    rows.memory_footprint() + rows.iter().map(B::memory_footprint).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inner_allocations_are_retained() {
+        let mut reusable = ReusableNestedVec::<Vec<u32>>::default();
+        let ptrs: Vec<_> = {
+            let mut rows = reusable.recycle::<Vec<u32>>();
+            rows.push_row().extend(0..10);
+            rows.push_row().extend(0..20);
+            rows.iter().map(|row| row.as_ptr()).collect()
+        };
+
+        let mut rows = reusable.recycle::<Vec<u32>>();
+        assert!(rows.is_empty());
+        let first = rows.push_row();
+        assert!(first.is_empty());
+        assert_eq!(first.as_ptr(), ptrs[0]);
+        assert_eq!(rows.push_row().as_ptr(), ptrs[1]);
+        assert_eq!(rows.push_row().capacity(), 0);
+    }
+
+    #[test]
+    // The leaked guard's row is never freed, which miri reports.
+    #[cfg_attr(miri, ignore)]
+    fn rows_of_leaked_guards_are_never_handed_out_again() {
+        let mut reusable = ReusableNestedVec::<String>::default();
+        let mut guard = reusable.recycle::<String>();
+        guard.push_row().push_str("stale");
+        mem::forget(guard);
+
+        let mut guard = reusable.recycle_same();
+        assert!(guard.push_row().is_empty());
+        drop(guard);
+        assert_eq!(reusable.retained_rows(), 1);
+    }
+
+    #[test]
+    fn truncated_strings_are_cleared_and_kept() {
+        let mut reusable = ReusableNestedVec::<String>::default();
+        {
+            let mut lines = reusable.recycle::<String>();
+            lines.push_row().push_str("first");
+            lines.push_row().push_str("second");
+            lines.truncate(1);
+            assert_eq!(&*lines, ["first"]);
+
+            let reused = lines.push_row();
+            assert!(reused.is_empty());
+            assert!(reused.capacity() >= 6);
+        }
+        assert_eq!(reusable.retained_rows(), 2);
+    }
+
+    #[test]
+    fn footprint_counts_retained_rows() {
+        let mut reusable = ReusableNestedVec::<Vec<u64>>::default();
+        assert_eq!(reusable.memory_footprint(), 0);
+        let footprint = {
+            let mut rows = reusable.recycle_same();
+            rows.push_row().reserve_exact(32);
+            rows.push_row().reserve_exact(64);
+            rows.truncate(1);
+            rows.memory_footprint()
+        };
+        assert!(footprint >= (32 + 64) * 8);
+        assert_eq!(reusable.memory_footprint(), footprint);
+    }
+
+    #[test]
+    fn a_panic_poisons_the_rows() {
+        let mut reusable = ReusableNestedVec::<String>::default();
+        reusable.set_poison_policy(PoisonPolicy::Error);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut rows = reusable.recycle_same();
+            rows.push_row().push_str("half");
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(reusable.is_poisoned());
+        assert!(reusable.try_recycle::<String>().is_err());
+
+        // Clearing the poison keeps the rows, which the guard reset.
+        reusable.clear_poison();
+        assert_eq!(reusable.retained_rows(), 1);
+        assert!(reusable.recycle_same().push_row().is_empty());
+    }
+}
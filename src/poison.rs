@@ -0,0 +1,104 @@
+//! What a container does after a guard was dropped by a panic.
+//!
+//! A panic that unwinds through a guard still drops it, so the container is
+//! cleared as usual, but whatever the guard was used for stopped halfway. As
+//! with a [`Mutex`](std::sync::Mutex), the container is then marked
+//! poisoned, and [`is_poisoned`](crate::ReusableVec::is_poisoned) reports
+//! it. A [`PoisonPolicy`] decides what the next `recycle` does about it.
+//!
+//! Every reusable container whose guard borrows it mutably, like
+//! [`ReusableVec`](crate::ReusableVec),
+//! [`ReusableHashMap`](crate::ReusableHashMap),
+//! [`ReusableBTreeMap`](crate::ReusableBTreeMap) or
+//! [`ReusableGenerationalArena`](crate::ReusableGenerationalArena), accepts a
+//! policy through `set_poison_policy`.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::panic::{catch_unwind, AssertUnwindSafe};
+//! use triple_r::{PoisonPolicy, ReusableVec};
+//!
+//! let mut batch = ReusableVec::<u32>::default();
+//! batch.set_poison_policy(PoisonPolicy::Error);
+//!
+//! let _ = catch_unwind(AssertUnwindSafe(|| {
+//!     let mut guard = batch.recycle_same();
+//!     guard.push(1);
+//!     panic!("failed halfway");
+//! }));
+//! assert!(batch.is_poisoned());
+//! assert!(batch.try_recycle::<u32>().is_err());
+//!
+//! batch.clear_poison();
+//! assert!(batch.try_recycle::<u32>().is_ok());
+//! ```
+use std::{error::Error, fmt, thread};
+
+/// What `recycle` does with a container that a panic poisoned.
+///
+/// With the `serde` feature, the policy deserializes from `"reset"` or
+/// `"error"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PoisonPolicy {
+    /// Drop the contents and the allocation, as if the container were new,
+    /// and carry on. This is the default.
+    #[default]
+    Reset,
+    /// Refuse to hand out a guard until
+    /// [`clear_poison`](crate::ReusableVec::clear_poison) is called:
+    /// `try_recycle` returns a [`PoisonError`], and `recycle` panics.
+    Error,
+}
+
+/// The error returned by `try_recycle` on a container that a panic
+/// poisoned, under [`PoisonPolicy::Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoisonError {
+    _private: (),
+}
+
+impl PoisonError {
+    pub(crate) fn new() -> Self {
+        Self { _private: () }
+    }
+}
+
+impl fmt::Display for PoisonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("recycled a container poisoned by a panic")
+    }
+}
+
+impl Error for PoisonError {}
+
+/// Poisons a container when the guard holding it is dropped by a panic.
+///
+/// Like a `MutexGuard`, a guard created while the thread was already
+/// panicking, for example in a destructor, doesn't poison anything.
+pub(crate) struct PoisonOnPanic<'a> {
+    poisoned: &'a mut bool,
+    panicking: bool,
+}
+
+impl<'a> PoisonOnPanic<'a> {
+    pub(crate) fn new(poisoned: &'a mut bool) -> Self {
+        Self {
+            poisoned,
+            panicking: thread::panicking(),
+        }
+    }
+}
+
+impl Drop for PoisonOnPanic<'_> {
+    fn drop(&mut self) {
+        if !self.panicking && thread::panicking() {
+            *self.poisoned = true;
+        }
+    }
+}
@@ -0,0 +1,764 @@
+use crate::PoolProfile;
+#[cfg(feature = "stats")]
+use std::sync::atomic::AtomicU64;
+use std::{
+    fmt, mem,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+/// A thread-safe pool of recycled `Vec` allocations.
+///
+/// [`ReusableVec`](crate::ReusableVec) retains a single allocation and hands
+/// it out through `&mut self`. A `VecPool` retains any number of them behind a
+/// lock and hands them out through `&self`, so it can be shared (for example
+/// in an [`Arc`](std::sync::Arc)) between threads or components that each need
+/// their own buffer at the same time.
+///
+/// [`get`](VecPool::get) returns a [`PooledVec`] guard that puts its `Vec`
+/// back when dropped. [`take`](VecPool::take) and [`put`](VecPool::put) do the
+/// same by hand, for buffers that have to leave the guard's scope.
+///
+/// Vectors handed back to the pool are always cleared, but keep their
+/// capacity.
+///
+/// The free list is guarded by a [`std::sync::Mutex`]. With the `parking_lot`
+/// feature, a `parking_lot::Mutex` is used instead, which is smaller and
+/// cheaper to lock when uncontended, unless the pool is built with
+/// `PoolBuilder::parking_lot(false)`. Either way, a thread panicking while it
+/// uses a buffer never leaves the pool unusable.
+///
+/// # Examples
+///
+/// ```
+/// use std::{sync::Arc, thread};
+/// use triple_r::VecPool;
+///
+/// let pool = Arc::new(VecPool::<u8>::new());
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|i| {
+///         let pool = Arc::clone(&pool);
+///         thread::spawn(move || {
+///             let mut buffer = pool.get();
+///             buffer.extend_from_slice(format!("worker {}", i).as_bytes());
+///             buffer.len()
+///         })
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap(), "worker 0".len());
+/// }
+/// // Every buffer was returned to the pool.
+/// assert!(pool.available() >= 1);
+/// ```
+pub struct VecPool<T> {
+    free: FreeList<T>,
+    max_idle: usize,
+    max_buffer_capacity: usize,
+    max_retained_bytes: usize,
+    idle_timeout: Option<Duration>,
+    name: Option<String>,
+    /// The bytes allocated by the buffers in the free list.
+    retained_bytes: AtomicUsize,
+    /// The number of checkouts since the statistics were last reset.
+    #[cfg(feature = "stats")]
+    takes: AtomicU64,
+    /// The shortest the free list has been since the statistics were last
+    /// reset. The buffers below this mark have not been checked out since.
+    #[cfg(feature = "stats")]
+    low_water: AtomicUsize,
+}
+
+impl<T> Default for VecPool<T> {
+    /// Creates a new, empty `VecPool`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for VecPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VecPool")
+            .field("name", &self.name)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+impl<T> VecPool<T> {
+    /// Creates a new, empty `VecPool`. No memory is allocated until the first
+    /// buffer is used.
+    pub fn new() -> Self {
+        Self {
+            free: FreeList::default(),
+            max_idle: usize::MAX,
+            max_buffer_capacity: usize::MAX,
+            max_retained_bytes: usize::MAX,
+            idle_timeout: None,
+            name: None,
+            retained_bytes: AtomicUsize::new(0),
+            #[cfg(feature = "stats")]
+            takes: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            low_water: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    /// Creates a pool with the bounds in `config`, warmed with its profile if
+    /// it has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use triple_r::{PoolConfig, VecPool};
+    ///
+    /// let config = PoolConfig {
+    ///     max_idle: Some(2),
+    ///     warm: Some("1024x4".parse().unwrap()),
+    ///     ..PoolConfig::default()
+    /// };
+    /// let pool = VecPool::<u8>::from_config(&config);
+    /// assert_eq!(pool.available(), 2);
+    /// ```
+    pub fn from_config(config: &PoolConfig) -> Self {
+        Self::with_free_list(config, FreeList::default())
+    }
+
+    /// Creates a pool with the bounds in `config`, keeping its buffers in
+    /// `free`.
+    fn with_free_list(config: &PoolConfig, free: FreeList<T>) -> Self {
+        let mut pool = Self::new();
+        pool.free = free;
+        if let Some(max_idle) = config.max_idle {
+            pool.max_idle = max_idle;
+        }
+        if let Some(max_buffer_capacity) = config.max_buffer_capacity {
+            pool.max_buffer_capacity = max_buffer_capacity;
+        }
+        if let Some(max_retained_bytes) = config.max_retained_bytes {
+            pool.max_retained_bytes = max_retained_bytes;
+        }
+        pool.idle_timeout = config.idle_timeout;
+        if let Some(profile) = &config.warm {
+            pool.warm(profile);
+        }
+        pool
+    }
+
+    /// Takes a buffer out of the pool, returning a guard that puts it back
+    /// when dropped.
+    ///
+    /// If the pool has no buffer available, a new, empty one is created.
+    pub fn get(&self) -> PooledVec<'_, T> {
+        PooledVec {
+            vec: self.take(),
+            pool: self,
+        }
+    }
+
+    /// Takes a buffer out of the pool for good.
+    ///
+    /// The buffer is empty, but keeps the capacity it was returned with. If
+    /// the pool has no buffer available, a new, empty one is created. Hand it
+    /// back with [`put`](VecPool::put) to make its allocation available again.
+    pub fn take(&self) -> Vec<T> {
+        let mut free = self.lock();
+        self.evict_idle(&mut free);
+        let vec = free.pop().map(|idle| idle.vec).unwrap_or_default();
+        // The counters are only updated under the lock, so relaxed ordering
+        // is enough.
+        self.retained_bytes
+            .fetch_sub(buffer_bytes(&vec), Ordering::Relaxed);
+        #[cfg(feature = "stats")]
+        {
+            self.takes.fetch_add(1, Ordering::Relaxed);
+            self.low_water.fetch_min(free.len(), Ordering::Relaxed);
+        }
+        vec
+    }
+
+    /// Clears `vec` and adds it to the pool.
+    ///
+    /// `vec` does not need to have come from this pool. Vectors without any
+    /// capacity are dropped instead, since there is nothing to retain, and so
+    /// are vectors that would exceed the bounds set with
+    /// [`from_config`](VecPool::from_config).
+    pub fn put(&self, mut vec: Vec<T>) {
+        if vec.capacity() == 0 || vec.capacity() > self.max_buffer_capacity {
+            return;
+        }
+        vec.clear();
+        let bytes = buffer_bytes(&vec);
+        let mut free = self.lock();
+        self.evict_idle(&mut free);
+        let retained = self.retained_bytes.load(Ordering::Relaxed);
+        if free.len() < self.max_idle && bytes <= self.max_retained_bytes - retained {
+            free.push(Idle {
+                vec,
+                since: Instant::now(),
+            });
+            self.retained_bytes
+                .store(retained + bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Drops the buffers that have waited in `free` for longer than the idle
+    /// timeout.
+    fn evict_idle(&self, free: &mut Vec<Idle<T>>) {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        // Buffers are pushed as they are returned, so the longest idle ones
+        // are at the bottom of the stack.
+        let expired = free.partition_point(|idle| idle.since.elapsed() > timeout);
+        if expired == 0 {
+            return;
+        }
+        let bytes: usize = free
+            .drain(..expired)
+            .map(|idle| buffer_bytes(&idle.vec))
+            .sum();
+        self.retained_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        #[cfg(feature = "stats")]
+        {
+            let low_water = self.low_water.load(Ordering::Relaxed);
+            self.low_water
+                .store(low_water.saturating_sub(expired), Ordering::Relaxed);
+        }
+    }
+
+    /// The name the pool was given with [`PoolBuilder::name`], for labelling
+    /// its metrics.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The number of bytes allocated by the buffers currently waiting in the
+    /// pool.
+    pub fn retained_bytes(&self) -> usize {
+        self.retained_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The number of buffers currently waiting in the pool.
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns the capacities of the buffers currently waiting in the pool.
+    pub(crate) fn capacities(&self) -> Vec<usize> {
+        self.lock().iter().map(|idle| idle.vec.capacity()).collect()
+    }
+
+    /// Starts a new observation window for
+    /// [`WasteReport::inspect_pool`](crate::stats::WasteReport::inspect_pool).
+    ///
+    /// The pool counts checkouts and remembers which buffers have not been
+    /// handed out since the window started. A buffer that sits unused through
+    /// enough checkouts is reported as idle.
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        let _free = self.lock();
+        self.takes.store(0, Ordering::Relaxed);
+        self.low_water.store(usize::MAX, Ordering::Relaxed);
+    }
+
+    /// Returns the number of checkouts in the current observation window and
+    /// the total capacity of the buffers that were not handed out by any of
+    /// them.
+    #[cfg(feature = "stats")]
+    pub(crate) fn idle_buffers(&self) -> (u64, usize) {
+        let free = self.lock();
+        let takes = self.takes.load(Ordering::Relaxed);
+        let low_water = self.low_water.load(Ordering::Relaxed).min(free.len());
+        // The free list is a stack, so the buffers below the low-water mark
+        // are exactly the ones nothing has popped.
+        let capacity = free[..low_water]
+            .iter()
+            .map(|idle| idle.vec.capacity())
+            .sum();
+        (takes, capacity)
+    }
+
+    fn lock(&self) -> FreeListGuard<'_, T> {
+        match &self.free {
+            // The free list is always in a consistent state, even if a
+            // thread panicked while holding the lock.
+            FreeList::Std(free) => {
+                FreeListGuard::Std(free.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+            }
+            #[cfg(feature = "parking_lot")]
+            FreeList::ParkingLot(free) => FreeListGuard::ParkingLot(free.lock()),
+        }
+    }
+}
+
+/// The buffers waiting in a [`VecPool`], behind the mutex it was built with.
+enum FreeList<T> {
+    Std(Mutex<Vec<Idle<T>>>),
+    #[cfg(feature = "parking_lot")]
+    ParkingLot(parking_lot::Mutex<Vec<Idle<T>>>),
+}
+
+impl<T> Default for FreeList<T> {
+    /// Creates an empty free list, behind a `parking_lot` mutex whenever the
+    /// feature is enabled.
+    fn default() -> Self {
+        #[cfg(feature = "parking_lot")]
+        {
+            Self::ParkingLot(parking_lot::Mutex::new(Vec::new()))
+        }
+        #[cfg(not(feature = "parking_lot"))]
+        {
+            Self::Std(Mutex::new(Vec::new()))
+        }
+    }
+}
+
+/// A locked [`FreeList`].
+enum FreeListGuard<'a, T> {
+    Std(MutexGuard<'a, Vec<Idle<T>>>),
+    #[cfg(feature = "parking_lot")]
+    ParkingLot(parking_lot::MutexGuard<'a, Vec<Idle<T>>>),
+}
+
+impl<'a, T> Deref for FreeListGuard<'a, T> {
+    type Target = Vec<Idle<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Std(free) => free,
+            #[cfg(feature = "parking_lot")]
+            Self::ParkingLot(free) => free,
+        }
+    }
+}
+
+impl<'a, T> DerefMut for FreeListGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Std(free) => free,
+            #[cfg(feature = "parking_lot")]
+            Self::ParkingLot(free) => free,
+        }
+    }
+}
+
+/// A buffer waiting in a [`VecPool`], and when it was returned.
+struct Idle<T> {
+    vec: Vec<T>,
+    since: Instant,
+}
+
+/// The bytes allocated by the buffer of `vec`.
+fn buffer_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * mem::size_of::<T>()
+}
+
+/// A RAII guard over a `Vec` taken from a [`VecPool`].
+///
+/// When the guard is dropped, the `Vec` is cleared and returned to the pool,
+/// preserving its allocation for the next user.
+pub struct PooledVec<'pool, T> {
+    vec: Vec<T>,
+    pool: &'pool VecPool<T>,
+}
+
+impl<'pool, T> PooledVec<'pool, T> {
+    /// Detaches the `Vec` from the pool, so it is not returned when the guard
+    /// would have been dropped.
+    pub fn into_inner(mut self) -> Vec<T> {
+        let vec = std::mem::take(&mut self.vec);
+        // The empty placeholder left behind has no capacity, so dropping the
+        // guard does not add anything to the pool.
+        drop(self);
+        vec
+    }
+}
+
+impl<'pool, T> Deref for PooledVec<'pool, T> {
+    type Target = Vec<T>;
+
+    /// Provides immutable access to the underlying `Vec`.
+    fn deref(&self) -> &Self::Target {
+        &self.vec
+    }
+}
+
+impl<'pool, T> DerefMut for PooledVec<'pool, T> {
+    /// Provides mutable access to the underlying `Vec`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.vec
+    }
+}
+
+impl<'pool, T> Drop for PooledVec<'pool, T> {
+    /// Clears the `Vec` and returns it to the pool.
+    fn drop(&mut self) {
+        self.pool.put(std::mem::take(&mut self.vec));
+    }
+}
+
+/// Bounds and warm-up settings for a [`VecPool`], for use with
+/// [`VecPool::from_config`].
+///
+/// Every setting is optional, and the default configuration is the same as
+/// [`VecPool::new`]. With the `serde` feature, the configuration can be
+/// deserialized, so it can live in a service's configuration file:
+///
+/// ```toml
+/// max_idle = 64
+/// max_buffer_capacity = 1048576
+/// max_retained_bytes = 16777216
+/// idle_timeout = 300
+/// warm = "65536x8,4096x32"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct PoolConfig {
+    /// The most buffers the pool keeps waiting at a time. Buffers returned
+    /// to a full pool are dropped.
+    pub max_idle: Option<usize>,
+    /// The largest capacity, in elements, of a buffer the pool keeps.
+    /// Buffers that grew beyond it are dropped when they are returned.
+    pub max_buffer_capacity: Option<usize>,
+    /// The most bytes the buffers waiting in the pool may hold in total.
+    /// Buffers that would push the pool over it are dropped when they are
+    /// returned.
+    pub max_retained_bytes: Option<usize>,
+    /// How long a buffer may wait in the pool before it is dropped, so a
+    /// quiet period after a burst of traffic gives the memory back. Expired
+    /// buffers are dropped on the next checkout or return. In configuration
+    /// files, the timeout is given in seconds.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_idle_timeout")
+    )]
+    pub idle_timeout: Option<Duration>,
+    /// A profile to warm the pool with when it is created.
+    pub warm: Option<PoolProfile>,
+}
+
+/// A builder for a [`VecPool`], declaring its bounds, warm-up and name in
+/// one place.
+///
+/// Every setting is optional, and a builder with none of them set builds the
+/// same pool as [`VecPool::new`]. The builder is not tied to an element type,
+/// so one builder can configure pools of several types.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use triple_r::PoolBuilder;
+///
+/// let pool = PoolBuilder::new()
+///     .name("frames")
+///     .prewarm(4, 4096)
+///     .max_idle(16)
+///     .max_retained_bytes(1 << 20)
+///     .idle_timeout(Duration::from_secs(60))
+///     .build::<u8>();
+///
+/// assert_eq!(pool.name(), Some("frames"));
+/// assert_eq!(pool.available(), 4);
+/// assert!(pool.get().capacity() >= 4096);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PoolBuilder {
+    config: PoolConfig,
+    prewarm: Vec<(usize, usize)>,
+    name: Option<String>,
+    #[cfg(feature = "parking_lot")]
+    std_mutex: bool,
+}
+
+impl PoolBuilder {
+    /// Starts a builder with no settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a builder from the settings in `config`.
+    pub fn from_config(config: &PoolConfig) -> Self {
+        Self {
+            config: config.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Names the pool, so metrics and debug output can tell it apart.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Fills the pool with `count` buffers of `capacity` elements when it is
+    /// built.
+    ///
+    /// Calls add up, so buffers of several sizes can be prewarmed, and they
+    /// are added after the profile of the configuration, if it has one.
+    pub fn prewarm(mut self, count: usize, capacity: usize) -> Self {
+        self.prewarm.push((count, capacity));
+        self
+    }
+
+    /// Sets [`PoolConfig::max_idle`].
+    pub fn max_idle(mut self, max_idle: usize) -> Self {
+        self.config.max_idle = Some(max_idle);
+        self
+    }
+
+    /// Sets [`PoolConfig::max_buffer_capacity`].
+    pub fn max_buffer_capacity(mut self, max_buffer_capacity: usize) -> Self {
+        self.config.max_buffer_capacity = Some(max_buffer_capacity);
+        self
+    }
+
+    /// Sets [`PoolConfig::max_retained_bytes`].
+    pub fn max_retained_bytes(mut self, max_retained_bytes: usize) -> Self {
+        self.config.max_retained_bytes = Some(max_retained_bytes);
+        self
+    }
+
+    /// Sets [`PoolConfig::idle_timeout`].
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.config.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Chooses whether the pool guards its buffers with a `parking_lot`
+    /// mutex, which is the default with the `parking_lot` feature, or with a
+    /// [`std::sync::Mutex`].
+    ///
+    /// Only pools built by this builder are affected. The
+    /// [`ReusablePool`](crate::ReusablePool) and the
+    /// [`SharedReusable`](crate::SharedReusable) handles built on it always
+    /// use `parking_lot` when the feature is enabled.
+    #[cfg(feature = "parking_lot")]
+    pub fn parking_lot(mut self, enabled: bool) -> Self {
+        self.std_mutex = !enabled;
+        self
+    }
+
+    /// Builds the pool, warmed with every buffer the builder asked for that
+    /// fits within its bounds.
+    pub fn build<T>(&self) -> VecPool<T> {
+        #[cfg(feature = "parking_lot")]
+        let free = if self.std_mutex {
+            FreeList::Std(Mutex::new(Vec::new()))
+        } else {
+            FreeList::default()
+        };
+        #[cfg(not(feature = "parking_lot"))]
+        let free = FreeList::default();
+        let mut pool = VecPool::with_free_list(&self.config, free);
+        pool.name = self.name.clone();
+        for &(count, capacity) in &self.prewarm {
+            for _ in 0..count {
+                pool.put(Vec::with_capacity(capacity));
+            }
+        }
+        pool
+    }
+}
+
+/// Reads an idle timeout given as a number of seconds.
+#[cfg(feature = "serde")]
+fn deserialize_idle_timeout<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let secs = <f64 as serde::Deserialize>::deserialize(deserializer)?;
+    if !secs.is_finite() || secs < 0.0 {
+        return Err(serde::de::Error::custom(
+            "the idle timeout must be a non-negative number of seconds",
+        ));
+    }
+    // `Duration::from_secs_f64` panics on values that don't fit, and
+    // `u64::MAX as f64` rounds up to the first of them.
+    if secs >= u64::MAX as f64 {
+        return Err(serde::de::Error::custom("the idle timeout is too large"));
+    }
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_returned_and_reused() {
+        let pool = VecPool::<u32>::new();
+        let ptr = {
+            let mut buffer = pool.get();
+            buffer.extend(0..100);
+            buffer.as_ptr()
+        };
+        assert_eq!(pool.available(), 1);
+
+        let buffer = pool.get();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 100);
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[test]
+    fn concurrent_guards_get_distinct_buffers() {
+        let pool = VecPool::<u8>::new();
+        let mut first = pool.get();
+        let mut second = pool.get();
+        first.push(1);
+        second.push(2);
+        assert_ne!(first.as_ptr(), second.as_ptr());
+        drop((first, second));
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn take_and_put_round_trip() {
+        let pool = VecPool::<u8>::new();
+        let mut vec = pool.take();
+        vec.extend_from_slice(b"hello");
+        pool.put(vec);
+
+        let vec = pool.take();
+        assert!(vec.is_empty());
+        assert!(vec.capacity() >= 5);
+
+        // Buffers without an allocation are not worth keeping.
+        pool.put(Vec::new());
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[test]
+    fn buffers_held_during_a_panic_are_returned() {
+        let pool = VecPool::<u8>::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut buffer = pool.get();
+            buffer.push(1);
+            panic!("worker failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(pool.available(), 1);
+        assert!(pool.get().is_empty());
+    }
+
+    #[test]
+    fn configured_bounds_are_enforced() {
+        let pool = VecPool::<u8>::from_config(&PoolConfig {
+            max_idle: Some(1),
+            max_buffer_capacity: Some(64),
+            max_retained_bytes: None,
+            idle_timeout: None,
+            warm: None,
+        });
+        pool.put(Vec::with_capacity(128));
+        assert_eq!(pool.available(), 0);
+
+        pool.put(Vec::with_capacity(32));
+        pool.put(Vec::with_capacity(32));
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn the_byte_budget_caps_what_the_pool_retains() {
+        let pool = VecPool::<u32>::from_config(&PoolConfig {
+            max_retained_bytes: Some(256),
+            ..PoolConfig::default()
+        });
+        pool.put(Vec::with_capacity(48));
+        pool.put(Vec::with_capacity(32));
+        assert_eq!(pool.available(), 1);
+        assert_eq!(pool.retained_bytes(), 192);
+
+        pool.put(Vec::with_capacity(16));
+        assert_eq!(pool.retained_bytes(), 256);
+        let taken = pool.take();
+        assert_eq!(taken.capacity(), 16);
+        assert_eq!(pool.retained_bytes(), 192);
+    }
+
+    #[test]
+    fn buffers_idle_past_the_timeout_are_dropped() {
+        let pool = VecPool::<u8>::from_config(&PoolConfig {
+            idle_timeout: Some(Duration::from_millis(10)),
+            ..PoolConfig::default()
+        });
+        pool.put(Vec::with_capacity(64));
+        std::thread::sleep(Duration::from_millis(20));
+        pool.put(Vec::with_capacity(32));
+        assert_eq!(pool.available(), 1);
+        assert_eq!(pool.retained_bytes(), 32);
+    }
+
+    #[test]
+    fn builders_prewarm_within_their_bounds() {
+        let builder = PoolBuilder::new()
+            .name("rows")
+            .prewarm(2, 8)
+            .prewarm(2, 256)
+            .max_buffer_capacity(128)
+            .max_idle(3);
+        let pool = builder.build::<u64>();
+        assert_eq!(pool.name(), Some("rows"));
+        assert_eq!(pool.capacities(), [8, 8]);
+
+        // The same builder configures pools of other types.
+        assert_eq!(builder.build::<u8>().available(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn configs_deserialize_with_defaults() {
+        let config: PoolConfig =
+            serde_json::from_str(r#"{ "max_idle": 8, "warm": "16x2" }"#).unwrap();
+        assert_eq!(config.max_idle, Some(8));
+        assert_eq!(config.max_buffer_capacity, None);
+        assert_eq!(VecPool::<u8>::from_config(&config).available(), 2);
+
+        assert!(serde_json::from_str::<PoolConfig>(r#"{ "max_iddle": 8 }"#).is_err());
+        assert!(serde_json::from_str::<PoolConfig>(r#"{ "warm": "16" }"#).is_err());
+
+        let config: PoolConfig = serde_json::from_str(r#"{ "idle_timeout": 1.5 }"#).unwrap();
+        assert_eq!(config.idle_timeout, Some(Duration::from_millis(1500)));
+        assert!(serde_json::from_str::<PoolConfig>(r#"{ "idle_timeout": -1 }"#).is_err());
+        assert!(serde_json::from_str::<PoolConfig>(r#"{ "idle_timeout": 1e20 }"#).is_err());
+    }
+
+    #[test]
+    fn detached_buffers_are_not_returned() {
+        let pool = VecPool::<u8>::new();
+        let mut buffer = pool.get();
+        buffer.push(42);
+        let vec = buffer.into_inner();
+        assert_eq!(vec, [42]);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[cfg(feature = "parking_lot")]
+    #[test]
+    fn builders_choose_the_mutex() {
+        let pool = PoolBuilder::new().prewarm(2, 8).build::<u8>();
+        assert!(matches!(pool.free, FreeList::ParkingLot(_)));
+
+        let pool = PoolBuilder::new()
+            .parking_lot(false)
+            .prewarm(2, 8)
+            .build::<u8>();
+        assert!(matches!(pool.free, FreeList::Std(_)));
+        assert_eq!(pool.available(), 2);
+        assert!(pool.get().capacity() >= 8);
+    }
+}
@@ -0,0 +1,89 @@
+//! [postcard](https://docs.rs/postcard) serialization into recycled buffers.
+//!
+//! [`postcard::to_stdvec`] allocates a new `Vec<u8>` for every message.
+//! [`serialize_reusing`] writes into a [`ReusableVec<u8>`] instead, and
+//! [`deserialize_reusing`] reads a whole message into one before decoding it,
+//! so a steady stream of snapshots or IPC messages needs no allocations for
+//! the byte buffers at all.
+//!
+//! This module is only available with the `postcard` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{postcard::{deserialize_reusing, serialize_reusing}, ReusableVec};
+//!
+//! let mut write_buffer = ReusableVec::<u8>::default();
+//! let mut read_buffer = ReusableVec::<u8>::default();
+//!
+//! for tick in 0..3u64 {
+//!     let snapshot = (tick, vec!["a".to_string(), "b".to_string()]);
+//!     let bytes = serialize_reusing(&snapshot, &mut write_buffer).unwrap();
+//!
+//!     let decoded: (u64, Vec<String>) =
+//!         deserialize_reusing(bytes.as_slice(), &mut read_buffer).unwrap();
+//!     assert_eq!(decoded, snapshot);
+//! }
+//! ```
+use crate::{ReusableVec, ReusableVecGuard};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{self, Read};
+
+/// Serializes `value` into the recycled `buffer`, returning a guard over the
+/// encoded bytes.
+///
+/// This is the recycling counterpart of [`postcard::to_stdvec`].
+pub fn serialize_reusing<'parent, T>(
+    value: &T,
+    buffer: &'parent mut ReusableVec<u8>,
+) -> postcard::Result<ReusableVecGuard<'parent, u8, u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut guard = buffer.recycle();
+    postcard::to_io(value, &mut *guard)?;
+    Ok(guard)
+}
+
+/// Reads `reader` to the end into the recycled `buffer` and deserializes a
+/// `T` from the bytes read.
+///
+/// Decoding errors are reported as [`io::ErrorKind::InvalidData`]. The buffer
+/// is cleared again before this function returns, keeping its capacity for
+/// the next message.
+pub fn deserialize_reusing<T, R>(mut reader: R, buffer: &mut ReusableVec<u8>) -> io::Result<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    let mut guard = buffer.recycle::<u8>();
+    reader.read_to_end(&mut guard)?;
+    postcard::from_bytes(&guard).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn round_trip_matches_to_stdvec() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let value: HashMap<String, u32> = [("one".to_string(), 1), ("two".to_string(), 2)]
+            .into_iter()
+            .collect();
+        let encoded = serialize_reusing(&value, &mut buffer).unwrap();
+        assert_eq!(*encoded, postcard::to_stdvec(&value).unwrap());
+
+        let mut read_buffer = ReusableVec::<u8>::default();
+        let decoded: HashMap<String, u32> =
+            deserialize_reusing(encoded.as_slice(), &mut read_buffer).unwrap();
+        assert_eq!(decoded, value);
+
+        // A truncated message is reported rather than decoded.
+        let truncated = &encoded[..encoded.len() - 1];
+        let err = deserialize_reusing::<HashMap<String, u32>, _>(truncated, &mut read_buffer)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
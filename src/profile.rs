@@ -0,0 +1,212 @@
+use crate::VecPool;
+use std::{error::Error, fmt, str::FromStr};
+
+/// A snapshot of how many buffers a [`VecPool`] retains, and at which
+/// capacities.
+///
+/// A freshly started pool has no buffers, so the first requests after a
+/// deploy pay for allocations a warmed-up pool would have avoided. Taking a
+/// profile of a pool in steady state with [`VecPool::profile`], storing it,
+/// and replaying it with [`VecPool::warm`] at the next startup gets there
+/// right away.
+///
+/// A profile is stored as a short line of text: comma-separated
+/// `capacity x count` pairs, largest capacity first, e.g. `4096x2,512x10`.
+/// Capacities are in elements, not bytes, so a profile should be replayed
+/// into a pool of the same element type. The empty string is the profile of
+/// an empty pool.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{PoolProfile, VecPool};
+///
+/// let pool = VecPool::<u8>::new();
+/// {
+///     let mut first = pool.get();
+///     let mut second = pool.get();
+///     first.reserve_exact(4096);
+///     second.reserve_exact(512);
+/// }
+///
+/// let saved = pool.profile().to_string();
+/// assert_eq!(saved, "4096x1,512x1");
+///
+/// // At the next startup:
+/// let warmed = VecPool::<u8>::new();
+/// warmed.warm(&saved.parse::<PoolProfile>().unwrap());
+/// assert_eq!(warmed.available(), 2);
+/// assert!(warmed.get().capacity() >= 512);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolProfile {
+    /// `(capacity, count)` pairs, sorted by descending capacity, with unique
+    /// capacities and non-zero counts.
+    buckets: Vec<(usize, usize)>,
+}
+
+impl PoolProfile {
+    /// Builds a profile from buffer capacities, in any order. Zero
+    /// capacities are skipped.
+    pub fn from_capacities<I: IntoIterator<Item = usize>>(capacities: I) -> Self {
+        let mut capacities: Vec<usize> = capacities
+            .into_iter()
+            .filter(|&capacity| capacity > 0)
+            .collect();
+        capacities.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut buckets: Vec<(usize, usize)> = Vec::new();
+        for capacity in capacities {
+            match buckets.last_mut() {
+                Some((last, count)) if *last == capacity => *count += 1,
+                _ => buckets.push((capacity, 1)),
+            }
+        }
+        Self { buckets }
+    }
+
+    /// The `(capacity, count)` pairs of the profile, largest capacity first.
+    pub fn buckets(&self) -> &[(usize, usize)] {
+        &self.buckets
+    }
+
+    /// The total number of buffers in the profile.
+    pub fn buffer_count(&self) -> usize {
+        self.buckets.iter().map(|&(_, count)| count).sum()
+    }
+
+    /// Returns `true` if the profile has no buffers.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+impl fmt::Display for PoolProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (capacity, count)) in self.buckets.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}x{}", capacity, count)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PoolProfile {
+    type Err = ParseProfileError;
+
+    /// Parses the format written by the [`Display`](fmt::Display)
+    /// implementation. Pairs may come in any order, and pairs repeating a
+    /// capacity are merged.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut capacities = Vec::new();
+        for pair in s.split(',') {
+            let invalid = || ParseProfileError {
+                pair: pair.trim().to_string(),
+            };
+            let (capacity, count) = pair.trim().split_once('x').ok_or_else(invalid)?;
+            let capacity: usize = capacity.parse().map_err(|_| invalid())?;
+            let count: usize = count.parse().map_err(|_| invalid())?;
+            capacities.extend(std::iter::repeat(capacity).take(count));
+        }
+        Ok(Self::from_capacities(capacities))
+    }
+}
+
+/// The error returned when parsing a [`PoolProfile`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseProfileError {
+    pair: String,
+}
+
+impl fmt::Display for ParseProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid pool profile entry {:?}, expected `<capacity>x<count>`",
+            self.pair
+        )
+    }
+}
+
+impl Error for ParseProfileError {}
+
+/// Deserializes a profile from its text form.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PoolProfile {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl<T> VecPool<T> {
+    /// Takes a [`PoolProfile`] of the buffers currently waiting in the pool.
+    ///
+    /// Buffers that are checked out are not part of the profile, so take it
+    /// while the pool is idle, or at least not at its busiest.
+    pub fn profile(&self) -> PoolProfile {
+        PoolProfile::from_capacities(self.capacities())
+    }
+
+    /// Allocates the buffers described by `profile` and adds them to the
+    /// pool.
+    ///
+    /// The largest buffers are handed out first.
+    pub fn warm(&self, profile: &PoolProfile) {
+        for &(capacity, count) in profile.buckets.iter().rev() {
+            for _ in 0..count {
+                self.put(Vec::with_capacity(capacity));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_round_trip_through_text() {
+        let profile = PoolProfile::from_capacities([64, 0, 1024, 64, 64]);
+        assert_eq!(profile.buckets(), &[(1024, 1), (64, 3)]);
+        assert_eq!(profile.buffer_count(), 4);
+
+        let text = profile.to_string();
+        assert_eq!(text, "1024x1,64x3");
+        assert_eq!(text.parse::<PoolProfile>().unwrap(), profile);
+        assert_eq!(
+            " 64x1, 1024x1,64x2 ".parse::<PoolProfile>().unwrap(),
+            profile
+        );
+        assert!("".parse::<PoolProfile>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn malformed_pairs_are_reported() {
+        let err = "64x1,64y2".parse::<PoolProfile>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid pool profile entry \"64y2\", expected `<capacity>x<count>`"
+        );
+        assert!("x3".parse::<PoolProfile>().is_err());
+    }
+
+    #[test]
+    fn warmed_pools_reproduce_the_profile() {
+        let profile: PoolProfile = "256x2,16x1".parse().unwrap();
+        let pool = VecPool::<u32>::new();
+        pool.warm(&profile);
+        assert_eq!(pool.profile(), profile);
+        assert!(pool.get().capacity() >= 256);
+    }
+}
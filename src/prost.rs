@@ -0,0 +1,104 @@
+//! Protobuf encoding into recycled buffers with [prost](https://docs.rs/prost).
+//!
+//! [`Message::encode_to_vec`] allocates a new `Vec<u8>` for every message. An
+//! RPC server encoding one response after another can instead keep a single
+//! [`ReusableVec<u8>`] per connection and encode into it with
+//! [`encode_reusing`]. The returned guard holds the encoded bytes; once it is
+//! dropped the buffer is cleared and its capacity is ready for the next
+//! message.
+//!
+//! This module is only available with the `prost` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use prost::Message;
+//! use triple_r::{prost::encode_reusing, ReusableVec};
+//!
+//! let mut buffer = ReusableVec::<u8>::default();
+//!
+//! for reply in ["first", "second", "third"] {
+//!     let reply = reply.to_string();
+//!     let encoded = encode_reusing(&reply, &mut buffer);
+//!     assert_eq!(*encoded, reply.encode_to_vec());
+//!     // ... write `encoded` to the connection ...
+//! }
+//! ```
+use crate::{ReusableVec, ReusableVecGuard};
+use prost::{
+    encoding::{encode_varint, encoded_len_varint},
+    Message,
+};
+
+/// Encodes `msg` into the recycled `buffer`, returning a guard over the
+/// encoded bytes.
+///
+/// This is the recycling counterpart of [`Message::encode_to_vec`]. The buffer
+/// is grown to the message's encoded length up front, so at most one
+/// reallocation happens per message, and none once the retained capacity is
+/// large enough.
+pub fn encode_reusing<'parent, M: Message>(
+    msg: &M,
+    buffer: &'parent mut ReusableVec<u8>,
+) -> ReusableVecGuard<'parent, u8, u8> {
+    let mut guard = buffer.recycle();
+    guard.reserve(msg.encoded_len());
+    msg.encode_raw(&mut *guard);
+    guard
+}
+
+/// Encodes `msg` with a length delimiter into the recycled `buffer`,
+/// returning a guard over the encoded bytes.
+///
+/// This is the recycling counterpart of
+/// [`Message::encode_length_delimited_to_vec`], for framing several messages
+/// on a single stream.
+pub fn encode_length_delimited_reusing<'parent, M: Message>(
+    msg: &M,
+    buffer: &'parent mut ReusableVec<u8>,
+) -> ReusableVecGuard<'parent, u8, u8> {
+    let mut guard = buffer.recycle();
+    let len = msg.encoded_len();
+    guard.reserve(len + encoded_len_varint(len as u64));
+    encode_varint(len as u64, &mut *guard);
+    msg.encode_raw(&mut *guard);
+    guard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_matches_encode_to_vec() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let msg = "hello world".to_string();
+        let encoded = encode_reusing(&msg, &mut buffer);
+        assert_eq!(*encoded, msg.encode_to_vec());
+        assert_eq!(String::decode(encoded.as_slice()).unwrap(), msg);
+    }
+
+    #[test]
+    fn length_delimited_matches_encode_length_delimited_to_vec() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let msg = 42u64;
+        let encoded = encode_length_delimited_reusing(&msg, &mut buffer);
+        assert_eq!(*encoded, msg.encode_length_delimited_to_vec());
+        assert_eq!(
+            u64::decode_length_delimited(encoded.as_slice()).unwrap(),
+            msg
+        );
+    }
+
+    #[test]
+    fn buffer_is_reused_between_messages() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let long = "x".repeat(1024);
+        let capacity = encode_reusing(&long, &mut buffer).capacity();
+
+        let short = "short".to_string();
+        let encoded = encode_reusing(&short, &mut buffer);
+        assert_eq!(*encoded, short.encode_to_vec());
+        assert_eq!(encoded.capacity(), capacity);
+    }
+}
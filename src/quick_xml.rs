@@ -0,0 +1,173 @@
+//! Streaming XML with [quick-xml](https://docs.rs/quick-xml) over recycled
+//! buffers.
+//!
+//! [`Reader::read_event_into`] needs a `Vec<u8>` to hold each event's bytes,
+//! and idiomatic code clears it after every event. [`RecyclingReader`] holds
+//! that buffer as a guard over a [`ReusableVec<u8>`], so an ETL job parsing
+//! one document after another keeps a single event buffer sized for its
+//! largest event instead of growing a fresh one per document.
+//!
+//! [`attribute_reusing`] does the same for attribute values, unescaping them
+//! into a recycled [`ReusableString`].
+//!
+//! This module is only available with the `quick-xml` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use quick_xml::{events::Event, Reader};
+//! use triple_r::{
+//!     quick_xml::{attribute_reusing, RecyclingReader},
+//!     ReusableString, ReusableVec,
+//! };
+//!
+//! let mut events = ReusableVec::<u8>::default();
+//! let mut value = ReusableString::default();
+//!
+//! for document in [r#"<row id="1"/>"#, r#"<row id="2"/>"#] {
+//!     let mut reader = RecyclingReader::new(Reader::from_str(document), &mut events);
+//!     loop {
+//!         match reader.read_event().unwrap() {
+//!             Event::Empty(row) => {
+//!                 let id = attribute_reusing(&row, "id", &mut value).unwrap().unwrap();
+//!                 assert!(id.parse::<u32>().is_ok());
+//!             }
+//!             Event::Eof => break,
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//! ```
+use crate::{ReusableString, ReusableStringGuard, ReusableVec, ReusableVecGuard};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader, XmlVersion,
+};
+use std::io::BufRead;
+
+/// A quick-xml [`Reader`] that reads every event into a recycled buffer.
+///
+/// The buffer is borrowed from a [`ReusableVec<u8>`] for as long as the
+/// `RecyclingReader` is alive, and is cleared before each event is read. When
+/// the `RecyclingReader` is dropped, the buffer's capacity is retained for
+/// the next document.
+pub struct RecyclingReader<'parent, R> {
+    reader: Reader<R>,
+    buffer: ReusableVecGuard<'parent, u8, u8>,
+}
+
+impl<'parent, R: BufRead> RecyclingReader<'parent, R> {
+    /// Wraps `reader`, reading its events into the recycled `buffer`.
+    pub fn new(reader: Reader<R>, buffer: &'parent mut ReusableVec<u8>) -> Self {
+        Self {
+            reader,
+            buffer: buffer.recycle(),
+        }
+    }
+
+    /// Reads the next event, reusing the retained buffer.
+    ///
+    /// This is equivalent to calling [`Reader::read_event_into`] and clearing
+    /// the buffer after every event.
+    pub fn read_event(&mut self) -> quick_xml::Result<Event<'_>> {
+        self.buffer.clear();
+        self.reader.read_event_into(&mut self.buffer)
+    }
+
+    /// Returns a reference to the wrapped [`Reader`].
+    pub fn get_ref(&self) -> &Reader<R> {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the wrapped [`Reader`], for example to
+    /// adjust its [`Config`](quick_xml::reader::Config).
+    pub fn get_mut(&mut self) -> &mut Reader<R> {
+        &mut self.reader
+    }
+
+    /// Releases the recycled buffer and returns the wrapped [`Reader`].
+    pub fn into_inner(self) -> Reader<R> {
+        self.reader
+    }
+}
+
+/// Unescapes the value of the attribute called `name` on `start` into the
+/// recycled `buffer`.
+///
+/// Returns `Ok(None)` if `start` has no such attribute. The value is
+/// normalized as an XML 1.0 attribute value, exactly like
+/// [`Attribute::normalized_value`](quick_xml::events::attributes::Attribute::normalized_value).
+pub fn attribute_reusing<'parent>(
+    start: &BytesStart<'_>,
+    name: &str,
+    buffer: &'parent mut ReusableString,
+) -> quick_xml::Result<Option<ReusableStringGuard<'parent>>> {
+    let attribute = match start.try_get_attribute(name)? {
+        Some(attribute) => attribute,
+        None => return Ok(None),
+    };
+    let mut guard = buffer.recycle();
+    guard.push_str(&attribute.normalized_value(XmlVersion::Implicit1_0)?);
+    Ok(Some(guard))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_read_into_the_recycled_buffer() {
+        let mut buffer = ReusableVec::<u8>::default();
+        {
+            let mut reader = RecyclingReader::new(
+                Reader::from_str("<root><child>some text</child></root>"),
+                &mut buffer,
+            );
+            let mut names = Vec::new();
+            loop {
+                match reader.read_event().unwrap() {
+                    Event::Start(start) => names.push(start.name().as_ref().to_string()),
+                    Event::Eof => break,
+                    _ => {}
+                }
+            }
+            assert_eq!(names, ["root", "child"]);
+        }
+
+        let guard = buffer.recycle::<u8>();
+        assert!(guard.is_empty());
+    }
+
+    #[test]
+    fn buffer_capacity_is_retained_across_readers() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let document = format!("<a>{}</a>", "x".repeat(512));
+        {
+            let mut reader =
+                RecyclingReader::new(Reader::from_reader(document.as_bytes()), &mut buffer);
+            while !matches!(reader.read_event().unwrap(), Event::Eof) {}
+        }
+        assert!(buffer.recycle::<u8>().capacity() >= 512);
+    }
+
+    #[test]
+    fn attributes_are_unescaped_into_recycled_strings() {
+        let mut buffer = ReusableVec::<u8>::default();
+        let mut value = ReusableString::default();
+        let mut reader = RecyclingReader::new(
+            Reader::from_str(r#"<item name="a &amp; b" />"#),
+            &mut buffer,
+        );
+        match reader.read_event().unwrap() {
+            Event::Empty(start) => {
+                let name = attribute_reusing(&start, "name", &mut value).unwrap();
+                assert_eq!(name.as_deref().map(String::as_str), Some("a & b"));
+                drop(name);
+                assert!(attribute_reusing(&start, "missing", &mut value)
+                    .unwrap()
+                    .is_none());
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    alloc::{self, Layout},
+    fmt,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    slice,
+};
+
+/// A single retained, aligned byte allocation, reused as scratch space of any
+/// size and alignment.
+///
+/// [`ReusableVec<u8>`](crate::ReusableVec) retains bytes, but only with the
+/// alignment of `u8`. `ReusableRawBuffer` owns a raw allocation instead, and
+/// [`recycle`](ReusableRawBuffer::recycle) hands out a
+/// [`ReusableRawBufferGuard`] over `len` bytes aligned to `align`. The
+/// allocation is only replaced when a request needs more room or a stricter
+/// alignment than it has, and then it is sized for the largest request seen
+/// so far, so a buffer shared between differently shaped uses settles on one
+/// allocation quickly.
+///
+/// The bytes are handed out as `MaybeUninit<u8>`, since they hold whatever
+/// the previous user left behind. [`zeroed`](ReusableRawBufferGuard::zeroed)
+/// gives a plain `&mut [u8]` for callers that need one.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableRawBuffer;
+///
+/// let mut scratch = ReusableRawBuffer::default();
+///
+/// {
+///     let mut lanes = scratch.recycle(256, 64);
+///     assert_eq!(lanes.len(), 256);
+///     assert_eq!(lanes.as_ptr() as usize % 64, 0);
+///     lanes.zeroed()[0] = 1;
+/// }
+///
+/// // A smaller, less aligned request reuses the same allocation.
+/// let mut header = scratch.recycle(16, 8);
+/// let header = header.zeroed();
+/// assert_eq!(header.len(), 16);
+/// assert!(scratch.capacity() >= 256);
+/// ```
+pub struct ReusableRawBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// SAFETY: The buffer exclusively owns a plain byte allocation.
+unsafe impl Send for ReusableRawBuffer {}
+
+// SAFETY: Shared references only expose the capacity and alignment.
+unsafe impl Sync for ReusableRawBuffer {}
+
+impl Default for ReusableRawBuffer {
+    /// Creates a buffer without an allocation.
+    fn default() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            layout: Layout::new::<()>(),
+        }
+    }
+}
+
+impl fmt::Debug for ReusableRawBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableRawBuffer")
+            .field("capacity", &self.capacity())
+            .field("align", &self.align())
+            .finish()
+    }
+}
+
+impl ReusableRawBuffer {
+    /// Creates a buffer with room for `capacity` bytes aligned to `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or if `capacity` rounded up
+    /// to `align` overflows `isize`.
+    pub fn with_capacity(capacity: usize, align: usize) -> Self {
+        let mut buffer = Self::default();
+        buffer.reserve(capacity, align);
+        buffer
+    }
+
+    /// The number of bytes the retained allocation can hand out.
+    pub fn capacity(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// The alignment of the retained allocation.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// The number of heap bytes the buffer holds on to, which is its
+    /// capacity.
+    pub fn memory_footprint(&self) -> usize {
+        self.capacity()
+    }
+
+    /// Returns a guard over `len` bytes aligned to `align`, growing the
+    /// allocation first if it is too small or not aligned enough.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or if `len` rounded up to
+    /// `align` overflows `isize`.
+    pub fn recycle(&mut self, len: usize, align: usize) -> ReusableRawBufferGuard<'_> {
+        self.reserve(len, align);
+        let ptr = if len == 0 {
+            // Zero-sized requests don't touch the allocation, but the empty
+            // slice still has to be aligned.
+            align as *mut MaybeUninit<u8>
+        } else {
+            self.ptr.as_ptr() as *mut MaybeUninit<u8>
+        };
+        ReusableRawBufferGuard {
+            // SAFETY: `reserve` ensured the allocation holds at least `len`
+            // bytes with at least the requested alignment, and `&mut self`
+            // guarantees exclusive access to it.
+            bytes: unsafe { slice::from_raw_parts_mut(ptr, len) },
+        }
+    }
+
+    fn reserve(&mut self, len: usize, align: usize) {
+        let fits = len <= self.layout.size() && align <= self.layout.align();
+        assert!(align.is_power_of_two(), "align must be a power of two");
+        if fits || len == 0 {
+            return;
+        }
+
+        let layout =
+            Layout::from_size_align(len.max(self.layout.size()), align.max(self.layout.align()))
+                .expect("requested buffer size overflows isize");
+        // SAFETY: `layout` has a non-zero size, since `len` is non-zero.
+        let ptr = unsafe { alloc::alloc(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        self.release();
+        self.ptr = ptr;
+        self.layout = layout;
+    }
+
+    fn release(&mut self) {
+        if self.layout.size() > 0 {
+            // SAFETY: `ptr` was allocated with `layout`.
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+        }
+    }
+}
+
+impl Drop for ReusableRawBuffer {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Exclusive access to a slice of a [`ReusableRawBuffer`]'s allocation.
+///
+/// The guard dereferences to `[MaybeUninit<u8>]`. Nothing happens when it is
+/// dropped: the bytes are left as they are for the next user to overwrite.
+pub struct ReusableRawBufferGuard<'parent> {
+    bytes: &'parent mut [MaybeUninit<u8>],
+}
+
+impl<'parent> ReusableRawBufferGuard<'parent> {
+    /// Fills the bytes with zeroes and returns them as initialized bytes.
+    pub fn zeroed(&mut self) -> &mut [u8] {
+        for byte in self.bytes.iter_mut() {
+            *byte = MaybeUninit::new(0);
+        }
+        // SAFETY: Every byte was just initialized.
+        unsafe { &mut *(self.bytes as *mut [MaybeUninit<u8>] as *mut [u8]) }
+    }
+}
+
+impl<'parent> Deref for ReusableRawBufferGuard<'parent> {
+    type Target = [MaybeUninit<u8>];
+
+    fn deref(&self) -> &Self::Target {
+        self.bytes
+    }
+}
+
+impl<'parent> DerefMut for ReusableRawBufferGuard<'parent> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_grow_only_when_needed() {
+        let mut buffer = ReusableRawBuffer::default();
+        let ptr = buffer.recycle(100, 16).as_ptr();
+        assert_eq!(ptr as usize % 16, 0);
+
+        assert_eq!(buffer.recycle(50, 8).as_ptr(), ptr);
+        assert_eq!(buffer.recycle(100, 16).as_ptr(), ptr);
+        assert_eq!((buffer.capacity(), buffer.align()), (100, 16));
+
+        let aligned = buffer.recycle(10, 128).as_ptr();
+        assert_eq!(aligned as usize % 128, 0);
+        assert_eq!((buffer.capacity(), buffer.align()), (100, 128));
+    }
+
+    #[test]
+    fn zero_sized_requests_are_aligned_and_empty() {
+        let mut buffer = ReusableRawBuffer::with_capacity(0, 1);
+        let guard = buffer.recycle(0, 32);
+        assert!(guard.is_empty());
+        assert_eq!(guard.as_ptr() as usize % 32, 0);
+        assert_eq!(buffer.capacity(), 0);
+    }
+
+    #[test]
+    fn zeroed_bytes_are_initialized() {
+        let mut buffer = ReusableRawBuffer::with_capacity(8, 8);
+        buffer.recycle(8, 8).zeroed().copy_from_slice(b"previous");
+        let mut guard = buffer.recycle(8, 8);
+        assert_eq!(guard.zeroed(), &[0; 8]);
+    }
+
+    #[test]
+    #[should_panic(expected = "align must be a power of two")]
+    fn alignments_must_be_powers_of_two() {
+        ReusableRawBuffer::default().recycle(8, 3);
+    }
+}
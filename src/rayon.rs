@@ -0,0 +1,206 @@
+//! Parallel collection into pooled buffers for [rayon](https://docs.rs/rayon).
+//!
+//! `ParallelIterator::collect` into a `Vec` allocates fresh buffers for the
+//! pieces each worker produces, and then again for the result. In a loop that
+//! collects every frame or every batch, that is a steady storm of large
+//! allocations. [`ParCollectReusing::par_collect_reusing`] takes all of those
+//! buffers from a [`VecPool`] instead: every rayon job collects into a pooled
+//! `Vec`, and the pieces are concatenated, in order, into one more pooled
+//! `Vec` that is returned. The intermediate buffers go straight back to the
+//! pool.
+//!
+//! This module is only available with the `rayon` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use rayon::prelude::*;
+//! use triple_r::{rayon::ParCollectReusing, VecPool};
+//!
+//! let pool = VecPool::<u64>::new();
+//!
+//! for frame in 0..3u64 {
+//!     let squares = (0..10_000u64)
+//!         .into_par_iter()
+//!         .map(|i| i * i + frame)
+//!         .par_collect_reusing(&pool);
+//!     assert_eq!(squares.len(), 10_000);
+//!     assert_eq!(squares[3], 9 + frame);
+//! } // `squares` goes back to the pool, ready for the next frame.
+//! ```
+//!
+//! [`ForEachWithScratch::for_each_with_scratch`] covers the other common
+//! case, where every item needs a scratch container of its own while it is
+//! processed. Each rayon job takes one container from a
+//! [`ReusablePool`] and hands it to every item it processes, so the workers
+//! never share a container and never allocate a new one once the pool is
+//! warm.
+//!
+//! ```
+//! use rayon::prelude::*;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use triple_r::{rayon::ForEachWithScratch, ReusablePool};
+//!
+//! let words = ReusablePool::<Vec<char>>::new();
+//! let palindromes = AtomicUsize::new(0);
+//!
+//! ["level", "rayon", "noon", "pool"]
+//!     .into_par_iter()
+//!     .for_each_with_scratch(&words, |chars, word| {
+//!         chars.extend(word.chars());
+//!         if chars.iter().eq(chars.iter().rev()) {
+//!             palindromes.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     });
+//!
+//! assert_eq!(palindromes.into_inner(), 2);
+//! assert!(words.available() >= 1);
+//! ```
+use crate::{PooledVec, Recyclable, ReusablePool, VecPool};
+use rayon::iter::ParallelIterator;
+
+/// Extends parallel iterators with
+/// [`par_collect_reusing`](ParCollectReusing::par_collect_reusing).
+pub trait ParCollectReusing: ParallelIterator {
+    /// Collects the items, in order, into a buffer taken from `pool`.
+    ///
+    /// Each rayon job collects its share of the items into a buffer from the
+    /// pool as well. Once the results are concatenated, those buffers are
+    /// returned to the pool, so after the first call, a loop that collects
+    /// similar amounts of data does not allocate at all.
+    fn par_collect_reusing(self, pool: &VecPool<Self::Item>) -> PooledVec<'_, Self::Item>;
+}
+
+impl<I> ParCollectReusing for I
+where
+    I: ParallelIterator,
+{
+    fn par_collect_reusing(self, pool: &VecPool<Self::Item>) -> PooledVec<'_, Self::Item> {
+        let pieces = self
+            .fold(
+                || pool.take(),
+                |mut piece, item| {
+                    piece.push(item);
+                    piece
+                },
+            )
+            .collect_vec_list();
+
+        let mut result = pool.get();
+        let len = pieces.iter().flatten().map(Vec::len).sum();
+        result.reserve(len);
+        for mut piece in pieces.into_iter().flatten() {
+            result.append(&mut piece);
+            pool.put(piece);
+        }
+        result
+    }
+}
+
+/// Extends parallel iterators with
+/// [`for_each_with_scratch`](ForEachWithScratch::for_each_with_scratch).
+pub trait ForEachWithScratch: ParallelIterator {
+    /// Calls `op` on every item with a scratch container taken from `pool`.
+    ///
+    /// Each rayon job takes a container from the pool, or creates one with
+    /// [`Default`] if the pool is empty, and uses it for all of the items it
+    /// processes. The container is cleared before every item, so `op` always
+    /// starts with an empty one, and it goes back to the pool when the job is
+    /// done, even if `op` panics.
+    fn for_each_with_scratch<T, F>(self, pool: &ReusablePool<T>, op: F)
+    where
+        T: Recyclable + Default + Send,
+        F: Fn(&mut T, Self::Item) + Sync + Send;
+}
+
+impl<I> ForEachWithScratch for I
+where
+    I: ParallelIterator,
+{
+    fn for_each_with_scratch<T, F>(self, pool: &ReusablePool<T>, op: F)
+    where
+        T: Recyclable + Default + Send,
+        F: Fn(&mut T, Self::Item) + Sync + Send,
+    {
+        self.for_each_init(
+            || Scratch {
+                object: Some(pool.take()),
+                pool,
+            },
+            |scratch, item| {
+                let object = scratch
+                    .object
+                    .as_mut()
+                    .expect("scratch is only taken back when the job ends");
+                object.clear();
+                op(object, item);
+            },
+        );
+    }
+}
+
+/// A container a rayon job borrowed from a [`ReusablePool`], returned when
+/// the job is done.
+struct Scratch<'pool, T: Recyclable> {
+    object: Option<T>,
+    pool: &'pool ReusablePool<T>,
+}
+
+impl<T: Recyclable> Drop for Scratch<'_, T> {
+    fn drop(&mut self) {
+        if let Some(object) = self.object.take() {
+            self.pool.put(object);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn results_keep_their_order() {
+        let pool = VecPool::<usize>::new();
+        let collected = (0..50_000).into_par_iter().par_collect_reusing(&pool);
+        assert!(collected.iter().copied().eq(0..50_000));
+    }
+
+    #[test]
+    fn all_buffers_return_to_the_pool() {
+        let pool = VecPool::<u32>::new();
+        let collected = (0..1_000u32).into_par_iter().par_collect_reusing(&pool);
+        let ptr = collected.as_ptr();
+        let pieces = pool.available();
+        drop(collected);
+
+        assert_eq!(pool.available(), pieces + 1);
+        // The result buffer went back last, so it is handed out first.
+        let reused = pool.take();
+        assert_eq!(reused.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn filtered_iterators_are_supported() {
+        let pool = VecPool::<u32>::new();
+        let evens = (0..100u32)
+            .into_par_iter()
+            .filter(|i| i % 2 == 0)
+            .par_collect_reusing(&pool);
+        assert_eq!(evens.len(), 50);
+        assert_eq!(evens[49], 98);
+    }
+
+    #[test]
+    fn scratch_is_empty_for_every_item_and_returned() {
+        let pool = ReusablePool::<String>::with_max_idle(64);
+        pool.put(String::with_capacity(32));
+        (0..1_000u32)
+            .into_par_iter()
+            .for_each_with_scratch(&pool, |scratch, i| {
+                assert!(scratch.is_empty());
+                scratch.push_str(&i.to_string());
+            });
+        assert!(pool.available() >= 1);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    Recyclable, Reset, Reusable, ReusableBTreeMap, ReusableBTreeMapGuard, ReusableBTreeSet,
+    ReusableBTreeSetGuard, ReusableBinaryHeap, ReusableBinaryHeapGuard, ReusableCStringBuf,
+    ReusableCStringBufGuard, ReusableGuard, ReusableHashMap, ReusableHashMapGuard,
+    ReusableNestedVec, ReusableNestedVecGuard, ReusableSmallString, ReusableSmallStringGuard,
+    ReusableString, ReusableStringGuard, ReusableVec, ReusableVecDeque, ReusableVecDequeGuard,
+    ReusableVecGuard,
+};
+use std::hash::BuildHasher;
+
+/// A reusable container that can be recycled without naming any types.
+///
+/// Every reusable type in this crate implements `Recycle` by recycling into
+/// its own element types, so [`Recycle::recycle`] is the same as calling the
+/// inherent `recycle_same` method. That makes it possible to recycle several
+/// containers through one call: with the `derive` feature, `#[derive(Recycle)]`
+/// implements this trait for a struct of reusable containers, returning a
+/// guard-of-guards struct whose fields are all cleared when it is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{Recycle, ReusableString, ReusableVec};
+///
+/// let mut ids = ReusableVec::<u32>::default();
+/// let mut name = ReusableString::default();
+///
+/// let mut ids_guard = Recycle::recycle(&mut ids);
+/// let mut name_guard = Recycle::recycle(&mut name);
+/// ids_guard.push(7);
+/// name_guard.push_str("seven");
+/// ```
+pub trait Recycle {
+    /// The guard returned by [`recycle`](Recycle::recycle).
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    /// Returns a guard with exclusive access to the empty container.
+    fn recycle(&mut self) -> Self::Guard<'_>;
+}
+
+impl<T: 'static> Recycle for ReusableVec<T> {
+    type Guard<'a> = ReusableVecGuard<'a, T, T>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableVec::recycle_same(self)
+    }
+}
+
+impl<T: 'static> Recycle for ReusableVecDeque<T> {
+    type Guard<'a> = ReusableVecDequeGuard<'a, T, T>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableVecDeque::recycle_same(self)
+    }
+}
+
+impl<T: Ord + 'static> Recycle for ReusableBinaryHeap<T> {
+    type Guard<'a> = ReusableBinaryHeapGuard<'a, T, T>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableBinaryHeap::recycle_same(self)
+    }
+}
+
+impl<K, V, S> Recycle for ReusableHashMap<K, V, S>
+where
+    K: 'static,
+    V: 'static,
+    S: BuildHasher + Default + 'static,
+{
+    type Guard<'a> = ReusableHashMapGuard<'a, K, V, K, V, S>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableHashMap::recycle_same(self)
+    }
+}
+
+impl<K, V> Recycle for ReusableBTreeMap<K, V>
+where
+    K: 'static,
+    V: 'static,
+{
+    type Guard<'a> = ReusableBTreeMapGuard<'a, K, V, K, V>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableBTreeMap::recycle_same(self)
+    }
+}
+
+impl<T: 'static> Recycle for ReusableBTreeSet<T> {
+    type Guard<'a> = ReusableBTreeSetGuard<'a, T, T>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableBTreeSet::recycle_same(self)
+    }
+}
+
+impl<B: Reset + Default + 'static> Recycle for ReusableNestedVec<B> {
+    type Guard<'a> = ReusableNestedVecGuard<'a, B, B>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableNestedVec::recycle_same(self)
+    }
+}
+
+impl Recycle for ReusableString {
+    type Guard<'a> = ReusableStringGuard<'a>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableString::recycle(self)
+    }
+}
+
+impl<const N: usize> Recycle for ReusableSmallString<N> {
+    type Guard<'a> = ReusableSmallStringGuard<'a, N>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableSmallString::recycle(self)
+    }
+}
+
+impl Recycle for ReusableCStringBuf {
+    type Guard<'a> = ReusableCStringBufGuard<'a>;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        ReusableCStringBuf::recycle(self)
+    }
+}
+
+impl<C: Recyclable> Recycle for Reusable<C> {
+    type Guard<'a>
+        = ReusableGuard<'a, C>
+    where
+        C: 'a;
+
+    fn recycle(&mut self) -> Self::Guard<'_> {
+        Reusable::recycle(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recycle_twice<R: Recycle>(reusable: &mut R, fill: impl Fn(&mut R::Guard<'_>)) {
+        fill(&mut reusable.recycle());
+        fill(&mut reusable.recycle());
+    }
+
+    #[test]
+    fn generic_recycling_clears_between_uses() {
+        let mut map = ReusableHashMap::<u32, String>::default();
+        recycle_twice(&mut map, |guard| {
+            assert!(guard.is_empty());
+            guard.insert(1, "one".to_string());
+        });
+
+        let mut string = ReusableString::default();
+        recycle_twice(&mut string, |guard| {
+            assert!(guard.is_empty());
+            guard.push_str("text");
+        });
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{Recycle, ReusableHashMap, ReusableString, ReusableVec};
+use std::{
+    any::{self, Any},
+    collections::BTreeMap,
+    fmt,
+    hash::{BuildHasher, Hash},
+};
+
+/// A reusable container that can be kept in a [`BufferRegistry`].
+pub trait RegistryBuffer: Recycle + Send + 'static {
+    /// The estimated number of heap bytes the container retains.
+    fn memory_footprint(&self) -> usize;
+
+    /// Releases the retained allocation.
+    fn shrink_to_fit(&mut self);
+}
+
+impl<T: Send + 'static> RegistryBuffer for ReusableVec<T> {
+    fn memory_footprint(&self) -> usize {
+        ReusableVec::memory_footprint(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        ReusableVec::shrink_to_fit(self);
+    }
+}
+
+impl RegistryBuffer for ReusableString {
+    fn memory_footprint(&self) -> usize {
+        ReusableString::memory_footprint(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        ReusableString::shrink_to_fit(self);
+    }
+}
+
+impl<K, V, S> RegistryBuffer for ReusableHashMap<K, V, S>
+where
+    K: Eq + Hash + Send + 'static,
+    V: Send + 'static,
+    S: BuildHasher + Default + Send + 'static,
+{
+    fn memory_footprint(&self) -> usize {
+        ReusableHashMap::memory_footprint(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        ReusableHashMap::shrink_to_fit(self);
+    }
+}
+
+/// A central, named collection of reusable containers.
+///
+/// In a large application, every subsystem keeping its own scratch buffers
+/// makes it hard to tell how much memory they retain, or to give it back.
+/// A `BufferRegistry` owns them all under `&'static str` names, like
+/// `"parser.tokens"`. A subsystem [`register`](BufferRegistry::register)s
+/// its buffer once, and later [`recycle`](BufferRegistry::recycle)s it by
+/// name and type. The application can then report the
+/// [footprint](BufferRegistry::memory_footprints) of every buffer, and
+/// [trim](BufferRegistry::shrink_to_fit) them all after a burst of work.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{BufferRegistry, ReusableString, ReusableVec};
+///
+/// let mut registry = BufferRegistry::new();
+/// registry.register("parser.tokens", ReusableVec::<u32>::with_capacity(1024));
+/// registry.register("render.line", ReusableString::default());
+///
+/// {
+///     let mut tokens = registry
+///         .recycle::<ReusableVec<u32>>("parser.tokens")
+///         .expect("registered as a ReusableVec<u32>");
+///     tokens.extend([1, 2, 3]);
+/// }
+///
+/// for (name, bytes) in registry.memory_footprints() {
+///     println!("{}: {} bytes", name, bytes);
+/// }
+/// assert!(registry.memory_footprint() >= 4096);
+///
+/// registry.shrink_to_fit();
+/// assert_eq!(registry.memory_footprint(), 0);
+/// ```
+#[derive(Default)]
+pub struct BufferRegistry {
+    entries: BTreeMap<&'static str, Entry>,
+}
+
+/// A registered container, with its type erased.
+struct Entry {
+    buffer: Box<dyn Any + Send>,
+    type_name: &'static str,
+    memory_footprint: fn(&dyn Any) -> usize,
+    shrink_to_fit: fn(&mut dyn Any),
+}
+
+impl Entry {
+    fn new<R: RegistryBuffer>(buffer: R) -> Self {
+        Self {
+            buffer: Box::new(buffer),
+            type_name: any::type_name::<R>(),
+            memory_footprint: |buffer| buffer.downcast_ref::<R>().map_or(0, R::memory_footprint),
+            shrink_to_fit: |buffer| {
+                if let Some(buffer) = buffer.downcast_mut::<R>() {
+                    buffer.shrink_to_fit();
+                }
+            },
+        }
+    }
+}
+
+impl BufferRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `buffer` under `name`, replacing whatever was registered
+    /// under it before, and returns it.
+    pub fn register<R: RegistryBuffer>(&mut self, name: &'static str, buffer: R) -> &mut R {
+        self.entries.insert(name, Entry::new(buffer));
+        self.entries
+            .get_mut(name)
+            .expect("the entry was just inserted")
+            .buffer
+            .downcast_mut()
+            .expect("the entry was just registered with this type")
+    }
+
+    /// Recycles the buffer registered under `name`.
+    ///
+    /// Returns `None` if nothing is registered under `name`, or if it was
+    /// registered with another type than `R`.
+    pub fn recycle<R: RegistryBuffer>(&mut self, name: &str) -> Option<R::Guard<'_>> {
+        self.get_mut::<R>(name).map(Recycle::recycle)
+    }
+
+    /// Returns the buffer registered under `name`, if it has type `R`.
+    pub fn get_mut<R: RegistryBuffer>(&mut self, name: &str) -> Option<&mut R> {
+        self.entries.get_mut(name)?.buffer.downcast_mut()
+    }
+
+    /// Whether anything is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Removes the buffer registered under `name`, releasing its allocation.
+    ///
+    /// Returns whether anything was registered under `name`.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    /// The names of the registered buffers, in order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.keys().copied()
+    }
+
+    /// The estimated number of heap bytes retained by each registered
+    /// buffer, by name, in order of the names.
+    pub fn memory_footprints(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.entries
+            .iter()
+            .map(|(&name, entry)| (name, (entry.memory_footprint)(&*entry.buffer)))
+    }
+
+    /// The estimated number of heap bytes retained by all registered buffers.
+    pub fn memory_footprint(&self) -> usize {
+        self.memory_footprints().map(|(_, bytes)| bytes).sum()
+    }
+
+    /// Releases the allocations retained by every registered buffer.
+    pub fn shrink_to_fit(&mut self) {
+        for entry in self.entries.values_mut() {
+            (entry.shrink_to_fit)(&mut *entry.buffer);
+        }
+    }
+}
+
+impl fmt::Debug for BufferRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.entries
+                    .iter()
+                    .map(|(name, entry)| (name, entry.type_name)),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_looked_up_by_name_and_type() {
+        let mut registry = BufferRegistry::new();
+        registry.register("words", ReusableVec::<String>::default());
+        registry.register("index", ReusableHashMap::<u32, u32>::with_capacity(64));
+
+        registry
+            .recycle::<ReusableVec<String>>("words")
+            .unwrap()
+            .push("retained".to_string());
+        assert!(registry.recycle::<ReusableVec<u32>>("words").is_none());
+        assert!(registry.recycle::<ReusableString>("missing").is_none());
+        assert!(
+            registry
+                .get_mut::<ReusableVec<String>>("words")
+                .unwrap()
+                .capacity()
+                >= 1
+        );
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["index", "words"]);
+        assert!(registry.unregister("index"));
+        assert!(!registry.contains("index"));
+    }
+}
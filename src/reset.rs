@@ -0,0 +1,111 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::BuildHasher,
+};
+
+/// A value that can be returned to an empty state in place, keeping any
+/// allocations it owns.
+///
+/// `Reset` is what lets a recycled container keep its elements alive instead
+/// of dropping them: [`ReusableVec::recycle_resettable`](crate::ReusableVec::recycle_resettable)
+/// resets each element when its guard is dropped, and
+/// [`ReusableNestedVec`](crate::ReusableNestedVec) resets its inner buffers.
+/// The standard collections implement it by clearing themselves.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::Reset;
+///
+/// #[derive(Default)]
+/// struct Message {
+///     id: u64,
+///     body: String,
+///     tags: Vec<String>,
+/// }
+///
+/// impl Reset for Message {
+///     fn reset(&mut self) {
+///         self.id = 0;
+///         self.body.reset();
+///         self.tags.reset();
+///     }
+/// }
+/// ```
+pub trait Reset {
+    /// Returns the value to its empty state, keeping its allocations.
+    fn reset(&mut self);
+}
+
+impl<T> Reset for Vec<T> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Reset for VecDeque<T> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Reset for BinaryHeap<T> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl Reset for String {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V, S: BuildHasher> Reset for HashMap<K, V, S> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, S: BuildHasher> Reset for HashSet<T, S> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V> Reset for BTreeMap<K, V> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Reset for BTreeSet<T> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Reset + ?Sized> Reset for Box<T> {
+    fn reset(&mut self) {
+        (**self).reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collections_keep_their_capacity() {
+        let mut map: HashMap<u32, u32> = (0..32).map(|i| (i, i)).collect();
+        let capacity = map.capacity();
+        map.reset();
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), capacity);
+
+        let mut boxed = Box::new(String::from("boxed"));
+        boxed.reset();
+        assert!(boxed.is_empty());
+        assert!(boxed.capacity() >= 5);
+    }
+}
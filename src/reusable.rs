@@ -0,0 +1,248 @@
+use crate::MemoryFootprint;
+use std::{
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    fmt,
+    hash::{BuildHasher, Hash},
+    ops::{Deref, DerefMut},
+};
+
+/// A container that can be emptied while keeping its allocation.
+///
+/// Implementing `Recyclable` for a custom container, such as a rope or a
+/// trie, gives it the same recycle/guard treatment as the built-in reusable
+/// types through [`Reusable`].
+///
+/// [`clear`](Recyclable::clear) must keep whatever the container has
+/// allocated, since that is what `Reusable` exists to retain.
+pub trait Recyclable {
+    /// Removes the contents, keeping the allocation.
+    fn clear(&mut self);
+
+    /// The number of elements the container can hold without allocating.
+    fn capacity(&self) -> usize;
+}
+
+impl<T> Recyclable for Vec<T> {
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+}
+
+impl<T> Recyclable for VecDeque<T> {
+    fn clear(&mut self) {
+        VecDeque::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        VecDeque::capacity(self)
+    }
+}
+
+impl<T: Ord> Recyclable for BinaryHeap<T> {
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        BinaryHeap::capacity(self)
+    }
+}
+
+impl Recyclable for String {
+    fn clear(&mut self) {
+        String::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        String::capacity(self)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Recyclable for HashMap<K, V, S> {
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        HashMap::capacity(self)
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher> Recyclable for HashSet<T, S> {
+    fn clear(&mut self) {
+        HashSet::clear(self);
+    }
+
+    fn capacity(&self) -> usize {
+        HashSet::capacity(self)
+    }
+}
+
+/// A reusable wrapper around any [`Recyclable`] container.
+///
+/// `Reusable` is the generic form of [`ReusableVec`](crate::ReusableVec) and
+/// friends: [`recycle`](Reusable::recycle) returns a [`ReusableGuard`] with
+/// exclusive access to the container, and the container is cleared, keeping
+/// its allocation, when the guard is dropped. Unlike the dedicated types, it
+/// cannot cast the container's element types, since it knows nothing about
+/// them.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::{Recyclable, Reusable};
+///
+/// /// A toy rope that keeps its chunk list between uses.
+/// #[derive(Default)]
+/// struct Rope {
+///     chunks: Vec<String>,
+/// }
+///
+/// impl Recyclable for Rope {
+///     fn clear(&mut self) {
+///         self.chunks.clear();
+///     }
+///
+///     fn capacity(&self) -> usize {
+///         self.chunks.capacity()
+///     }
+/// }
+///
+/// let mut reusable = Reusable::<Rope>::default();
+///
+/// for words in [["a", "b", "c"], ["d", "e", "f"]] {
+///     let mut rope = reusable.recycle();
+///     rope.chunks.extend(words.iter().map(|w| w.to_string()));
+/// }
+///
+/// assert!(reusable.capacity() >= 3);
+/// ```
+#[derive(Default)]
+pub struct Reusable<C> {
+    inner: C,
+}
+
+impl<C: Recyclable> fmt::Debug for Reusable<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reusable")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<C: Recyclable> Reusable<C> {
+    /// Wraps `container`, clearing it so the first guard starts out empty.
+    pub fn new(mut container: C) -> Self {
+        container.clear();
+        Self { inner: container }
+    }
+
+    /// Returns a guard for the container.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    pub fn recycle(&mut self) -> ReusableGuard<'_, C> {
+        // A guard that was leaked instead of dropped left its contents behind.
+        self.inner.clear();
+        ReusableGuard {
+            inner: &mut self.inner,
+        }
+    }
+
+    /// Recycles the container for the duration of `f`, clearing it when `f`
+    /// returns, so the guard can't be held past the closure.
+    pub fn recycle_with<R>(&mut self, f: impl FnOnce(&mut ReusableGuard<'_, C>) -> R) -> R {
+        f(&mut self.recycle())
+    }
+
+    /// The capacity of the retained container.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// The estimated number of heap bytes the retained container holds on
+    /// to.
+    pub fn memory_footprint(&self) -> usize
+    where
+        C: MemoryFootprint,
+    {
+        self.inner.memory_footprint()
+    }
+
+    /// Returns the retained container, which is always empty.
+    pub fn into_inner(mut self) -> C {
+        self.inner.clear();
+        self.inner
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to the container
+/// of a [`Reusable`].
+///
+/// When the guard is dropped, it clears the container.
+pub struct ReusableGuard<'parent, C: Recyclable> {
+    inner: &'parent mut C,
+}
+
+impl<'parent, C: Recyclable> Deref for ReusableGuard<'parent, C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl<'parent, C: Recyclable> ReusableGuard<'parent, C> {
+    /// The estimated number of heap bytes the container holds on to,
+    /// counting its unused capacity.
+    pub fn memory_footprint(&self) -> usize
+    where
+        C: MemoryFootprint,
+    {
+        self.inner.memory_footprint()
+    }
+}
+
+impl<'parent, C: Recyclable> DerefMut for ReusableGuard<'parent, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner
+    }
+}
+
+impl<'parent, C: Recyclable> Drop for ReusableGuard<'parent, C> {
+    /// Clears the container, preserving its allocation.
+    fn drop(&mut self) {
+        self.inner.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containers_are_cleared_and_keep_capacity() {
+        let mut reusable = Reusable::<HashMap<u32, u32>>::default();
+        {
+            let mut map = reusable.recycle();
+            map.extend((0..64).map(|i| (i, i)));
+        }
+        let capacity = reusable.capacity();
+        assert!(capacity >= 64);
+
+        let map = reusable.recycle();
+        assert!(map.is_empty());
+        assert_eq!(Recyclable::capacity(&*map), capacity);
+    }
+
+    #[test]
+    fn new_clears_the_wrapped_container() {
+        let mut reusable = Reusable::new(vec![1, 2, 3]);
+        assert!(reusable.recycle().is_empty());
+        assert!(reusable.into_inner().capacity() >= 3);
+    }
+}
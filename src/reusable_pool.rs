@@ -0,0 +1,225 @@
+use crate::Recyclable;
+#[cfg(feature = "parking_lot")]
+use parking_lot::{Mutex, MutexGuard};
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{Mutex, MutexGuard};
+use std::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::Arc,
+};
+
+/// A thread-safe pool of any number of recycled [`Recyclable`] containers.
+///
+/// A [`Reusable`](crate::Reusable) hands out one guard at a time through
+/// `&mut self`. A `ReusablePool` keeps a free list of containers instead, so
+/// several workers can each hold one at the same time.
+/// [`get`](ReusablePool::get) returns an owned [`ReusablePoolGuard`] that
+/// keeps the pool alive through an [`Arc`], so it can be moved into a
+/// spawned thread or task. The container is cleared, keeping its allocation,
+/// and put back when the guard is dropped.
+///
+/// Like [`VecPool`](crate::VecPool), the free list is guarded by a
+/// [`std::sync::Mutex`], or a `parking_lot::Mutex` with the `parking_lot`
+/// feature, and a panic while a container is checked out never leaves the
+/// pool unusable.
+///
+/// # Examples
+///
+/// ```
+/// use std::{collections::HashMap, sync::Arc, thread};
+/// use triple_r::ReusablePool;
+///
+/// let pool = Arc::new(ReusablePool::<HashMap<u32, u32>>::new());
+///
+/// // Four maps are checked out at once, and each moves to its own thread.
+/// let maps: Vec<_> = (0..4).map(|_| pool.get()).collect();
+/// let workers: Vec<_> = (0..)
+///     .zip(maps)
+///     .map(|(i, mut counts)| {
+///         thread::spawn(move || {
+///             counts.extend((0..100).map(|n| (n, n * i)));
+///             counts.len()
+///         })
+///     })
+///     .collect();
+///
+/// for worker in workers {
+///     assert_eq!(worker.join().unwrap(), 100);
+/// }
+/// // The four maps are back, empty, with their tables.
+/// assert_eq!(pool.available(), 4);
+/// assert!(pool.get().capacity() >= 100);
+/// ```
+pub struct ReusablePool<T> {
+    free: Mutex<Vec<T>>,
+    max_idle: usize,
+}
+
+impl<T> Default for ReusablePool<T> {
+    /// Creates a new, empty `ReusablePool`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for ReusablePool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusablePool")
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+impl<T> ReusablePool<T> {
+    /// Creates a new, empty `ReusablePool`. Containers are created on demand
+    /// with [`Default`].
+    pub const fn new() -> Self {
+        Self::with_max_idle(usize::MAX)
+    }
+
+    /// Creates a pool that keeps at most `max_idle` containers waiting.
+    ///
+    /// Containers handed back to a full pool are dropped.
+    pub const fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            free: Mutex::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// Takes a container out of the pool, returning a guard that puts it back
+    /// when dropped.
+    ///
+    /// If the pool has no container available, a new one is created with
+    /// [`Default`].
+    pub fn get(self: &Arc<Self>) -> ReusablePoolGuard<T>
+    where
+        T: Recyclable + Default,
+    {
+        ReusablePoolGuard {
+            object: ManuallyDrop::new(self.take()),
+            pool: Arc::clone(self),
+        }
+    }
+
+    /// Takes a container out of the pool, or creates one if there is none.
+    pub(crate) fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.lock().pop().unwrap_or_default()
+    }
+
+    /// Clears `object` and adds it to the pool, unless the pool is full.
+    ///
+    /// `object` does not need to have come from this pool.
+    pub fn put(&self, mut object: T)
+    where
+        T: Recyclable,
+    {
+        object.clear();
+        let mut free = self.lock();
+        if free.len() < self.max_idle {
+            free.push(object);
+        }
+    }
+
+    /// The number of containers currently waiting in the pool.
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    #[cfg(not(feature = "parking_lot"))]
+    fn lock(&self) -> MutexGuard<'_, Vec<T>> {
+        // The free list is always in a consistent state, even if a thread
+        // panicked while holding the lock.
+        self.free
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[cfg(feature = "parking_lot")]
+    fn lock(&self) -> MutexGuard<'_, Vec<T>> {
+        self.free.lock()
+    }
+}
+
+/// An owned guard over a container taken from a [`ReusablePool`].
+///
+/// When the guard is dropped, the container is cleared and returned to the
+/// pool, preserving its allocation for the next user.
+pub struct ReusablePoolGuard<T: Recyclable> {
+    object: ManuallyDrop<T>,
+    pool: Arc<ReusablePool<T>>,
+}
+
+impl<T: Recyclable> ReusablePoolGuard<T> {
+    /// Detaches the container from the pool, so it is not returned when the
+    /// guard would have been dropped.
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so the container and
+        // the pool handle are each moved out exactly once.
+        unsafe {
+            drop(ptr::read(&this.pool));
+            ManuallyDrop::take(&mut this.object)
+        }
+    }
+}
+
+impl<T: Recyclable> Deref for ReusablePoolGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.object
+    }
+}
+
+impl<T: Recyclable> DerefMut for ReusablePoolGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.object
+    }
+}
+
+impl<T: Recyclable + fmt::Debug> fmt::Debug for ReusablePoolGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.object, f)
+    }
+}
+
+impl<T: Recyclable> Drop for ReusablePoolGuard<T> {
+    /// Clears the container and returns it to the pool.
+    fn drop(&mut self) {
+        // SAFETY: The container is only taken here, and the guard is gone
+        // afterwards.
+        let object = unsafe { ManuallyDrop::take(&mut self.object) };
+        self.pool.put(object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_can_be_held_at_the_same_time() {
+        let pool = Arc::new(ReusablePool::<Vec<u32>>::with_max_idle(1));
+        let mut first = pool.get();
+        let mut second = pool.get();
+        first.extend(0..10);
+        second.push(1);
+        drop(first);
+        drop(second);
+
+        // Only one of them was kept, empty.
+        assert_eq!(pool.available(), 1);
+        assert!(pool.get().is_empty());
+
+        let detached = pool.get().into_inner();
+        assert_eq!(pool.available(), 0);
+        assert!(detached.is_empty());
+    }
+}
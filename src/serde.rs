@@ -0,0 +1,333 @@
+//! Serde support for recycled collections.
+//!
+//! Deserializing a request body into a `Vec` or a `HashMap` allocates a new
+//! collection for every request. The seeds in this module implement
+//! [`DeserializeSeed`] instead: [`RecycleVecSeed`] and [`RecycleMapSeed`]
+//! recycle a [`ReusableVec`] or a [`ReusableHashMap`] and fill the guard as
+//! the input is read, so a loop over JSON or CBOR messages keeps one
+//! allocation sized for its largest message.
+//!
+//! Like [`recycle`](ReusableVec::recycle), the seeds can cast the stored
+//! types, so a `ReusableVec<&'static str>` can hold strings borrowed from
+//! each input in turn.
+//!
+//! The guards implement [`Serialize`] as well, as the collection they hold,
+//! so results built in a recycled collection can be written out without
+//! copying them into a fresh one first.
+//!
+//! This module is only available with the `serde` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::de::DeserializeSeed;
+//! use triple_r::{serde::RecycleVecSeed, ReusableVec};
+//!
+//! let mut names = ReusableVec::<&'static str>::default();
+//!
+//! for input in [r#"["ada", "grace"]"#, r#"["barbara"]"#] {
+//!     let mut de = serde_json::Deserializer::from_str(input);
+//!     let guard = RecycleVecSeed::new(&mut names).deserialize(&mut de).unwrap();
+//!     assert!(guard.iter().all(|name| input.contains(name)));
+//! }
+//! ```
+use crate::{
+    ReusableBTreeMapGuard, ReusableBTreeSetGuard, ReusableBinaryHeapGuard, ReusableHashMap,
+    ReusableHashMapGuard, ReusableSmallStringGuard, ReusableStringGuard, ReusableVec,
+    ReusableVecDequeGuard, ReusableVecGuard, ReuseCastInto,
+};
+use serde::{
+    de::{Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor},
+    Serialize, Serializer,
+};
+use std::{
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+
+/// A [`DeserializeSeed`] that deserializes a sequence into a recycled
+/// [`ReusableVec`].
+///
+/// The value it produces is the populated [`ReusableVecGuard`]. If
+/// deserialization fails, the guard is dropped and the `Vec` is cleared,
+/// keeping its allocation.
+pub struct RecycleVecSeed<'parent, T1: 'static, T2> {
+    reusable: &'parent mut ReusableVec<T1>,
+    _target: PhantomData<fn() -> T2>,
+}
+
+impl<'parent, T1: 'static, T2> RecycleVecSeed<'parent, T1, T2> {
+    /// Creates a seed that recycles `reusable`.
+    pub fn new(reusable: &'parent mut ReusableVec<T1>) -> Self {
+        Self {
+            reusable,
+            _target: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'parent, T1, T2> DeserializeSeed<'de> for RecycleVecSeed<'parent, T1, T2>
+where
+    T1: ReuseCastInto<T2> + 'static,
+    T2: Deserialize<'de>,
+{
+    type Value = ReusableVecGuard<'parent, T1, T2>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VecVisitor {
+            guard: self.reusable.recycle(),
+        })
+    }
+}
+
+struct VecVisitor<'parent, T1: 'static, T2> {
+    guard: ReusableVecGuard<'parent, T1, T2>,
+}
+
+impl<'de, 'parent, T1, T2> Visitor<'de> for VecVisitor<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: Deserialize<'de>,
+{
+    type Value = ReusableVecGuard<'parent, T1, T2>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while let Some(element) = seq.next_element()? {
+            self.guard.push(element);
+        }
+        Ok(self.guard)
+    }
+}
+
+/// A [`DeserializeSeed`] that deserializes a map into a recycled
+/// [`ReusableHashMap`].
+///
+/// The value it produces is the populated [`ReusableHashMapGuard`]. Later
+/// entries replace earlier ones with the same key, as with `HashMap`'s own
+/// `Deserialize` impl. If deserialization fails, the guard is dropped and the
+/// map is cleared, keeping its allocation.
+pub struct RecycleMapSeed<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    reusable: &'parent mut ReusableHashMap<K1, V1, S>,
+    _target: PhantomData<fn() -> (K2, V2)>,
+}
+
+impl<'parent, K1, V1, K2, V2, S> RecycleMapSeed<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    /// Creates a seed that recycles `reusable`.
+    pub fn new(reusable: &'parent mut ReusableHashMap<K1, V1, S>) -> Self {
+        Self {
+            reusable,
+            _target: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'parent, K1, V1, K2, V2, S> DeserializeSeed<'de>
+    for RecycleMapSeed<'parent, K1, V1, K2, V2, S>
+where
+    K1: ReuseCastInto<K2> + 'static,
+    V1: ReuseCastInto<V2> + 'static,
+    K2: Deserialize<'de> + Eq + Hash,
+    V2: Deserialize<'de>,
+    S: 'static + BuildHasher + Default,
+{
+    type Value = ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor {
+            guard: self.reusable.recycle(),
+        })
+    }
+}
+
+struct MapVisitor<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    guard: ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>,
+}
+
+impl<'de, 'parent, K1, V1, K2, V2, S> Visitor<'de> for MapVisitor<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Deserialize<'de> + Eq + Hash,
+    V2: Deserialize<'de>,
+    S: 'static + BuildHasher + Default,
+{
+    type Value = ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some((key, value)) = map.next_entry()? {
+            self.guard.insert(key, value);
+        }
+        Ok(self.guard)
+    }
+}
+
+macro_rules! serialize_seq_guard {
+    ($($guard:ident),+ $(,)?) => {$(
+        impl<'parent, T1, T2> Serialize for $guard<'parent, T1, T2>
+        where
+            T1: 'static,
+            T2: Serialize,
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.collect_seq(self.iter())
+            }
+        }
+    )+};
+}
+
+serialize_seq_guard!(
+    ReusableVecGuard,
+    ReusableVecDequeGuard,
+    ReusableBinaryHeapGuard,
+    ReusableBTreeSetGuard,
+);
+
+impl<'parent, K1, V1, K2, V2, S> Serialize for ReusableHashMapGuard<'parent, K1, V1, K2, V2, S>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Serialize,
+    V2: Serialize,
+    S: 'static + BuildHasher + Default,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'parent, K1, V1, K2, V2> Serialize for ReusableBTreeMapGuard<'parent, K1, V1, K2, V2>
+where
+    K1: 'static,
+    V1: 'static,
+    K2: Serialize,
+    V2: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'parent> Serialize for ReusableStringGuard<'parent> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'parent, const N: usize> Serialize for ReusableSmallStringGuard<'parent, N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_borrow_from_each_input() {
+        let mut counts = ReusableHashMap::<&'static str, u32>::default();
+        for (input, expected) in [(r#"{"a": 1, "b": 2}"#, 2), (r#"{"c": 3}"#, 1)] {
+            let owned = input.to_string();
+            let mut de = serde_json::Deserializer::from_str(&owned);
+            let guard = RecycleMapSeed::new(&mut counts)
+                .deserialize(&mut de)
+                .unwrap();
+            assert_eq!(guard.len(), expected);
+            assert_eq!(guard.values().sum::<u32>(), 3);
+        }
+    }
+
+    #[test]
+    fn guards_serialize_as_their_collections() {
+        let mut ids = ReusableVec::<u32>::default();
+        let mut guard = ids.recycle_same();
+        guard.extend([1, 2, 3]);
+        assert_eq!(serde_json::to_string(&guard).unwrap(), "[1,2,3]");
+
+        let mut names = ReusableHashMap::<&'static str, &'static str>::default();
+        let mut guard = names.recycle_same();
+        guard.insert("name", "ada");
+        assert_eq!(serde_json::to_string(&guard).unwrap(), r#"{"name":"ada"}"#);
+
+        let mut text = crate::ReusableString::default();
+        let mut guard = text.recycle();
+        guard.push_str("quoted \"text\"");
+        assert_eq!(
+            serde_json::to_string(&guard).unwrap(),
+            r#""quoted \"text\"""#
+        );
+    }
+
+    #[test]
+    fn errors_leave_the_vec_empty_with_its_allocation() {
+        let mut numbers = ReusableVec::<u32>::default();
+        let capacity = {
+            let mut de = serde_json::Deserializer::from_str("[1, 2, 3, 4]");
+            let guard = RecycleVecSeed::new(&mut numbers)
+                .deserialize(&mut de)
+                .unwrap();
+            assert_eq!(*guard, [1, 2, 3, 4]);
+            guard.capacity()
+        };
+
+        let mut de = serde_json::Deserializer::from_str("[5, 6, \"seven\"]");
+        assert!(RecycleVecSeed::<_, u32>::new(&mut numbers)
+            .deserialize(&mut de)
+            .is_err());
+
+        let guard = numbers.recycle_same();
+        assert!(guard.is_empty());
+        assert_eq!(guard.capacity(), capacity);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{Recyclable, ReusablePool, ReusablePoolGuard};
+use std::{fmt, sync::Arc};
+
+/// A cloneable handle to one recycled container, handing it out as an owned
+/// guard.
+///
+/// The guards of [`ReusableVec`](crate::ReusableVec) and the other reusable
+/// types borrow their parent mutably, so they can't be held across an
+/// `.await` in a spawned task, which must be `'static` and `Send`. A
+/// `SharedReusable` keeps its container behind an [`Arc`] instead.
+/// [`recycle_owned`](SharedReusable::recycle_owned) moves the container out
+/// into a [`ReusablePoolGuard`] that owns a handle to its origin, so it is
+/// `'static`, and `Send` whenever the container is. When the guard is
+/// dropped, on whichever thread, the container is cleared and moved back,
+/// keeping its allocation.
+///
+/// Only one container is retained. If it is already out when
+/// `recycle_owned` is called, a new one is created with [`Default`], and
+/// whichever of the two is returned first is kept. Use a [`ReusablePool`]
+/// directly to retain several.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use triple_r::SharedReusable;
+///
+/// let scratch = SharedReusable::<Vec<u8>>::new();
+///
+/// let mut buffer = scratch.recycle_owned();
+/// let worker = thread::spawn(move || {
+///     buffer.extend_from_slice(b"written on another thread");
+///     // The buffer is returned, cleared, when it is dropped here.
+/// });
+/// worker.join().unwrap();
+///
+/// let buffer = scratch.recycle_owned();
+/// assert!(buffer.is_empty());
+/// assert!(buffer.capacity() >= 25);
+/// ```
+pub struct SharedReusable<T> {
+    pool: Arc<ReusablePool<T>>,
+}
+
+impl<T> SharedReusable<T> {
+    /// Creates a handle with no container yet. The first one is created on
+    /// demand with [`Default`].
+    pub fn new() -> Self {
+        Self {
+            pool: Arc::new(ReusablePool::with_max_idle(1)),
+        }
+    }
+
+    /// Creates a handle retaining `object`, cleared.
+    pub fn with_object(object: T) -> Self
+    where
+        T: Recyclable,
+    {
+        let shared = Self::new();
+        shared.pool.put(object);
+        shared
+    }
+
+    /// Moves the container out into an owned guard, which clears it and moves
+    /// it back when dropped.
+    ///
+    /// If the container is already out, a new one is created with
+    /// [`Default`].
+    pub fn recycle_owned(&self) -> ReusablePoolGuard<T>
+    where
+        T: Recyclable + Default,
+    {
+        self.pool.get()
+    }
+
+    /// Whether the container is currently retained by the handle, rather than
+    /// out in a guard or not created yet.
+    pub fn is_available(&self) -> bool {
+        self.pool.available() > 0
+    }
+}
+
+impl<T> Clone for SharedReusable<T> {
+    /// Returns another handle to the same container.
+    fn clone(&self) -> Self {
+        Self {
+            pool: Arc::clone(&self.pool),
+        }
+    }
+}
+
+impl<T> Default for SharedReusable<T> {
+    /// Creates a handle with no container yet.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for SharedReusable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedReusable")
+            .field("available", &self.is_available())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+    #[test]
+    fn owned_guards_return_the_container_from_other_threads() {
+        let shared = SharedReusable::with_object(HashMap::<u32, u32>::with_capacity(64));
+        let mut map = shared.recycle_owned();
+        assert_send_static(&map);
+        assert!(!shared.is_available());
+
+        map.insert(1, 1);
+        let handle = shared.clone();
+        std::thread::spawn(move || drop(map)).join().unwrap();
+
+        assert!(handle.is_available());
+        let map = shared.recycle_owned();
+        assert!(map.is_empty());
+        assert!(map.capacity() >= 64);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::MemoryFootprint;
+use std::{fmt, ops::Deref};
+
+/// A [`ReusableString`](crate::ReusableString) variant that keeps short
+/// strings inline and only touches its retained heap allocation for long
+/// ones.
+///
+/// Every guard starts out with an inline buffer of `N` bytes. As long as the
+/// contents fit, they never leave it, so a loop that mostly builds short
+/// strings (keys, tokens, small labels) never allocates at all. Once the
+/// contents outgrow the inline buffer, they move into a heap `String` that,
+/// like in `ReusableString`, is cleared but kept when the guard is dropped, so
+/// the occasional long string is recycled too.
+///
+/// The guard dereferences to `str` and implements [`fmt::Write`]. It cannot
+/// hand out a `&mut String`, since the contents are not always in one.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Write;
+/// use triple_r::ReusableSmallString;
+///
+/// let mut reusable = ReusableSmallString::<16>::default();
+///
+/// for id in [7, 42, 1234] {
+///     let mut key = reusable.recycle();
+///     write!(key, "user:{}", id).unwrap();
+///     assert!(key.is_inline());
+/// }
+/// // Nothing was allocated so far.
+/// assert_eq!(reusable.heap_capacity(), 0);
+///
+/// {
+///     let mut line = reusable.recycle();
+///     line.push_str("a string longer than sixteen bytes");
+///     assert!(!line.is_inline());
+/// }
+/// // The heap allocation is kept for the next long string.
+/// assert!(reusable.heap_capacity() >= 34);
+/// ```
+#[derive(Default)]
+pub struct ReusableSmallString<const N: usize = 22> {
+    heap: String,
+}
+
+impl<const N: usize> fmt::Debug for ReusableSmallString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableSmallString")
+            .field("heap_capacity", &self.heap_capacity())
+            .finish()
+    }
+}
+
+impl<const N: usize> ReusableSmallString<N> {
+    /// Returns a guard holding an empty string.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    pub fn recycle(&mut self) -> ReusableSmallStringGuard<'_, N> {
+        // A guard that was leaked instead of dropped left its text behind.
+        self.heap.clear();
+        ReusableSmallStringGuard {
+            inline: [0; N],
+            inline_len: 0,
+            spilled: false,
+            heap: &mut self.heap,
+        }
+    }
+
+    /// Recycles the string for the duration of `f`, clearing it when `f`
+    /// returns, so the guard can't be held past the closure.
+    pub fn recycle_with<R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableSmallStringGuard<'_, N>) -> R,
+    ) -> R {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained heap `String`, which is always empty.
+    pub fn into_inner(mut self) -> String {
+        self.heap.clear();
+        self.heap
+    }
+
+    /// The capacity of the retained heap allocation, which is zero until a
+    /// guard has outgrown its inline buffer.
+    pub fn heap_capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    /// The estimated number of heap bytes the retained `String` holds on to.
+    /// The inline buffer lives in the guard, so it is not counted.
+    pub fn memory_footprint(&self) -> usize {
+        self.heap.memory_footprint()
+    }
+}
+
+/// A RAII guard over the string of a [`ReusableSmallString`].
+///
+/// When the guard is dropped, the retained heap `String` is cleared, keeping
+/// its allocation.
+pub struct ReusableSmallStringGuard<'parent, const N: usize> {
+    inline: [u8; N],
+    inline_len: usize,
+    spilled: bool,
+    heap: &'parent mut String,
+}
+
+impl<'parent, const N: usize> ReusableSmallStringGuard<'parent, N> {
+    /// The estimated number of heap bytes the guard holds on to, which is
+    /// the retained heap `String` whether or not the contents have spilled
+    /// into it.
+    pub fn memory_footprint(&self) -> usize {
+        self.heap.memory_footprint()
+    }
+
+    /// Returns the contents as a string slice.
+    pub fn as_str(&self) -> &str {
+        if self.spilled {
+            self.heap
+        } else {
+            // SAFETY: The inline buffer only ever receives whole `str`s, so
+            // its first `inline_len` bytes are valid UTF-8.
+            unsafe { std::str::from_utf8_unchecked(&self.inline[..self.inline_len]) }
+        }
+    }
+
+    /// Appends a string slice, moving the contents to the heap if they no
+    /// longer fit inline.
+    pub fn push_str(&mut self, s: &str) {
+        if !self.spilled {
+            let len = self.inline_len + s.len();
+            if len <= N {
+                self.inline[self.inline_len..len].copy_from_slice(s.as_bytes());
+                self.inline_len = len;
+                return;
+            }
+            self.heap.reserve(len);
+            // SAFETY: See `as_str`.
+            self.heap.push_str(unsafe {
+                std::str::from_utf8_unchecked(&self.inline[..self.inline_len])
+            });
+            self.spilled = true;
+        }
+        self.heap.push_str(s);
+    }
+
+    /// Appends a character.
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Empties the string. Later pushes start out inline again.
+    pub fn clear(&mut self) {
+        self.inline_len = 0;
+        self.spilled = false;
+        self.heap.clear();
+    }
+
+    /// Returns `true` if the contents are stored in the inline buffer.
+    pub fn is_inline(&self) -> bool {
+        !self.spilled
+    }
+}
+
+impl<'parent, const N: usize> Deref for ReusableSmallStringGuard<'parent, N> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<'parent, const N: usize> fmt::Write for ReusableSmallStringGuard<'parent, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl<'parent, const N: usize> fmt::Display for ReusableSmallStringGuard<'parent, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'parent, const N: usize> fmt::Debug for ReusableSmallStringGuard<'parent, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<'parent, const N: usize> Drop for ReusableSmallStringGuard<'parent, N> {
+    /// Clears the heap `String`, preserving its allocation.
+    fn drop(&mut self) {
+        self.heap.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_strings_stay_inline() {
+        let mut reusable = ReusableSmallString::<8>::default();
+        {
+            let mut guard = reusable.recycle();
+            guard.push_str("héllo");
+            guard.push('!');
+            assert_eq!(&*guard, "héllo!");
+            assert!(guard.is_inline());
+        }
+        assert_eq!(reusable.heap_capacity(), 0);
+    }
+
+    #[test]
+    fn long_strings_spill_and_keep_the_allocation() {
+        let mut reusable = ReusableSmallString::<4>::default();
+        {
+            let mut guard = reusable.recycle();
+            guard.push_str("abc");
+            guard.push_str("def");
+            assert_eq!(guard.as_str(), "abcdef");
+            assert!(!guard.is_inline());
+
+            guard.clear();
+            guard.push_str("xy");
+            assert!(guard.is_inline());
+            assert_eq!(guard.to_string(), "xy");
+        }
+        assert!(reusable.heap_capacity() >= 6);
+        assert!(reusable.recycle().is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fmt, mem::MaybeUninit, ptr};
+
+/// A cursor over the spare capacity of a recycled byte buffer, created by
+/// [`ReusableVecGuard::fill_spare_capacity`](crate::ReusableVecGuard::fill_spare_capacity)
+/// and
+/// [`ReusableStringBytesGuard::fill_spare_capacity`](crate::ReusableStringBytesGuard::fill_spare_capacity).
+///
+/// The cursor works like the standard library's unstable `BorrowedCursor`.
+/// It tracks how many of its bytes are *filled*, and will be added to the
+/// buffer, and how many are *initialized*, so they can be handed out as
+/// `&mut [u8]`. Both start at zero, since the spare capacity holds whatever
+/// the previous cycle left behind.
+///
+/// - [`append`](SpareCapacity::append) copies bytes in and fills them.
+/// - [`init_mut`](SpareCapacity::init_mut) zeroes the bytes that aren't
+///   initialized yet, once, and returns the unfilled part for APIs that take
+///   a `&mut [u8]`, like [`std::io::Read`]. [`advance`](SpareCapacity::advance)
+///   then fills what was written, and checks that it was initialized.
+/// - [`uninit_mut`](SpareCapacity::uninit_mut) returns the unfilled part as
+///   `&mut [MaybeUninit<u8>]` without zeroing it, for syscall-style readers,
+///   and the unsafe [`advance_unchecked`](SpareCapacity::advance_unchecked)
+///   fills what they wrote.
+pub struct SpareCapacity<'a> {
+    buf: &'a mut [MaybeUninit<u8>],
+    filled: usize,
+    init: usize,
+}
+
+impl<'a> SpareCapacity<'a> {
+    pub(crate) fn new(buf: &'a mut [MaybeUninit<u8>]) -> Self {
+        Self {
+            buf,
+            filled: 0,
+            init: 0,
+        }
+    }
+
+    /// The number of bytes that can still be filled.
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.filled
+    }
+
+    /// The number of bytes filled so far.
+    pub fn filled(&self) -> usize {
+        self.filled
+    }
+
+    /// The bytes filled so far.
+    pub fn filled_bytes(&self) -> &[u8] {
+        // SAFETY: The first `filled` bytes are initialized.
+        unsafe { &*(&self.buf[..self.filled] as *const [MaybeUninit<u8>] as *const [u8]) }
+    }
+
+    /// Copies `bytes` into the cursor and fills them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is longer than [`remaining`](Self::remaining).
+    pub fn append(&mut self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= self.remaining(),
+            "appended bytes exceed the spare capacity"
+        );
+        // SAFETY: The destination holds at least `bytes.len()` bytes, and a
+        // shared and a mutable borrow never overlap.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self.buf[self.filled..].as_mut_ptr() as *mut u8,
+                bytes.len(),
+            );
+        }
+        self.filled += bytes.len();
+        self.init = self.init.max(self.filled);
+    }
+
+    /// Returns the unfilled bytes, zeroing the ones that aren't initialized
+    /// yet.
+    ///
+    /// Bytes are only zeroed the first time, so calling this again after a
+    /// partial [`advance`](Self::advance) is cheap.
+    pub fn init_mut(&mut self) -> &mut [u8] {
+        for byte in &mut self.buf[self.init..] {
+            *byte = MaybeUninit::new(0);
+        }
+        self.init = self.buf.len();
+        let unfilled = &mut self.buf[self.filled..];
+        // SAFETY: Every byte was just initialized.
+        unsafe { &mut *(unfilled as *mut [MaybeUninit<u8>] as *mut [u8]) }
+    }
+
+    /// Returns the unfilled bytes without initializing them.
+    ///
+    /// Writing to them is safe, but they only count as filled after an
+    /// [`advance_unchecked`](Self::advance_unchecked). Since the slice can be
+    /// used to de-initialize bytes, none of them count as initialized any
+    /// more, and the next [`init_mut`](Self::init_mut) zeroes them again.
+    pub fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.init = self.filled;
+        &mut self.buf[self.filled..]
+    }
+
+    /// Fills the next `n` bytes, which were written through
+    /// [`init_mut`](Self::init_mut).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `n` bytes isn't initialized.
+    pub fn advance(&mut self, n: usize) {
+        assert!(
+            n <= self.init - self.filled,
+            "advanced past the initialized bytes"
+        );
+        self.filled += n;
+    }
+
+    /// Fills the next `n` bytes, without checking they are initialized.
+    ///
+    /// # Safety
+    ///
+    /// The next `n` bytes must have been initialized, for example by writing
+    /// to [`uninit_mut`](Self::uninit_mut) or by a syscall that reported
+    /// writing `n` bytes to it.
+    pub unsafe fn advance_unchecked(&mut self, n: usize) {
+        debug_assert!(n <= self.remaining());
+        self.filled += n;
+        self.init = self.init.max(self.filled);
+    }
+}
+
+impl fmt::Debug for SpareCapacity<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpareCapacity")
+            .field("filled", &self.filled)
+            .field("init", &self.init)
+            .field("capacity", &self.buf.len())
+            .finish()
+    }
+}
+
+/// Reserves room for at least `additional` more bytes in `vec`, lets `f`
+/// fill its spare capacity, and commits what was filled.
+pub(crate) fn fill_spare_capacity<R>(
+    vec: &mut Vec<u8>,
+    additional: usize,
+    f: impl FnOnce(&mut SpareCapacity<'_>) -> R,
+) -> R {
+    vec.reserve(additional);
+    let len = vec.len();
+    let mut cursor = SpareCapacity::new(vec.spare_capacity_mut());
+    let result = f(&mut cursor);
+    let filled = cursor.filled;
+    // SAFETY: The cursor only counts bytes as filled once they are
+    // initialized, and never more than the spare capacity.
+    unsafe { vec.set_len(len + filled) };
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_filled_bytes_are_committed() {
+        let mut vec = b"head:".to_vec();
+        let read = fill_spare_capacity(&mut vec, 16, |spare| {
+            spare.append(b"ab");
+            let unfilled = spare.init_mut();
+            unfilled[..3].copy_from_slice(b"cde");
+            unfilled[3] = b'!';
+            spare.advance(3);
+            spare.filled()
+        });
+        assert_eq!(read, 5);
+        assert_eq!(vec, b"head:abcde");
+    }
+
+    #[test]
+    #[should_panic(expected = "advanced past the initialized bytes")]
+    fn uninitialized_bytes_cannot_be_filled_safely() {
+        fill_spare_capacity(&mut Vec::new(), 8, |spare| {
+            spare.uninit_mut()[0] = MaybeUninit::new(1);
+            spare.advance(1);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "advanced past the initialized bytes")]
+    fn uninit_access_forgets_initialized_bytes() {
+        fill_spare_capacity(&mut Vec::new(), 8, |spare| {
+            spare.init_mut();
+            spare.uninit_mut()[0] = MaybeUninit::uninit();
+            spare.advance(1);
+        });
+    }
+}
@@ -0,0 +1,453 @@
+//! Usage statistics for recyclers, and a report of the ones that are just
+//! hoarding memory.
+//!
+//! Recycling trades memory for allocations: every reusable container keeps the
+//! largest allocation it has ever needed. That is the point when the memory is
+//! used again on every cycle, but a recycler that is rarely filled, or no
+//! longer used at all, only pins memory.
+//!
+//! With the `stats` feature, [`ReusableVec`], [`ReusableString`], and
+//! [`ReusableHashMap`] record [`RecycleStats`] every time a guard is dropped,
+//! and [`VecPool`] tracks which of its buffers have not been handed out
+//! recently. A container's `stats` method shows how many cycles it ran, how
+//! many of them had to reallocate, and roughly how many allocations
+//! recycling saved, which is the number to watch in production.
+//!
+//! A [`WasteReport`] collects the recyclers whose retained memory has not
+//! been touched for a number of cycles, or whose retained capacity is mostly
+//! unused, so operators can shrink or remove them.
+//!
+//! This module is only available with the `stats` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{
+//!     stats::{WasteReason, WasteReport},
+//!     ReusableString, ReusableVec,
+//! };
+//!
+//! let mut rows = ReusableVec::<u64>::default();
+//! let mut line = ReusableString::default();
+//!
+//! // One big batch, followed by a long run of empty ones.
+//! rows.recycle::<u64>().extend(0..10_000);
+//! for _ in 0..100 {
+//!     let _rows = rows.recycle::<u64>();
+//!     line.recycle().push_str("a line that fills most of its buffer");
+//! }
+//!
+//! let mut report = WasteReport::new(50, 0.25);
+//! report.inspect("rows", &rows);
+//! report.inspect("line", &line);
+//!
+//! assert_eq!(report.entries().len(), 1);
+//! let entry = &report.entries()[0];
+//! assert_eq!(entry.name(), "rows");
+//! assert!(matches!(entry.reason(), WasteReason::Idle { cycles: 100 }));
+//! println!("{}", report);
+//! ```
+use crate::{ReusableHashMap, ReusableString, ReusableVec, VecPool};
+use std::{fmt, hash::BuildHasher};
+
+/// Statistics recorded by a reusable container every time a guard is
+/// dropped.
+///
+/// Lengths and capacities are those of the collection as the guard is
+/// dropped, before it is cleared or shrunk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecycleStats {
+    cycles: u64,
+    idle_cycles: u64,
+    utilization_sum: f64,
+    utilized_cycles: u64,
+    peak_len: usize,
+    capacity: usize,
+    reallocations: u64,
+    allocations_avoided: u64,
+    /// The capacity the current guard started with.
+    start_capacity: usize,
+}
+
+impl RecycleStats {
+    /// The number of guards that have been dropped.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// The largest number of elements a cycle ended with.
+    pub fn peak_len(&self) -> usize {
+        self.peak_len
+    }
+
+    /// The capacity at the end of the latest cycle.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of cycles that outgrew the capacity they started with, and
+    /// so had to reallocate at least once.
+    pub fn reallocations(&self) -> u64 {
+        self.reallocations
+    }
+
+    /// An estimate of the allocations recycling has saved.
+    ///
+    /// This counts the cycles that stored elements without outgrowing the
+    /// capacity they started with. Each of them would have allocated at least
+    /// once with a new collection, so the real number is usually higher.
+    pub fn allocations_avoided(&self) -> u64 {
+        self.allocations_avoided
+    }
+
+    /// The number of consecutive cycles, up to the latest one, that ended
+    /// with the container empty.
+    pub fn idle_cycles(&self) -> u64 {
+        self.idle_cycles
+    }
+
+    /// The average fraction of the retained capacity that was in use at the
+    /// end of a cycle, or `None` if no cycle ended with any capacity.
+    ///
+    /// A ratio close to `1.0` means the retained allocation is sized to what
+    /// is actually needed. A ratio close to `0.0` means most of it is carried
+    /// around for nothing.
+    pub fn reuse_ratio(&self) -> Option<f64> {
+        if self.utilized_cycles == 0 {
+            return None;
+        }
+        Some(self.utilization_sum / self.utilized_cycles as f64)
+    }
+
+    /// Notes the capacity a new guard starts with.
+    pub(crate) fn begin(&mut self, capacity: usize) {
+        self.start_capacity = capacity;
+    }
+
+    /// Records a cycle that ended with `len` elements in `capacity` slots.
+    pub(crate) fn record(&mut self, len: usize, capacity: usize) {
+        self.cycles += 1;
+        self.peak_len = self.peak_len.max(len);
+        self.capacity = capacity;
+        if capacity > self.start_capacity {
+            self.reallocations += 1;
+        } else if len > 0 {
+            self.allocations_avoided += 1;
+        }
+        if len == 0 {
+            self.idle_cycles += 1;
+        } else {
+            self.idle_cycles = 0;
+        }
+        if capacity > 0 {
+            self.utilization_sum += len as f64 / capacity as f64;
+            self.utilized_cycles += 1;
+        }
+    }
+}
+
+/// A reusable container that records [`RecycleStats`].
+pub trait TrackedReusable {
+    /// The statistics recorded so far.
+    fn stats(&self) -> &RecycleStats;
+
+    /// An estimate of the number of bytes the container currently retains.
+    fn retained_bytes(&self) -> usize;
+}
+
+impl<T: 'static> TrackedReusable for ReusableVec<T> {
+    fn stats(&self) -> &RecycleStats {
+        ReusableVec::stats(self)
+    }
+
+    fn retained_bytes(&self) -> usize {
+        self.memory_footprint()
+    }
+}
+
+impl TrackedReusable for ReusableString {
+    fn stats(&self) -> &RecycleStats {
+        ReusableString::stats(self)
+    }
+
+    fn retained_bytes(&self) -> usize {
+        self.memory_footprint()
+    }
+}
+
+impl<K, V, S> TrackedReusable for ReusableHashMap<K, V, S>
+where
+    K: 'static,
+    V: 'static,
+    S: 'static + BuildHasher + Default,
+{
+    fn stats(&self) -> &RecycleStats {
+        ReusableHashMap::stats(self)
+    }
+
+    fn retained_bytes(&self) -> usize {
+        self.memory_footprint()
+    }
+}
+
+/// Why a [`WasteEntry`] ended up in a [`WasteReport`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WasteReason {
+    /// The retained memory has not been used for this many consecutive
+    /// cycles. For pools, a cycle is a checkout.
+    Idle {
+        /// The number of cycles without use.
+        cycles: u64,
+    },
+    /// The average fraction of the retained capacity in use at the end of a
+    /// cycle is below the report's threshold.
+    LowReuse {
+        /// The observed [`reuse_ratio`](RecycleStats::reuse_ratio).
+        ratio: f64,
+    },
+}
+
+/// A recycler flagged by a [`WasteReport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasteEntry {
+    name: String,
+    retained_bytes: usize,
+    reason: WasteReason,
+}
+
+impl WasteEntry {
+    /// The name the recycler was inspected under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of bytes the recycler retains for nothing.
+    pub fn retained_bytes(&self) -> usize {
+        self.retained_bytes
+    }
+
+    /// Why the recycler was flagged.
+    pub fn reason(&self) -> WasteReason {
+        self.reason
+    }
+}
+
+/// A report of the recyclers that retain memory without using it.
+///
+/// Recyclers are added with [`inspect`](WasteReport::inspect) and
+/// [`inspect_pool`](WasteReport::inspect_pool). A recycler that retains no
+/// memory is never flagged. Otherwise, it is flagged as
+/// [`Idle`](WasteReason::Idle) if it has not used its memory for at least
+/// `idle_cycles` cycles, or as [`LowReuse`](WasteReason::LowReuse) if it has
+/// run at least `idle_cycles` cycles with a
+/// [`reuse_ratio`](RecycleStats::reuse_ratio) below `min_reuse_ratio`.
+///
+/// The report's [`Display`](fmt::Display) implementation lists the entries,
+/// largest first.
+#[derive(Debug, Clone)]
+pub struct WasteReport {
+    idle_cycles: u64,
+    min_reuse_ratio: f64,
+    entries: Vec<WasteEntry>,
+}
+
+impl WasteReport {
+    /// Creates an empty report with the given thresholds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idle_cycles` is zero or `min_reuse_ratio` is not between
+    /// `0.0` and `1.0`.
+    pub fn new(idle_cycles: u64, min_reuse_ratio: f64) -> Self {
+        assert!(idle_cycles > 0, "idle_cycles must be greater than zero");
+        assert!(
+            (0.0..=1.0).contains(&min_reuse_ratio),
+            "min_reuse_ratio must be between 0.0 and 1.0"
+        );
+        Self {
+            idle_cycles,
+            min_reuse_ratio,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Checks `reusable`, adding it to the report under `name` if it is
+    /// wasting memory.
+    pub fn inspect<R: TrackedReusable + ?Sized>(&mut self, name: &str, reusable: &R) {
+        let retained_bytes = reusable.retained_bytes();
+        if retained_bytes == 0 {
+            return;
+        }
+
+        let stats = reusable.stats();
+        let reason = if stats.idle_cycles() >= self.idle_cycles {
+            WasteReason::Idle {
+                cycles: stats.idle_cycles(),
+            }
+        } else {
+            match stats.reuse_ratio() {
+                Some(ratio)
+                    if stats.cycles() >= self.idle_cycles && ratio < self.min_reuse_ratio =>
+                {
+                    WasteReason::LowReuse { ratio }
+                }
+                _ => return,
+            }
+        };
+        self.push(name, retained_bytes, reason);
+    }
+
+    /// Checks `pool`, adding an entry under `name` for the buffers that have
+    /// not been checked out during the last `idle_cycles` checkouts or more.
+    ///
+    /// See [`VecPool::reset_stats`] for how that window is tracked.
+    pub fn inspect_pool<T>(&mut self, name: &str, pool: &VecPool<T>) {
+        let (checkouts, idle_capacity) = pool.idle_buffers();
+        let retained_bytes = idle_capacity * std::mem::size_of::<T>();
+        if checkouts >= self.idle_cycles && retained_bytes > 0 {
+            self.push(
+                name,
+                retained_bytes,
+                WasteReason::Idle { cycles: checkouts },
+            );
+        }
+    }
+
+    fn push(&mut self, name: &str, retained_bytes: usize, reason: WasteReason) {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.retained_bytes >= retained_bytes);
+        self.entries.insert(
+            index,
+            WasteEntry {
+                name: name.to_string(),
+                retained_bytes,
+                reason,
+            },
+        );
+    }
+
+    /// The flagged recyclers, largest first.
+    pub fn entries(&self) -> &[WasteEntry] {
+        &self.entries
+    }
+
+    /// The total number of bytes retained by the flagged recyclers.
+    pub fn wasted_bytes(&self) -> usize {
+        self.entries.iter().map(|entry| entry.retained_bytes).sum()
+    }
+}
+
+impl fmt::Display for WasteReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} recycler(s) retaining {} bytes without using them",
+            self.entries.len(),
+            self.wasted_bytes()
+        )?;
+        for entry in &self.entries {
+            write!(f, "  {}: {} bytes, ", entry.name, entry.retained_bytes)?;
+            match entry.reason {
+                WasteReason::Idle { cycles } => writeln!(f, "idle for {} cycles", cycles)?,
+                WasteReason::LowReuse { ratio } => {
+                    writeln!(f, "{:.1}% of capacity in use", ratio * 100.0)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_track_idle_streaks_and_utilization() {
+        let mut stats = RecycleStats::default();
+        assert_eq!(stats.reuse_ratio(), None);
+        stats.record(4, 8);
+        stats.record(0, 8);
+        stats.record(0, 8);
+        assert_eq!(stats.cycles(), 3);
+        assert_eq!(stats.idle_cycles(), 2);
+        assert!((stats.reuse_ratio().unwrap() - 1.0 / 6.0).abs() < 1e-9);
+
+        stats.record(8, 8);
+        assert_eq!(stats.idle_cycles(), 0);
+    }
+
+    #[test]
+    fn stats_tell_reused_cycles_from_reallocating_ones() {
+        let mut stats = RecycleStats::default();
+        stats.begin(0);
+        stats.record(10, 16);
+        stats.begin(16);
+        stats.record(12, 16);
+        stats.begin(16);
+        stats.record(0, 16);
+
+        assert_eq!(stats.reallocations(), 1);
+        assert_eq!(stats.allocations_avoided(), 1);
+        assert_eq!(stats.peak_len(), 12);
+        assert_eq!(stats.capacity(), 16);
+    }
+
+    #[test]
+    fn containers_record_stats_on_drop() {
+        let mut map = ReusableHashMap::<u32, u32>::default();
+        map.recycle::<u32, u32>().insert(1, 1);
+        let _ = map.recycle::<u32, u32>();
+        assert_eq!(map.stats().cycles(), 2);
+        assert_eq!(map.stats().idle_cycles(), 1);
+        assert!(map.retained_bytes() > 0);
+    }
+
+    #[test]
+    fn underused_recyclers_are_flagged_as_low_reuse() {
+        let mut vec = ReusableVec::<u8>::default();
+        {
+            let mut guard = vec.recycle::<u8>();
+            guard.reserve(1024);
+            guard.push(1);
+        }
+        for _ in 0..4 {
+            vec.recycle::<u8>().push(1);
+        }
+
+        let mut report = WasteReport::new(5, 0.5);
+        report.inspect("vec", &vec);
+        assert!(matches!(
+            report.entries()[0].reason(),
+            WasteReason::LowReuse { ratio } if ratio < 0.01
+        ));
+
+        let mut unused = ReusableVec::<u8>::default();
+        let _ = unused.recycle::<u8>();
+        report.inspect("unused", &unused);
+        assert_eq!(report.entries().len(), 1);
+    }
+
+    #[test]
+    fn untouched_pool_buffers_are_flagged() {
+        let pool = VecPool::<u64>::new();
+        {
+            let mut burst: Vec<_> = (0..4).map(|_| pool.get()).collect();
+            for buffer in &mut burst {
+                buffer.reserve(16);
+            }
+        }
+        pool.reset_stats();
+        for _ in 0..10 {
+            pool.get().push(1);
+        }
+
+        let mut report = WasteReport::new(10, 0.5);
+        report.inspect_pool("pool", &pool);
+        let entry = &report.entries()[0];
+        assert_eq!(entry.reason(), WasteReason::Idle { cycles: 10 });
+        assert!(entry.retained_bytes() >= 3 * 16 * 8);
+        assert!(report.to_string().contains("pool"));
+    }
+}
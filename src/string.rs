@@ -1,6 +1,22 @@
+#[cfg(feature = "stats")]
+use crate::stats::RecycleStats;
+#[cfg(feature = "tracing")]
+use crate::trace::GuardTrace;
+#[cfg(feature = "zeroize")]
+use crate::zeroize::{clear_and_zeroize, DropPolicy};
+use crate::{
+    adaptive::AdaptiveCapacity,
+    hooks::{HookWatch, Hooks},
+    spare::{self, SpareCapacity},
+    AdaptiveCapacityPolicy, MemoryFootprint,
+};
 use std::{
+    borrow::{Borrow, BorrowMut},
     cell::UnsafeCell,
+    fmt,
+    hash::{Hash, Hasher},
     marker::PhantomData,
+    mem,
     ops::{Deref, DerefMut},
 };
 
@@ -32,9 +48,15 @@ use std::{
 /// assert!(final_guard.is_empty());
 /// assert_eq!(final_guard.capacity(), last_capacity);
 /// ```
-#[derive(Debug)]
 pub struct ReusableString {
     inner: UnsafeCell<String>,
+    max_capacity: Option<usize>,
+    adaptive: Option<AdaptiveCapacity>,
+    hooks: Hooks,
+    #[cfg(feature = "zeroize")]
+    drop_policy: DropPolicy,
+    #[cfg(feature = "stats")]
+    stats: RecycleStats,
 }
 
 // A `ReusableString` can be sent across threads.
@@ -44,25 +66,71 @@ unsafe impl Send for ReusableString {}
 // method requires `&mut self`, preventing data races.
 unsafe impl Sync for ReusableString {}
 
+impl fmt::Debug for ReusableString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ReusableString");
+        debug.field("capacity", &self.capacity());
+        #[cfg(feature = "stats")]
+        debug.field("stats", &self.stats);
+        debug.finish()
+    }
+}
+
 impl Default for ReusableString {
     /// Creates a new, empty `ReusableString`.
     fn default() -> Self {
         Self {
             inner: UnsafeCell::new(String::new()),
+            max_capacity: None,
+            adaptive: None,
+            hooks: Hooks::default(),
+            #[cfg(feature = "zeroize")]
+            drop_policy: DropPolicy::default(),
+            #[cfg(feature = "stats")]
+            stats: RecycleStats::default(),
         }
     }
 }
 
+impl From<String> for ReusableString {
+    /// Adopts the allocation of `string`, discarding its contents.
+    fn from(mut string: String) -> Self {
+        string.clear();
+        let mut reusable = Self::default();
+        *reusable.inner.get_mut() = string;
+        reusable
+    }
+}
+
 /// A RAII guard that provides temporary, exclusive access to a `String` from a
 /// [`ReusableString`].
 ///
 /// When this guard is dropped, it clears the underlying `String`, preserving its
 /// allocation for future use.
+///
+/// The guard implements [`fmt::Write`], so it can be the target of `write!`
+/// or passed wherever a formatting sink is expected.
 pub struct ReusableStringGuard<'parent> {
     inner: *mut String,
+    max_capacity: Option<usize>,
+    adaptive: &'parent mut Option<AdaptiveCapacity>,
+    hooks: HookWatch<'parent>,
+    #[cfg(feature = "zeroize")]
+    drop_policy: DropPolicy,
+    #[cfg(feature = "stats")]
+    stats: &'parent mut RecycleStats,
+    #[cfg(feature = "tracing")]
+    trace: GuardTrace,
+    /// The length the contents had while the allocation was lent out by a
+    /// [`ReusableStringBytesGuard`], which hands it back empty.
+    lent_len: Option<usize>,
     _parent: PhantomData<&'parent mut ReusableString>,
 }
 
+// SAFETY: The guard is an exclusive borrow of its parent, which is `Send` and
+// `Sync`, so it can be moved to another thread like a `&mut String`.
+unsafe impl Send for ReusableStringGuard<'_> {}
+
 impl<'parent> Deref for ReusableStringGuard<'parent> {
     type Target = String;
 
@@ -82,29 +150,449 @@ impl<'parent> DerefMut for ReusableStringGuard<'parent> {
     }
 }
 
+impl<'parent> ReusableStringGuard<'parent> {
+    /// The estimated number of heap bytes the `String` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `String`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one, and the drop policy no longer applies to it. Use this
+    /// when a cycle's result has to outlive the guard, for example to send it
+    /// to another thread.
+    pub fn detach(mut self) -> String {
+        mem::take(&mut *self)
+    }
+}
+
+impl<'parent> fmt::Write for ReusableStringGuard<'parent> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fmt::Write::write_str(&mut **self, s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        fmt::Write::write_char(&mut **self, c)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        fmt::Write::write_fmt(&mut **self, args)
+    }
+}
+
+impl<'parent> fmt::Debug for ReusableStringGuard<'parent> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, 'b> PartialEq<ReusableStringGuard<'b>> for ReusableStringGuard<'a> {
+    fn eq(&self, other: &ReusableStringGuard<'b>) -> bool {
+        **self == **other
+    }
+}
+
+impl<'parent> Eq for ReusableStringGuard<'parent> {}
+
+impl<'parent> Hash for ReusableStringGuard<'parent> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<'parent> AsRef<String> for ReusableStringGuard<'parent> {
+    fn as_ref(&self) -> &String {
+        self
+    }
+}
+
+impl<'parent> AsMut<String> for ReusableStringGuard<'parent> {
+    fn as_mut(&mut self) -> &mut String {
+        self
+    }
+}
+
+impl<'parent> Borrow<String> for ReusableStringGuard<'parent> {
+    fn borrow(&self) -> &String {
+        self
+    }
+}
+
+impl<'parent> BorrowMut<String> for ReusableStringGuard<'parent> {
+    fn borrow_mut(&mut self) -> &mut String {
+        self
+    }
+}
+
+impl<'parent> AsRef<str> for ReusableStringGuard<'parent> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl<'parent> AsMut<str> for ReusableStringGuard<'parent> {
+    fn as_mut(&mut self) -> &mut str {
+        self
+    }
+}
+
+impl<'parent> Borrow<str> for ReusableStringGuard<'parent> {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl<'parent> BorrowMut<str> for ReusableStringGuard<'parent> {
+    fn borrow_mut(&mut self) -> &mut str {
+        self
+    }
+}
+
+impl<'parent> AsRef<[u8]> for ReusableStringGuard<'parent> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+macro_rules! impl_string_guard_eq {
+    ($($other:ty),+ $(,)?) => {$(
+        impl<'parent> PartialEq<$other> for ReusableStringGuard<'parent> {
+            fn eq(&self, other: &$other) -> bool {
+                self.as_str() == &other[..]
+            }
+        }
+
+        impl<'parent> PartialEq<ReusableStringGuard<'parent>> for $other {
+            fn eq(&self, other: &ReusableStringGuard<'parent>) -> bool {
+                &self[..] == other.as_str()
+            }
+        }
+    )+};
+}
+
+impl_string_guard_eq!(str, &str, String);
+
+/// A guard that lends the allocation of a [`ReusableString`] out as a
+/// `Vec<u8>`, created by [`ReusableString::recycle_as_bytes`].
+///
+/// The bytes don't have to be valid UTF-8. The allocation is moved out of
+/// the `String` while the guard is alive, and moved back, cleared, when it is
+/// dropped, so the `String` never holds anything but UTF-8, even if the guard
+/// is leaked.
+pub struct ReusableStringBytesGuard<'parent> {
+    bytes: Vec<u8>,
+    string: ReusableStringGuard<'parent>,
+}
+
+impl<'parent> Deref for ReusableStringBytesGuard<'parent> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl<'parent> DerefMut for ReusableStringBytesGuard<'parent> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bytes
+    }
+}
+
+impl<'parent> ReusableStringBytesGuard<'parent> {
+    /// Reserves room for at least `additional` more bytes, and lets `f` fill
+    /// the spare capacity through a [`SpareCapacity`] cursor, without zeroing
+    /// it first.
+    ///
+    /// See [`ReusableVecGuard::fill_spare_capacity`](crate::ReusableVecGuard::fill_spare_capacity).
+    pub fn fill_spare_capacity<R>(
+        &mut self,
+        additional: usize,
+        f: impl FnOnce(&mut SpareCapacity<'_>) -> R,
+    ) -> R {
+        spare::fill_spare_capacity(&mut self.bytes, additional, f)
+    }
+}
+
+impl<'parent> fmt::Debug for ReusableStringBytesGuard<'parent> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.bytes, f)
+    }
+}
+
+impl<'parent> Drop for ReusableStringBytesGuard<'parent> {
+    /// Moves the allocation back into the `String`, cleared, and lets the
+    /// string guard apply the statistics and capacity policies to the length
+    /// the bytes had.
+    fn drop(&mut self) {
+        let mut bytes = mem::take(&mut self.bytes);
+        self.string.lent_len = Some(bytes.len());
+        bytes.clear();
+        *self.string = String::from_utf8(bytes).expect("an empty buffer is valid UTF-8");
+    }
+}
+
 impl ReusableString {
+    /// Creates a `ReusableString` whose allocation already holds `capacity`
+    /// bytes, so the first cycles don't reallocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut reusable = Self::default();
+        reusable.prewarm(capacity);
+        reusable
+    }
+
+    /// Grows the retained allocation to hold at least `capacity` bytes,
+    /// without taking a guard.
+    ///
+    /// Does nothing if the allocation is already large enough.
+    pub fn prewarm(&mut self, capacity: usize) {
+        // The string is always empty between guards.
+        self.inner.get_mut().reserve_exact(capacity);
+    }
+
+    /// Releases the retained allocation, without taking a guard.
+    pub fn shrink_to_fit(&mut self) {
+        // The string is always empty between guards, so nothing is kept.
+        self.inner.get_mut().shrink_to_fit();
+    }
+
+    /// Shrinks the retained allocation to hold at least `min_capacity`
+    /// bytes, without taking a guard.
+    ///
+    /// Does nothing if the allocation is already smaller. Use this to let
+    /// go of the memory a one-off batch left behind while keeping room for
+    /// the usual one.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.inner.get_mut().shrink_to(min_capacity);
+    }
+
     /// Reuses the `String`'s allocation, returning a guard for temporary access.
     ///
     /// The `&mut self` requirement ensures that only one guard can be active
     /// at a time.
     pub fn recycle<'parent>(&'parent mut self) -> ReusableStringGuard<'parent> {
+        // A guard that was leaked instead of dropped left its text behind.
+        self.inner.get_mut().clear();
+        let capacity = self.inner.get_mut().capacity();
+        #[cfg(feature = "stats")]
+        self.stats.begin(capacity);
+        #[cfg(feature = "tracing")]
+        let trace = GuardTrace::recycle(std::any::type_name::<Self>(), capacity);
         // SAFETY: We use `get()` to obtain a raw pointer, which is safe
         // because `&mut self` guarantees exclusive access.
         ReusableStringGuard {
             inner: self.inner.get(),
+            max_capacity: self.max_capacity,
+            adaptive: &mut self.adaptive,
+            hooks: self.hooks.recycle(capacity),
+            #[cfg(feature = "zeroize")]
+            drop_policy: self.drop_policy,
+            #[cfg(feature = "stats")]
+            stats: &mut self.stats,
+            #[cfg(feature = "tracing")]
+            trace,
+            lent_len: None,
             _parent: PhantomData,
         }
     }
+
+    /// Recycles the `String` for the duration of `f`, clearing it when `f`
+    /// returns, so the guard can't be held past the closure.
+    pub fn recycle_with<R>(&mut self, f: impl FnOnce(&mut ReusableStringGuard<'_>) -> R) -> R {
+        f(&mut self.recycle())
+    }
+
+    /// Reuses the `String`'s allocation as a `Vec<u8>`, for phases that run
+    /// binary encoders.
+    ///
+    /// When the guard is dropped, the allocation goes back to the `String`,
+    /// cleared, and the next [`recycle`](Self::recycle) can use it for text
+    /// again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use triple_r::ReusableString;
+    ///
+    /// let mut scratch = ReusableString::with_capacity(64);
+    ///
+    /// {
+    ///     let mut frame = scratch.recycle_as_bytes();
+    ///     frame.extend_from_slice(&[0xff, 0x00, 0x2a]);
+    ///     assert_eq!(frame[..], [0xff, 0x00, 0x2a]);
+    /// }
+    ///
+    /// let text = scratch.recycle();
+    /// assert!(text.is_empty());
+    /// assert!(text.capacity() >= 64);
+    /// ```
+    pub fn recycle_as_bytes(&mut self) -> ReusableStringBytesGuard<'_> {
+        let mut string = self.recycle();
+        ReusableStringBytesGuard {
+            bytes: mem::take(&mut *string).into_bytes(),
+            string,
+        }
+    }
+
+    /// Returns the retained `String`, which is always empty.
+    pub fn into_inner(self) -> String {
+        let mut string = self.inner.into_inner();
+        string.clear();
+        string
+    }
+
+    /// The number of bytes the retained `String` can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `String` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+
+    /// Exchanges the retained allocation with the one in `other`, without
+    /// copying either.
+    ///
+    /// `other` gets the retained `String`, empty, and its own allocation is kept
+    /// for the next guard. Anything left in `other` is dropped.
+    pub fn swap(&mut self, other: &mut String) {
+        other.clear();
+        mem::swap(self.inner.get_mut(), other);
+    }
+
+    /// Installs or removes the [`AdaptiveCapacityPolicy`] consulted every
+    /// time a guard is dropped.
+    ///
+    /// Replacing the policy discards the lengths observed so far. Passing
+    /// `None`, the default, keeps whatever the guards grew to.
+    pub fn set_adaptive_capacity(&mut self, policy: Option<AdaptiveCapacityPolicy>) {
+        self.adaptive = policy.map(AdaptiveCapacity::new);
+    }
+
+    /// Returns the current [`AdaptiveCapacityPolicy`], if one is installed.
+    pub fn adaptive_capacity(&self) -> Option<AdaptiveCapacityPolicy> {
+        self.adaptive.as_ref().map(AdaptiveCapacity::policy)
+    }
+
+    /// Calls `hook` with the capacity of the `String` every time `recycle`
+    /// hands out a guard.
+    ///
+    /// Replaces the previous recycle hook. See the [`hooks`](crate::hooks)
+    /// module for an example.
+    pub fn on_recycle(&mut self, hook: impl Fn(usize) + Send + Sync + 'static) {
+        self.hooks.on_recycle = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the capacity a guard started with and the one it
+    /// was dropped with, whenever the `String` outgrew its retained allocation.
+    ///
+    /// Replaces the previous grow hook.
+    pub fn on_grow(&mut self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.hooks.on_grow = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the capacity before and after, whenever a capacity
+    /// policy shrinks the retained allocation as a guard is dropped.
+    ///
+    /// Replaces the previous shrink hook.
+    pub fn on_shrink(&mut self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.hooks.on_shrink = Some(Box::new(hook));
+    }
+
+    /// Removes the hooks installed with [`on_recycle`](Self::on_recycle),
+    /// [`on_grow`](Self::on_grow) and [`on_shrink`](Self::on_shrink).
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    /// Caps the capacity kept between guards at `max` bytes.
+    ///
+    /// A guard that is dropped with a larger allocation shrinks it back to
+    /// `max`, so one outlier batch doesn't pin its memory for the rest of the
+    /// program. `None`, the default, keeps whatever the guards grew to.
+    pub fn set_max_retained_capacity(&mut self, max: Option<usize>) {
+        self.max_capacity = max;
+    }
+
+    /// Returns the cap set by
+    /// [`set_max_retained_capacity`](Self::set_max_retained_capacity).
+    pub fn max_retained_capacity(&self) -> Option<usize> {
+        self.max_capacity
+    }
+
+    /// Sets what guards do with the retained allocation when they are
+    /// dropped.
+    ///
+    /// See the [`zeroize`](crate::zeroize) module for what
+    /// [`DropPolicy::Zeroize`] does and does not cover.
+    #[cfg(feature = "zeroize")]
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+        self.drop_policy = policy;
+    }
+
+    /// Returns the current drop policy.
+    #[cfg(feature = "zeroize")]
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// The statistics recorded so far.
+    ///
+    /// This method is only available with the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &RecycleStats {
+        &self.stats
+    }
 }
 
 impl<'parent> Drop for ReusableStringGuard<'parent> {
-    /// Clears the `String` when the guard is dropped.
+    /// Clears the `String` when the guard is dropped, shrinking it as the
+    /// [adaptive capacity policy](ReusableString::set_adaptive_capacity) and
+    /// the [maximum retained capacity](ReusableString::set_max_retained_capacity)
+    /// require.
+    ///
+    /// With the `zeroize` feature and [`DropPolicy::Zeroize`], the retained
+    /// allocation is overwritten with zeroes as well.
     fn drop(&mut self) {
         // SAFETY: The pointer is guaranteed to be valid for the lifetime
         // of the guard. Clearing the string prepares it for the next reuse.
-        unsafe {
-            (*self.inner).clear();
+        let string = unsafe { &mut *self.inner };
+        let len = self.lent_len.unwrap_or(string.len());
+        let capacity = string.capacity();
+        #[cfg(feature = "stats")]
+        self.stats.record(len, capacity);
+        #[cfg(feature = "zeroize")]
+        if self.drop_policy == DropPolicy::Zeroize {
+            // SAFETY: The string is emptied before the bytes are zeroed, so it
+            // never holds invalid UTF-8.
+            clear_and_zeroize(unsafe { string.as_mut_vec() });
+        }
+        string.clear();
+        if let Some(target) = self
+            .adaptive
+            .as_mut()
+            .and_then(|adaptive| adaptive.observe(len, string.capacity()))
+        {
+            string.shrink_to(target);
+        }
+        if let Some(max) = self.max_capacity {
+            string.shrink_to(max);
         }
+        self.hooks.finish(capacity, string.capacity());
+        #[cfg(feature = "tracing")]
+        self.trace.finish(len, capacity, string.capacity());
     }
 }
 
@@ -125,6 +613,120 @@ mod tests {
         assert!(guard.capacity() >= 5);
     }
 
+    #[test]
+    fn recycle_with_clears_when_the_closure_returns() {
+        let mut s = ReusableString::default();
+        let len = s.recycle_with(|guard| {
+            guard.push_str("scoped");
+            guard.len()
+        });
+        assert_eq!(len, 6);
+        let guard = s.recycle();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 6);
+    }
+
+    #[test]
+    fn guard_is_a_formatting_sink() {
+        fn render(out: &mut impl fmt::Write, id: u32) -> fmt::Result {
+            write!(out, "user:{}", id)
+        }
+
+        let mut s = ReusableString::default();
+        let mut guard = s.recycle();
+        render(&mut guard, 42).unwrap();
+        fmt::Write::write_char(&mut guard, '!').unwrap();
+        assert_eq!(*guard, "user:42!");
+    }
+
+    #[test]
+    fn guards_compare_and_hash_like_strings() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash_of(value: &impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut s = ReusableString::default();
+        let mut guard = s.recycle();
+        guard.push_str("key");
+
+        assert_eq!(guard, "key");
+        assert_eq!("key", guard);
+        assert_eq!(guard, String::from("key"));
+        assert_eq!(format!("{:?}", guard), r#""key""#);
+        assert_eq!(hash_of(&guard), hash_of(&String::from("key")));
+    }
+
+    #[test]
+    fn guards_pass_as_str() {
+        fn shout(text: impl AsRef<str>) -> String {
+            text.as_ref().to_uppercase()
+        }
+
+        let mut s = ReusableString::default();
+        let mut guard = s.recycle();
+        guard.push_str("quiet");
+        assert_eq!(shout(&guard), "QUIET");
+
+        let keys: std::collections::HashSet<String> = ["quiet".to_string()].into();
+        assert!(keys.contains(Borrow::<str>::borrow(&guard)));
+    }
+
+    #[test]
+    fn with_capacity_and_prewarm_grow_the_allocation() {
+        let mut s = ReusableString::with_capacity(64);
+        assert!(s.recycle().capacity() >= 64);
+        s.prewarm(256);
+        assert!(s.recycle().capacity() >= 256);
+    }
+
+    #[test]
+    fn from_adopts_the_allocation() {
+        let mut s = ReusableString::from("x".repeat(100));
+        let guard = s.recycle();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 100);
+    }
+
+    #[test]
+    fn swap_keeps_the_other_allocation() {
+        let mut s = ReusableString::default();
+        let mut external = String::with_capacity(512);
+        external.push_str("contents");
+
+        s.swap(&mut external);
+        assert_eq!(external.capacity(), 0);
+        let guard = s.recycle();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 512);
+    }
+
+    #[test]
+    fn byte_guards_return_the_allocation_as_text() {
+        let mut s = ReusableString::default();
+        s.set_max_retained_capacity(Some(1024));
+        s.recycle_as_bytes().extend_from_slice(&[0xc3; 2048]);
+        let guard = s.recycle();
+        assert!(guard.is_empty());
+        assert_eq!(guard.capacity(), 1024);
+        drop(guard);
+        #[cfg(feature = "stats")]
+        assert_eq!(s.stats().peak_len(), 2048);
+    }
+
+    #[test]
+    // The allocation is leaked on purpose, which miri reports.
+    #[cfg_attr(miri, ignore)]
+    fn leaked_byte_guards_take_the_allocation_with_them() {
+        let mut s = ReusableString::with_capacity(1024);
+        std::mem::forget(s.recycle_as_bytes());
+        assert_eq!(s.capacity(), 0);
+        assert!(s.recycle().is_empty());
+    }
+
     #[test]
     fn capacity_is_preserved() {
         let mut s = ReusableString::default();
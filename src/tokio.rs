@@ -0,0 +1,252 @@
+//! Async reads into recycled buffers, and a pool whose checkout can be awaited,
+//! for [tokio](https://docs.rs/tokio).
+//!
+//! [`read_to_end_reusing`] and [`read_to_end_pooled`] read an [`AsyncRead`]
+//! to its end straight into a recycled `Vec<u8>`, from a
+//! [`ReusableVec`] or a [`VecPool`]. For framed protocols,
+//! [`AsyncReadExt::read_buf`] already fills any `BufMut`: with this feature,
+//! a [`ReusableVecGuard`] over `u8` and a [`BytesMut`](bytes::BytesMut) from
+//! an [`AsyncPool`] both are one.
+//!
+//! An [`AsyncPool`] bounds how many containers can be checked out at once.
+//! Once the bound is reached, [`get`](AsyncPool::get) waits for a guard to be
+//! returned instead of allocating another buffer, which caps the memory a
+//! burst of connections can take. Its guards own a handle to the pool, so
+//! they can be held across `.await` points in spawned tasks.
+//!
+//! This module is only available with the `tokio` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use bytes::BytesMut;
+//! use tokio::io::AsyncReadExt;
+//! use triple_r::tokio::AsyncPool;
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let pool = AsyncPool::<BytesMut>::new(64);
+//!
+//! let mut socket: &[u8] = b"HELLO";
+//! let mut frame = pool.get().await;
+//! frame.reserve(1024);
+//! socket.read_buf(&mut *frame).await?;
+//! assert_eq!(&frame[..], b"HELLO");
+//! drop(frame);
+//!
+//! // The buffer is back, empty, with its capacity.
+//! assert!(pool.get().await.capacity() >= 1024);
+//! # std::io::Result::Ok(())
+//! # }).unwrap();
+//! ```
+use crate::{PooledVec, Recyclable, ReusablePool, ReusableVec, ReusableVecGuard, VecPool};
+use std::{
+    fmt, io,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+
+/// Reads `reader` to its end into a guard recycled from `buffer`.
+///
+/// If reading fails, the guard is dropped and the bytes read so far are
+/// cleared, but the allocation is kept.
+pub async fn read_to_end_reusing<'a, R>(
+    reader: &mut R,
+    buffer: &'a mut ReusableVec<u8>,
+) -> io::Result<ReusableVecGuard<'a, u8, u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let mut guard = buffer.recycle_same();
+    reader.read_to_end(&mut guard).await?;
+    Ok(guard)
+}
+
+/// Reads `reader` to its end into a buffer taken from `pool`.
+///
+/// If reading fails, the buffer goes back to the pool.
+pub async fn read_to_end_pooled<'a, R>(
+    reader: &mut R,
+    pool: &'a VecPool<u8>,
+) -> io::Result<PooledVec<'a, u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let mut buffer = pool.get();
+    reader.read_to_end(&mut buffer).await?;
+    Ok(buffer)
+}
+
+/// A pool of recycled [`Recyclable`] containers whose checkout waits while
+/// too many are out.
+///
+/// Cloning an `AsyncPool` shares the same containers and the same bound.
+/// Containers are created on demand with [`Default`], and the free list is a
+/// [`ReusablePool`], whose lock is never held across an `.await`.
+pub struct AsyncPool<T> {
+    free: Arc<ReusablePool<T>>,
+    permits: Arc<Semaphore>,
+}
+
+impl<T> Clone for AsyncPool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            free: Arc::clone(&self.free),
+            permits: Arc::clone(&self.permits),
+        }
+    }
+}
+
+impl<T> fmt::Debug for AsyncPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncPool")
+            .field("available", &self.available())
+            .field("permits", &self.permits.available_permits())
+            .finish()
+    }
+}
+
+impl<T> AsyncPool<T> {
+    /// Creates a new, empty pool that lets at most `max_checked_out`
+    /// containers be out at once.
+    pub fn new(max_checked_out: usize) -> Self {
+        Self {
+            free: Arc::new(ReusablePool::with_max_idle(max_checked_out)),
+            permits: Arc::new(Semaphore::new(max_checked_out)),
+        }
+    }
+
+    /// Takes a container out of the pool, waiting for one to be returned if
+    /// `max_checked_out` of them are already out.
+    ///
+    /// Waiters are served in the order they called `get`.
+    pub async fn get(&self) -> AsyncPoolGuard<T>
+    where
+        T: Recyclable + Default,
+    {
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .expect("the semaphore of a pool is never closed");
+        self.checkout(permit)
+    }
+
+    /// Takes a container out of the pool, or returns `None` if
+    /// `max_checked_out` of them are already out.
+    pub fn try_get(&self) -> Option<AsyncPoolGuard<T>>
+    where
+        T: Recyclable + Default,
+    {
+        let permit = Arc::clone(&self.permits).try_acquire_owned().ok()?;
+        Some(self.checkout(permit))
+    }
+
+    /// The number of containers currently waiting in the pool.
+    pub fn available(&self) -> usize {
+        self.free.available()
+    }
+
+    fn checkout(&self, permit: OwnedSemaphorePermit) -> AsyncPoolGuard<T>
+    where
+        T: Recyclable + Default,
+    {
+        AsyncPoolGuard {
+            object: ManuallyDrop::new(self.free.take()),
+            free: Arc::clone(&self.free),
+            _permit: permit,
+        }
+    }
+}
+
+/// An owned guard over a container taken from an [`AsyncPool`].
+///
+/// When the guard is dropped, the container is cleared and returned to the
+/// pool, and the next waiting [`get`](AsyncPool::get) can take it.
+pub struct AsyncPoolGuard<T: Recyclable> {
+    object: ManuallyDrop<T>,
+    free: Arc<ReusablePool<T>>,
+    // Dropped after the container is returned, so the waiter it wakes finds
+    // it in the pool.
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<T: Recyclable> Deref for AsyncPoolGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.object
+    }
+}
+
+impl<T: Recyclable> DerefMut for AsyncPoolGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.object
+    }
+}
+
+impl<T: Recyclable + fmt::Debug> fmt::Debug for AsyncPoolGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.object, f)
+    }
+}
+
+impl<T: Recyclable> Drop for AsyncPoolGuard<T> {
+    /// Clears the container and returns it to the pool.
+    fn drop(&mut self) {
+        // SAFETY: The container is only taken here, and the guard is gone
+        // afterwards.
+        let object = unsafe { ManuallyDrop::take(&mut self.object) };
+        self.free.put(object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn reads_land_in_recycled_buffers() {
+        block_on(async {
+            let mut buffer = ReusableVec::<u8>::default();
+            let mut reader: &[u8] = b"first message";
+            let read = read_to_end_reusing(&mut reader, &mut buffer).await.unwrap();
+            assert_eq!(&read[..], b"first message");
+            drop(read);
+            assert!(buffer.capacity() >= 13);
+
+            let pool = VecPool::new();
+            let mut reader: &[u8] = b"second";
+            let read = read_to_end_pooled(&mut reader, &pool).await.unwrap();
+            assert_eq!(&read[..], b"second");
+        });
+    }
+
+    #[test]
+    fn checkouts_wait_for_a_returned_container() {
+        block_on(async {
+            let pool = AsyncPool::<Vec<u8>>::new(1);
+            let mut first = pool.get().await;
+            first.extend_from_slice(b"busy");
+            assert!(pool.try_get().is_none());
+
+            let waiter = tokio::spawn({
+                let pool = pool.clone();
+                async move { pool.get().await.capacity() }
+            });
+            tokio::task::yield_now().await;
+            drop(first);
+            assert!(waiter.await.unwrap() >= 4);
+        });
+    }
+}
@@ -0,0 +1,115 @@
+//! Instrumentation of guards with [tracing](https://docs.rs/tracing).
+//!
+//! Every guard from `recycle` emits a `TRACE` event when it is created, and
+//! a `DEBUG` event when it is dropped if the collection outgrew the capacity
+//! it started with, or if a capacity policy shrank it afterwards. The events
+//! carry the container's type name, so a subscriber filtering on the
+//! `triple_r` target can tell which reusable is behind an allocation spike.
+use tracing::{debug, trace};
+
+/// What a guard needs to remember to report on its own drop.
+pub(crate) struct GuardTrace {
+    container: &'static str,
+    start_capacity: usize,
+}
+
+impl GuardTrace {
+    /// Reports a guard handed out by `container` with `capacity` to spare.
+    pub(crate) fn recycle(container: &'static str, capacity: usize) -> Self {
+        trace!(target: "triple_r", container, capacity, "recycle");
+        Self {
+            container,
+            start_capacity: capacity,
+        }
+    }
+
+    /// Reports a guard dropped with `len` elements in `capacity` slots, of
+    /// which `retained` are kept for the next guard.
+    pub(crate) fn finish(&self, len: usize, capacity: usize, retained: usize) {
+        let container = self.container;
+        if capacity > self.start_capacity {
+            debug!(
+                target: "triple_r",
+                container,
+                len,
+                retained = self.start_capacity,
+                capacity,
+                "grew beyond the retained capacity"
+            );
+        }
+        if retained < capacity {
+            debug!(
+                target: "triple_r",
+                container,
+                capacity,
+                retained,
+                "shrank the retained allocation"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ReusableVec;
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    /// Collects the messages of the events it sees.
+    #[derive(Clone, Default)]
+    struct Messages(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Messages {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                self.0.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl Subscriber for Messages {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn guards_report_growth_and_shrinking() {
+        let messages = Messages::default();
+        tracing::subscriber::with_default(messages.clone(), || {
+            let mut vec = ReusableVec::<u64>::default();
+            vec.set_max_retained_capacity(Some(16));
+            vec.recycle_same().extend(0..100);
+        });
+        assert_eq!(
+            *messages.0.lock().unwrap(),
+            [
+                "recycle",
+                "grew beyond the retained capacity",
+                "shrank the retained allocation",
+            ]
+        );
+    }
+}
@@ -1,8 +1,29 @@
-use crate::ReuseCastInto;
+#[cfg(feature = "defer-drop")]
+use crate::defer_drop::{DropOffload, DropThread};
+#[cfg(feature = "stats")]
+use crate::stats::RecycleStats;
+#[cfg(feature = "tracing")]
+use crate::trace::GuardTrace;
+#[cfg(feature = "zeroize")]
+use crate::zeroize::{clear_and_zeroize, DropPolicy};
+use crate::{
+    adaptive::AdaptiveCapacity,
+    clear_policy::Leftovers,
+    hooks::{HookWatch, Hooks},
+    poison::PoisonOnPanic,
+    spare::{self, SpareCapacity},
+    AdaptiveCapacityPolicy, ClearPolicy, MemoryFootprint, PoisonError, PoisonPolicy, Reset,
+    ReuseCastInto,
+};
 use std::{
+    borrow::{Borrow, BorrowMut},
     cell::UnsafeCell,
+    fmt,
+    hash::{Hash, Hasher},
     marker::PhantomData,
+    mem,
     ops::{Deref, DerefMut},
+    vec::Drain,
 };
 
 /// A wrapper around [`Vec`] that allows for reusing its allocation.
@@ -54,9 +75,28 @@ use std::{
 ///     assert_eq!(*vec_guard, vec![30]);
 /// }
 /// ```
-#[derive(Debug)]
 pub struct ReusableVec<T: 'static> {
     inner: UnsafeCell<Vec<T>>,
+    max_capacity: Option<usize>,
+    adaptive: Option<AdaptiveCapacity>,
+    clear_policy: ClearPolicy,
+    /// What the last guard left in the `Vec`, if it deferred clearing or
+    /// was leaked.
+    leftovers: Leftovers,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+    clear_budget: Option<usize>,
+    /// Elements a guard had more of than the clear budget, dropped a
+    /// budget's worth at a time.
+    pending: Vec<T>,
+    hooks: Hooks,
+    #[cfg(feature = "zeroize")]
+    drop_policy: DropPolicy,
+    #[cfg(feature = "defer-drop")]
+    drop_thread: Option<DropOffload<Vec<T>>>,
+    #[cfg(feature = "stats")]
+    stats: RecycleStats,
 }
 
 // The `ReusableVec` is safe to send across threads if `T` is `Send`.
@@ -67,15 +107,54 @@ unsafe impl<T: Send> Send for ReusableVec<T> {}
 // without external synchronization (like a `Mutex`).
 unsafe impl<T: Send> Sync for ReusableVec<T> {}
 
+// Only the allocation is shown. Between guards the elements are stale, and
+// after a leaked guard they may borrow data that is gone.
+impl<T: 'static> fmt::Debug for ReusableVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ReusableVec");
+        debug
+            .field("capacity", &self.capacity())
+            .field("len", &self.len());
+        #[cfg(feature = "stats")]
+        debug.field("stats", &self.stats);
+        debug.finish()
+    }
+}
+
 impl<T: 'static> Default for ReusableVec<T> {
     /// Creates a new, empty `ReusableVec` with no allocation.
     fn default() -> Self {
         Self {
             inner: UnsafeCell::new(Vec::new()),
+            max_capacity: None,
+            adaptive: None,
+            clear_policy: ClearPolicy::default(),
+            leftovers: Leftovers::None,
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+            clear_budget: None,
+            pending: Vec::new(),
+            hooks: Hooks::default(),
+            #[cfg(feature = "zeroize")]
+            drop_policy: DropPolicy::default(),
+            #[cfg(feature = "defer-drop")]
+            drop_thread: None,
+            #[cfg(feature = "stats")]
+            stats: RecycleStats::default(),
         }
     }
 }
 
+impl<T: 'static> From<Vec<T>> for ReusableVec<T> {
+    /// Adopts the allocation of `vec`, dropping its elements.
+    fn from(mut vec: Vec<T>) -> Self {
+        vec.clear();
+        let mut reusable = Self::default();
+        *reusable.inner.get_mut() = vec;
+        reusable
+    }
+}
+
 /// A RAII guard that provides temporary, exclusive access to a `Vec` from a
 /// [`ReusableVec`].
 ///
@@ -84,15 +163,43 @@ impl<T: 'static> Default for ReusableVec<T> {
 /// automatically clears the `Vec`, preserving its allocation for the next cycle.
 ///
 /// The lifetime `'parent` ensures that this guard cannot outlive the
-/// [`ReusableVec`] from which it was borrowed.
+/// [`ReusableVec`] from which it was borrowed. The guard is `Send` when the
+/// element types are, so it can be moved into a [`std::thread::scope`]
+/// closure and filled on another thread.
 pub struct ReusableVecGuard<'parent, T1, T2>
 where
     T1: 'static,
 {
     inner: *mut Vec<T2>,
+    max_capacity: Option<usize>,
+    adaptive: &'parent mut Option<AdaptiveCapacity>,
+    leftovers: &'parent mut Leftovers,
+    /// Whether the elements are left for the next `recycle` to clear.
+    defer: bool,
+    _poison: PoisonOnPanic<'parent>,
+    /// The clear budget and the parent's pending elements, if the elements
+    /// can be dropped as `T1`s at any later point.
+    chunked: Option<(usize, &'parent mut Vec<T1>)>,
+    hooks: HookWatch<'parent>,
+    #[cfg(feature = "tracing")]
+    trace: GuardTrace,
+    #[cfg(feature = "zeroize")]
+    drop_policy: DropPolicy,
+    /// Where the elements go instead of being dropped in place, if they can
+    /// be dropped as `T1`s at any later point.
+    #[cfg(feature = "defer-drop")]
+    drop_thread: Option<&'parent DropOffload<Vec<T1>>>,
+    #[cfg(feature = "stats")]
+    stats: &'parent mut RecycleStats,
     _parent: PhantomData<&'parent mut ReusableVec<T1>>,
 }
 
+// SAFETY: The guard is an exclusive borrow of its parent, which is `Send` and
+// `Sync` when `T1` is. The parent stays mutably borrowed while the guard is
+// alive, so nothing else can reach the `Vec` or the parent's fields the guard
+// points to, and hooks are `Send + Sync` themselves.
+unsafe impl<'parent, T1: Send, T2: Send> Send for ReusableVecGuard<'parent, T1, T2> {}
+
 impl<'parent, T1, T2> Deref for ReusableVecGuard<'parent, T1, T2>
 where
     T1: 'static,
@@ -121,10 +228,263 @@ where
     }
 }
 
+impl<'parent, T1, T2> ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// The estimated number of heap bytes the `Vec` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `Vec`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one, and the drop policy no longer applies to it. Use this
+    /// when a cycle's result has to outlive the guard, for example to send it
+    /// to another thread.
+    pub fn detach(mut self) -> Vec<T2> {
+        mem::take(&mut *self)
+    }
+}
+
+impl<'parent, T1> ReusableVecGuard<'parent, T1, u8>
+where
+    T1: 'static,
+{
+    /// Reserves room for at least `additional` more bytes, and lets `f` fill
+    /// the spare capacity through a [`SpareCapacity`] cursor, without zeroing
+    /// it first.
+    ///
+    /// Only the bytes the cursor counts as filled are added to the `Vec`,
+    /// after the ones it already holds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use triple_r::ReusableVec;
+    ///
+    /// let mut buffer = ReusableVec::<u8>::default();
+    /// let mut socket: &[u8] = b"recycled bytes";
+    ///
+    /// let mut frame = buffer.recycle_same();
+    /// let read = frame.fill_spare_capacity(64, |spare| {
+    ///     let read = socket.read(spare.init_mut())?;
+    ///     spare.advance(read);
+    ///     std::io::Result::Ok(read)
+    /// })?;
+    /// assert_eq!(read, 14);
+    /// assert_eq!(&frame[..], b"recycled bytes");
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn fill_spare_capacity<R>(
+        &mut self,
+        additional: usize,
+        f: impl FnOnce(&mut SpareCapacity<'_>) -> R,
+    ) -> R {
+        spare::fill_spare_capacity(self, additional, f)
+    }
+}
+
+impl<'parent, T1, T2> fmt::Debug for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, 'b, T1, T2, U1, U2> PartialEq<ReusableVecGuard<'b, U1, U2>>
+    for ReusableVecGuard<'a, T1, T2>
+where
+    T1: 'static,
+    U1: 'static,
+    T2: PartialEq<U2>,
+{
+    fn eq(&self, other: &ReusableVecGuard<'b, U1, U2>) -> bool {
+        **self == **other
+    }
+}
+
+impl<'parent, T1, T2, U> PartialEq<Vec<U>> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: PartialEq<U>,
+{
+    fn eq(&self, other: &Vec<U>) -> bool {
+        **self == *other
+    }
+}
+
+impl<'parent, T1, T2, U> PartialEq<[U]> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: PartialEq<U>,
+{
+    fn eq(&self, other: &[U]) -> bool {
+        **self == *other
+    }
+}
+
+impl<'parent, T1, T2, U, const N: usize> PartialEq<[U; N]> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: PartialEq<U>,
+{
+    fn eq(&self, other: &[U; N]) -> bool {
+        **self == *other
+    }
+}
+
+impl<'parent, T1, T2, U> PartialEq<ReusableVecGuard<'parent, T1, T2>> for Vec<U>
+where
+    T1: 'static,
+    U: PartialEq<T2>,
+{
+    fn eq(&self, other: &ReusableVecGuard<'parent, T1, T2>) -> bool {
+        *self == **other
+    }
+}
+
+impl<'parent, T1, T2> Eq for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: Eq,
+{
+}
+
+impl<'parent, T1, T2> Hash for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<'parent, T1, T2> AsRef<Vec<T2>> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn as_ref(&self) -> &Vec<T2> {
+        self
+    }
+}
+
+impl<'parent, T1, T2> AsMut<Vec<T2>> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn as_mut(&mut self) -> &mut Vec<T2> {
+        self
+    }
+}
+
+impl<'parent, T1, T2> Borrow<Vec<T2>> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn borrow(&self) -> &Vec<T2> {
+        self
+    }
+}
+
+impl<'parent, T1, T2> BorrowMut<Vec<T2>> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn borrow_mut(&mut self) -> &mut Vec<T2> {
+        self
+    }
+}
+
+impl<'parent, T1, T2> AsRef<[T2]> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn as_ref(&self) -> &[T2] {
+        self
+    }
+}
+
+impl<'parent, T1, T2> AsMut<[T2]> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn as_mut(&mut self) -> &mut [T2] {
+        self
+    }
+}
+
+impl<'parent, T1, T2> Borrow<[T2]> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn borrow(&self) -> &[T2] {
+        self
+    }
+}
+
+impl<'parent, T1, T2> BorrowMut<[T2]> for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    fn borrow_mut(&mut self) -> &mut [T2] {
+        self
+    }
+}
+
 impl<T1> ReusableVec<T1>
 where
     T1: 'static,
 {
+    /// Creates a `ReusableVec` whose allocation already holds `capacity`
+    /// elements, so the first cycles don't reallocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut reusable = Self::default();
+        reusable.prewarm(capacity);
+        reusable
+    }
+
+    /// Grows the retained allocation to hold at least `capacity` elements,
+    /// without taking a guard.
+    ///
+    /// Does nothing if the allocation is already large enough.
+    pub fn prewarm(&mut self, capacity: usize) {
+        self.clear_leftovers();
+        let vec = self.inner.get_mut();
+        // Elements kept by `recycle_resettable` count towards the capacity.
+        vec.reserve_exact(capacity.saturating_sub(vec.len()));
+    }
+
+    /// Releases the retained allocation, without taking a guard.
+    ///
+    /// Elements kept by [`recycle_resettable`](Self::recycle_resettable)
+    /// stay, so the allocation only shrinks to fit them.
+    pub fn shrink_to_fit(&mut self) {
+        self.clear_leftovers();
+        self.inner.get_mut().shrink_to_fit();
+    }
+
+    /// Shrinks the retained allocation to hold at least `min_capacity`
+    /// elements, without taking a guard.
+    ///
+    /// Does nothing if the allocation is already smaller. Use this to let
+    /// go of the memory a one-off batch left behind while keeping room for
+    /// the usual one.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.clear_leftovers();
+        self.inner.get_mut().shrink_to(min_capacity);
+    }
+
     /// Reuses the `Vec`'s allocation, returning a guard for temporary access.
     ///
     /// This method allows the `Vec`'s element type to be "cast" to a new type `T2`,
@@ -132,33 +492,742 @@ where
     ///
     /// The `&mut self` requirement is a key safety feature, as it ensures that
     /// only one guard can be active at any given time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the `Vec` and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
     pub fn recycle<'parent, T2>(&'parent mut self) -> ReusableVecGuard<'parent, T1, T2>
     where
         T1: ReuseCastInto<T2>,
     {
+        // SAFETY: `ReuseCastInto` guarantees that `T1` and `T2` share a
+        // layout.
+        unsafe { self.recycle_unchecked() }
+    }
+
+    /// Returns a guard for the `Vec` without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the `Vec` and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableVecGuard<'_, T1, T1> {
+        // SAFETY: A type always shares a layout with itself. Elements left
+        // behind are `T1`s, which can be dropped whenever.
+        unsafe { self.guard(true) }
+    }
+
+    /// Reuses the `Vec`'s allocation like [`recycle`](Self::recycle), but
+    /// returns an error instead of panicking if a panic poisoned it and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<T2>(&mut self) -> Result<ReusableVecGuard<'_, T1, T2>, PoisonError>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Recycles the `Vec` for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableVecGuard<'_, T1, T2>) -> R,
+    ) -> R
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `Vec`, allocation and all.
+    ///
+    /// It holds no elements, except any kept by
+    /// [`recycle_resettable`](Self::recycle_resettable).
+    pub fn into_inner(mut self) -> Vec<T1> {
+        self.clear_leftovers();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// The number of elements the retained `Vec` can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `Vec` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() + self.pending.memory_footprint() }
+    }
+
+    /// The number of elements kept between guards by
+    /// [`recycle_resettable`](Self::recycle_resettable).
+    ///
+    /// Every other guard leaves the `Vec` empty.
+    pub fn len(&self) -> usize {
+        if self.leftovers != Leftovers::None {
+            return 0;
+        }
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).len() }
+    }
+
+    /// Returns `true` if no elements are kept between guards.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Exchanges the retained allocation with the one in `other`, without
+    /// copying either.
+    ///
+    /// `other` gets the retained `Vec`, empty, and its own allocation is kept
+    /// for the next guard. Anything left in `other` is dropped.
+    pub fn swap(&mut self, other: &mut Vec<T1>) {
+        self.clear_leftovers();
+        other.clear();
+        mem::swap(self.inner.get_mut(), other);
+        // Elements kept by `recycle_resettable` are not handed out.
+        other.clear();
+    }
+
+    /// Recycles the `Vec` and fills it from `iter`, returning the populated
+    /// guard.
+    ///
+    /// Room for the iterator's lower size hint is reserved up front, so a
+    /// warm `Vec` usually fills without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use triple_r::ReusableVec;
+    ///
+    /// let mut squares = ReusableVec::<u64>::default();
+    /// let guard = squares.recycle_collect((1..=4).map(|n| n * n));
+    /// assert_eq!(*guard, [1, 4, 9, 16]);
+    /// ```
+    pub fn recycle_collect<T2, I>(&mut self, iter: I) -> ReusableVecGuard<'_, T1, T2>
+    where
+        T1: ReuseCastInto<T2>,
+        I: IntoIterator<Item = T2>,
+    {
+        let iter = iter.into_iter();
+        let mut guard = self.recycle();
+        guard.reserve(iter.size_hint().0);
+        guard.extend(iter);
+        guard
+    }
+
+    /// Returns a guard cast to `T2` without checking that the cast is valid.
+    ///
+    /// # Safety
+    ///
+    /// A `Vec<T1>` must be castable to a `Vec<T2>`, which at least requires
+    /// `T1` and `T2` to have the same size and alignment.
+    pub(crate) unsafe fn recycle_unchecked<T2>(&mut self) -> ReusableVecGuard<'_, T1, T2> {
+        // Cast elements may borrow data that is gone by the next `recycle`,
+        // so they are only dropped there if dropping them does nothing.
+        let droppable = !mem::needs_drop::<T1>() && !mem::needs_drop::<T2>();
+        self.guard(droppable)
+    }
+
+    /// Returns a guard cast to `T2`, which may leave its elements behind if
+    /// they are `droppable` and the clear policy asks for it.
+    ///
+    /// # Safety
+    ///
+    /// The same as [`recycle_unchecked`](Self::recycle_unchecked), and if
+    /// `droppable` is set, dropping the elements left behind as `T1`s must be
+    /// sound at any later point.
+    unsafe fn guard<T2>(&mut self, droppable: bool) -> ReusableVecGuard<'_, T1, T2> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.clear_leftovers();
+        self.drop_pending();
+        // A guard from `recycle_resettable` leaves its elements behind, and
+        // those can't be cast to `T2`.
+        self.inner.get_mut().clear();
+        // Until the guard is dropped, whatever is in the `Vec` is treated as
+        // leaked.
+        self.leftovers = if droppable {
+            Leftovers::Droppable
+        } else {
+            Leftovers::Leaked
+        };
+        let trivial = !mem::needs_drop::<T1>() && !mem::needs_drop::<T2>();
+        let defer = droppable && self.clear_policy.defers(trivial);
+        #[cfg(feature = "zeroize")]
+        let defer = defer && self.drop_policy != DropPolicy::Zeroize;
+        #[cfg(feature = "defer-drop")]
+        let drop_thread = self
+            .drop_thread
+            .as_ref()
+            .filter(|_| droppable && mem::needs_drop::<T1>());
+        #[cfg(all(feature = "defer-drop", feature = "zeroize"))]
+        let drop_thread = drop_thread.filter(|_| self.drop_policy != DropPolicy::Zeroize);
+        let chunked = match self.clear_budget {
+            Some(budget) if droppable && mem::needs_drop::<T1>() => {
+                Some((budget, &mut self.pending))
+            }
+            _ => None,
+        };
+        #[cfg(feature = "zeroize")]
+        let chunked = chunked.filter(|_| self.drop_policy != DropPolicy::Zeroize);
+        let capacity = self.inner.get_mut().capacity();
+        #[cfg(feature = "stats")]
+        self.stats.begin(capacity);
+        #[cfg(feature = "tracing")]
+        let trace = GuardTrace::recycle(std::any::type_name::<Self>(), capacity);
         // SAFETY: We use `get()` to obtain a raw pointer to the vector.
         // This is safe because `&mut self` guarantees exclusive access.
         let inner_ptr = self.inner.get() as *mut Vec<T2>;
 
         ReusableVecGuard {
             inner: inner_ptr,
+            max_capacity: self.max_capacity,
+            adaptive: &mut self.adaptive,
+            leftovers: &mut self.leftovers,
+            defer,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            chunked,
+            hooks: self.hooks.recycle(capacity),
+            #[cfg(feature = "tracing")]
+            trace,
+            #[cfg(feature = "zeroize")]
+            drop_policy: self.drop_policy,
+            #[cfg(feature = "defer-drop")]
+            drop_thread,
+            #[cfg(feature = "stats")]
+            stats: &mut self.stats,
             _parent: PhantomData,
         }
     }
+
+    /// Returns a guard that keeps elements alive between uses and resets
+    /// them in place, instead of dropping them.
+    ///
+    /// This is the object-pool flavor of [`recycle`](ReusableVec::recycle):
+    /// [`push_reset`](ReusableVecResetGuard::push_reset) hands out a retained
+    /// element whenever one is left, and when the guard is dropped, every
+    /// element is [`Reset`] and kept. A `ReusableVec<MyBuffer>` then retains
+    /// each buffer's own allocations across cycles, not just the outer one.
+    ///
+    /// The element type cannot be cast, since the elements outlive the
+    /// guard. A later call to `recycle` drops the retained elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use triple_r::ReusableVec;
+    ///
+    /// let mut frames = ReusableVec::<Vec<u8>>::default();
+    ///
+    /// for size in [1024, 512] {
+    ///     let mut guard = frames.recycle_resettable();
+    ///     let frame = guard.push_reset();
+    ///     assert!(frame.is_empty());
+    ///     frame.resize(size, 0);
+    /// }
+    ///
+    /// // The first frame's allocation survived the second cycle.
+    /// assert!(frames.recycle_resettable().push_reset().capacity() >= 1024);
+    /// ```
+    pub fn recycle_resettable(&mut self) -> ReusableVecResetGuard<'_, T1>
+    where
+        T1: Reset + Default,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.clear_leftovers();
+        self.drop_pending();
+        #[cfg(feature = "stats")]
+        self.stats.begin(self.inner.get_mut().capacity());
+        ReusableVecResetGuard {
+            elements: self.inner.get_mut(),
+            len: 0,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            #[cfg(feature = "stats")]
+            stats: &mut self.stats,
+        }
+    }
+
+    /// Returns a mutable reference to the retained `Vec`.
+    ///
+    /// This is safe because `&mut self` guarantees that no guard is alive.
+    #[cfg(feature = "arrow")]
+    pub(crate) fn get_mut(&mut self) -> &mut Vec<T1> {
+        self.clear_leftovers();
+        self.inner.get_mut()
+    }
+
+    /// Sets when the `Vec` is cleared: when a guard is dropped, at the next
+    /// `recycle`, or both.
+    ///
+    /// See [`ClearPolicy`] for which guards can defer clearing. With the
+    /// `zeroize` feature and [`DropPolicy::Zeroize`], guards always clear
+    /// when they are dropped.
+    pub fn set_clear_policy(&mut self, policy: ClearPolicy) {
+        self.clear_policy = policy;
+    }
+
+    /// Returns the current clear policy.
+    pub fn clear_policy(&self) -> ClearPolicy {
+        self.clear_policy
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the `Vec` was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the `Vec` as no longer poisoned, keeping its allocation.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the `Vec`, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.clear_leftovers();
+            *self.inner.get_mut() = Vec::new();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Empties the `Vec` of whatever the last guard left behind.
+    ///
+    /// Elements deferred by [`ClearPolicy::OnRecycle`] are dropped. Those of
+    /// a leaked guard that cast them are leaked instead, since their
+    /// destructors could read borrowed data that is gone.
+    fn clear_leftovers(&mut self) {
+        let vec = self.inner.get_mut();
+        match mem::replace(&mut self.leftovers, Leftovers::None) {
+            Leftovers::None => {}
+            Leftovers::Droppable => vec.clear(),
+            // SAFETY: Leaking elements is always sound, and the `Vec` keeps
+            // its allocation.
+            Leftovers::Leaked => unsafe { vec.set_len(0) },
+        }
+    }
+
+    /// Drops at most a clear budget's worth of the pending elements, or all
+    /// of them if there is no budget.
+    fn drop_pending(&mut self) {
+        match self.clear_budget {
+            Some(budget) => {
+                drop_from_back(&mut self.pending, budget);
+            }
+            None => self.pending.clear(),
+        }
+    }
+
+    /// Caps how many elements are dropped when a guard is dropped, and
+    /// again when `recycle` is called, at `budget`.
+    ///
+    /// A guard holding more elements than that moves them to a second
+    /// buffer instead, which later guards and `recycle` calls work through
+    /// `budget` elements at a time. No single call then takes longer than
+    /// dropping `budget` elements, but the second buffer is retained too, so
+    /// a `Vec` that regularly goes over budget keeps two allocations.
+    ///
+    /// Only guards whose elements can be dropped later are chunked, like
+    /// the ones from [`recycle_same`](Self::recycle_same), and guards that
+    /// defer clearing to the next `recycle` ignore the budget. `None`, the
+    /// default, drops everything at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is `Some(0)`.
+    pub fn set_clear_budget(&mut self, budget: Option<usize>) {
+        assert!(budget != Some(0), "clear budget must be greater than zero");
+        self.clear_budget = budget;
+    }
+
+    /// Returns the budget set by
+    /// [`set_clear_budget`](Self::set_clear_budget).
+    pub fn clear_budget(&self) -> Option<usize> {
+        self.clear_budget
+    }
+
+    /// Installs or removes the [`AdaptiveCapacityPolicy`] consulted every
+    /// time a guard is dropped.
+    ///
+    /// Replacing the policy discards the lengths observed so far. Passing
+    /// `None`, the default, keeps whatever the guards grew to.
+    pub fn set_adaptive_capacity(&mut self, policy: Option<AdaptiveCapacityPolicy>) {
+        self.adaptive = policy.map(AdaptiveCapacity::new);
+    }
+
+    /// Returns the current [`AdaptiveCapacityPolicy`], if one is installed.
+    pub fn adaptive_capacity(&self) -> Option<AdaptiveCapacityPolicy> {
+        self.adaptive.as_ref().map(AdaptiveCapacity::policy)
+    }
+
+    /// Calls `hook` with the capacity of the `Vec` every time `recycle`
+    /// hands out a guard.
+    ///
+    /// Replaces the previous recycle hook. See the [`hooks`](crate::hooks)
+    /// module for an example.
+    pub fn on_recycle(&mut self, hook: impl Fn(usize) + Send + Sync + 'static) {
+        self.hooks.on_recycle = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the capacity a guard started with and the one it
+    /// was dropped with, whenever the `Vec` outgrew its retained allocation.
+    ///
+    /// Replaces the previous grow hook.
+    pub fn on_grow(&mut self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.hooks.on_grow = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the capacity before and after, whenever a capacity
+    /// policy shrinks the retained allocation as a guard is dropped.
+    ///
+    /// Replaces the previous shrink hook.
+    pub fn on_shrink(&mut self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.hooks.on_shrink = Some(Box::new(hook));
+    }
+
+    /// Removes the hooks installed with [`on_recycle`](Self::on_recycle),
+    /// [`on_grow`](Self::on_grow) and [`on_shrink`](Self::on_shrink).
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    /// Caps the capacity kept between guards at `max` elements.
+    ///
+    /// A guard that is dropped with a larger allocation shrinks it back to
+    /// `max`, so one outlier batch doesn't pin its memory for the rest of the
+    /// program. `None`, the default, keeps whatever the guards grew to.
+    ///
+    /// Elements kept by [`recycle_resettable`](Self::recycle_resettable) are
+    /// not affected.
+    pub fn set_max_retained_capacity(&mut self, max: Option<usize>) {
+        self.max_capacity = max;
+    }
+
+    /// Returns the cap set by
+    /// [`set_max_retained_capacity`](Self::set_max_retained_capacity).
+    pub fn max_retained_capacity(&self) -> Option<usize> {
+        self.max_capacity
+    }
+
+    /// Sets what guards do with the retained allocation when they are
+    /// dropped.
+    ///
+    /// See the [`zeroize`](crate::zeroize) module for what
+    /// [`DropPolicy::Zeroize`] does and does not cover.
+    #[cfg(feature = "zeroize")]
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+        self.drop_policy = policy;
+    }
+
+    /// Returns the current drop policy.
+    #[cfg(feature = "zeroize")]
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// Installs or removes the [`DropThread`] that guards hand their
+    /// elements to instead of dropping them.
+    ///
+    /// See the [`defer_drop`](crate::defer_drop) module for which guards
+    /// use it. Passing `None`, the default, drops elements in place.
+    #[cfg(feature = "defer-drop")]
+    pub fn set_drop_thread(&mut self, thread: Option<DropThread>)
+    where
+        T1: Send,
+    {
+        self.drop_thread = thread.map(DropOffload::new);
+    }
+
+    /// The statistics recorded so far.
+    ///
+    /// This method is only available with the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &RecycleStats {
+        &self.stats
+    }
+}
+
+impl<T: 'static> Drop for ReusableVec<T> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the `Vec`.
+    fn drop(&mut self) {
+        self.clear_leftovers();
+    }
 }
 
 impl<'parent, T1, T2> Drop for ReusableVecGuard<'parent, T1, T2>
 where
     T1: 'static,
 {
-    /// Clears the `Vec` when the guard is dropped.
+    /// Clears the `Vec` when the guard is dropped, unless the
+    /// [clear policy](ReusableVec::set_clear_policy) defers it, and shrinks it
+    /// as the [adaptive capacity policy](ReusableVec::set_adaptive_capacity)
+    /// and the [maximum retained capacity](ReusableVec::set_max_retained_capacity)
+    /// require.
+    ///
+    /// With the `zeroize` feature and [`DropPolicy::Zeroize`], the retained
+    /// allocation is overwritten with zeroes as well. With the `defer-drop`
+    /// feature and a [drop thread](ReusableVec::set_drop_thread), the
+    /// elements may be dropped there instead.
     fn drop(&mut self) {
         // SAFETY: The pointer `self.inner` is guaranteed to be valid because
         // the guard's lifetime is tied to the parent `ReusableVec`.
         // Clearing the vector prepares it for the next reuse cycle.
-        unsafe {
-            (*self.inner).clear();
+        let vec = unsafe { &mut *self.inner };
+        let len = vec.len();
+        let capacity = vec.capacity();
+        #[cfg(feature = "stats")]
+        self.stats.record(len, capacity);
+        #[cfg(feature = "defer-drop")]
+        let vec = match self.drop_thread {
+            Some(offload) if !self.defer && len > 0 => {
+                let capacity = vec.capacity();
+                // SAFETY: The guard only has a drop thread if its elements
+                // can be dropped as `T1`s at any later point, and `inner`
+                // points to the parent's `Vec<T1>`.
+                let parent = unsafe { &mut *(self.inner as *mut Vec<T1>) };
+                let keep = self.max_capacity.map_or(capacity, |max| capacity.min(max));
+                offload.send(mem::replace(parent, Vec::with_capacity(keep)));
+                // SAFETY: As above; the `Vec` is empty now.
+                unsafe { &mut *self.inner }
+            }
+            _ => vec,
+        };
+        let vec = match &mut self.chunked {
+            Some((budget, pending)) if !self.defer => {
+                let capacity = vec.capacity();
+                // SAFETY: The guard is only chunked if its elements can be
+                // dropped as `T1`s at any later point, and `inner` points to
+                // the parent's `Vec<T1>`.
+                let parent = unsafe { &mut *(self.inner as *mut Vec<T1>) };
+                let budget = drop_from_back(pending, *budget);
+                if parent.len() > budget {
+                    if pending.is_empty() {
+                        // The buffers trade places, and the guard's gets
+                        // back the room it had.
+                        mem::swap(parent, pending);
+                        parent.reserve_exact(capacity);
+                    } else {
+                        pending.append(parent);
+                    }
+                    drop_from_back(pending, budget);
+                }
+                // SAFETY: As above; the `Vec` holds no more than the budget
+                // now.
+                unsafe { &mut *self.inner }
+            }
+            _ => vec,
+        };
+        if !self.defer {
+            #[cfg(feature = "zeroize")]
+            if self.drop_policy == DropPolicy::Zeroize {
+                clear_and_zeroize(vec);
+            }
+            vec.clear();
+            *self.leftovers = Leftovers::None;
+        }
+        if let Some(target) = self
+            .adaptive
+            .as_mut()
+            .and_then(|adaptive| adaptive.observe(len, vec.capacity()))
+        {
+            vec.shrink_to(target);
         }
+        if let Some(max) = self.max_capacity {
+            vec.shrink_to(max);
+        }
+        self.hooks.finish(capacity, vec.capacity());
+        #[cfg(feature = "tracing")]
+        self.trace.finish(len, capacity, vec.capacity());
+    }
+}
+
+/// Drops at most `budget` elements from the back of `vec`, returning how
+/// much of the budget is left.
+fn drop_from_back<T>(vec: &mut Vec<T>, budget: usize) -> usize {
+    let dropped = budget.min(vec.len());
+    vec.truncate(vec.len() - dropped);
+    budget - dropped
+}
+
+/// A draining iterator over the contents of a [`ReusableVecGuard`].
+///
+/// Returned by the guard's `IntoIterator` impl. The elements are moved out by
+/// value, and the `Vec` keeps its allocation for the next guard.
+pub struct ReusableVecIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    drain: Drain<'parent, T2>,
+    // Declared after `drain`, so the guard's cleanup runs once the drain has
+    // finished with the `Vec`.
+    _guard: ReusableVecGuard<'parent, T1, T2>,
+}
+
+impl<'parent, T1, T2> IntoIterator for ReusableVecGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    type Item = T2;
+    type IntoIter = ReusableVecIntoIter<'parent, T1, T2>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // SAFETY: `self.inner` is valid and exclusive for `'parent`. The
+        // guard is moved into the iterator and only touches the `Vec` again
+        // when it is dropped, after the drain.
+        let drain = unsafe { &mut *self.inner }.drain(..);
+        ReusableVecIntoIter {
+            drain,
+            _guard: self,
+        }
+    }
+}
+
+impl<'parent, T1, T2> Iterator for ReusableVecIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    type Item = T2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<'parent, T1, T2> DoubleEndedIterator for ReusableVecIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<'parent, T1, T2> ExactSizeIterator for ReusableVecIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+}
+
+/// A RAII guard from [`ReusableVec::recycle_resettable`].
+///
+/// The guard dereferences to the slice of elements pushed so far. When it is
+/// dropped, every element is reset in place and kept for the next guard.
+pub struct ReusableVecResetGuard<'parent, T>
+where
+    T: Reset + Default + 'static,
+{
+    elements: &'parent mut Vec<T>,
+    len: usize,
+    _poison: PoisonOnPanic<'parent>,
+    #[cfg(feature = "stats")]
+    stats: &'parent mut RecycleStats,
+}
+
+impl<'parent, T> ReusableVecResetGuard<'parent, T>
+where
+    T: Reset + Default + 'static,
+{
+    /// Appends an element in its reset state and returns it, reusing a
+    /// retained element if there is one.
+    pub fn push_reset(&mut self) -> &mut T {
+        if self.len == self.elements.len() {
+            self.elements.push(T::default());
+        }
+        self.len += 1;
+        &mut self.elements[self.len - 1]
+    }
+
+    /// Shortens the guard to `len` elements. The removed elements are reset
+    /// and kept for reuse.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.elements[len..self.len].iter_mut().for_each(T::reset);
+            self.len = len;
+        }
+    }
+
+    /// Removes every element, keeping all of them for reuse.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+}
+
+impl<'parent, T> Deref for ReusableVecResetGuard<'parent, T>
+where
+    T: Reset + Default + 'static,
+{
+    type Target = [T];
+
+    /// Provides immutable access to the elements pushed so far.
+    fn deref(&self) -> &Self::Target {
+        &self.elements[..self.len]
+    }
+}
+
+impl<'parent, T> DerefMut for ReusableVecResetGuard<'parent, T>
+where
+    T: Reset + Default + 'static,
+{
+    /// Provides mutable access to the elements pushed so far.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.elements[..self.len]
+    }
+}
+
+impl<'parent, T> Drop for ReusableVecResetGuard<'parent, T>
+where
+    T: Reset + Default + 'static,
+{
+    /// Resets every element pushed through the guard, keeping all of them.
+    fn drop(&mut self) {
+        #[cfg(feature = "stats")]
+        self.stats.record(self.len, self.elements.capacity());
+        self.clear();
     }
 }
 
@@ -180,6 +1249,279 @@ mod tests {
         assert!(r_vec.capacity() >= 2);
     }
 
+    #[test]
+    fn recycle_same_needs_no_cast_impl() {
+        struct Sample {
+            value: u32,
+        }
+
+        let mut vec = ReusableVec::<Sample>::default();
+        {
+            let mut r_vec = vec.recycle_same();
+            r_vec.push(Sample { value: 3 });
+            assert_eq!(r_vec[0].value, 3);
+        }
+        assert!(vec.recycle_same().is_empty());
+    }
+
+    #[test]
+    fn recycle_collect_reuses_the_allocation() {
+        let mut vec = ReusableVec::<&'static str>::default();
+        let ptr = {
+            let text = String::from("a b c d");
+            let r_vec = vec.recycle_collect(text.split(' '));
+            assert_eq!(*r_vec, ["a", "b", "c", "d"]);
+            r_vec.as_ptr() as usize
+        };
+        let r_vec = vec.recycle_collect(["e"; 4]);
+        assert_eq!(r_vec.as_ptr() as usize, ptr);
+    }
+
+    #[test]
+    fn into_iter_drains_and_keeps_the_allocation() {
+        let mut vec = ReusableVec::<String>::default();
+        let mut r_vec = vec.recycle_same();
+        r_vec.extend(["a", "b", "c"].map(String::from));
+        let ptr = r_vec.as_ptr() as usize;
+
+        let mut iter = r_vec.into_iter();
+        assert_eq!(iter.next_back().as_deref(), Some("c"));
+        assert_eq!(iter.len(), 2);
+        // Dropping the iterator early drops the rest.
+        drop(iter);
+
+        let r_vec = vec.recycle_same();
+        assert!(r_vec.is_empty());
+        assert_eq!(r_vec.as_ptr() as usize, ptr);
+    }
+
+    #[test]
+    fn guards_compare_and_print_like_vecs() {
+        let mut a = ReusableVec::<u8>::default();
+        let mut b = ReusableVec::<u8>::default();
+        let mut left = a.recycle_same();
+        let mut right = b.recycle_same();
+        left.extend([1, 2]);
+        right.extend([1, 2]);
+
+        assert_eq!(left, right);
+        assert_eq!(left, vec![1, 2]);
+        assert_eq!(vec![1, 2], left);
+        assert_eq!(left, [1, 2]);
+        assert_eq!(format!("{:?}", left), "[1, 2]");
+
+        right.push(3);
+        assert_ne!(left, right);
+    }
+
+    #[test]
+    fn guards_pass_as_slices_and_vecs() {
+        fn total(values: impl AsRef<[u32]>) -> u32 {
+            values.as_ref().iter().sum()
+        }
+
+        fn append(values: &mut impl BorrowMut<Vec<u32>>) {
+            values.borrow_mut().push(10);
+        }
+
+        let mut vec = ReusableVec::<u32>::default();
+        let mut guard = vec.recycle_same();
+        guard.extend([1, 2, 3]);
+        append(&mut guard);
+        assert_eq!(total(&guard), 16);
+
+        let seen: std::collections::HashSet<Vec<u32>> = [vec![1, 2, 3, 10]].into();
+        assert!(seen.contains(Borrow::<[u32]>::borrow(&guard)));
+    }
+
+    #[test]
+    fn with_capacity_and_prewarm_grow_the_allocation() {
+        let mut vec = ReusableVec::<u64>::with_capacity(32);
+        assert!(vec.recycle_same().capacity() >= 32);
+
+        vec.prewarm(16);
+        assert!(vec.recycle_same().capacity() >= 32);
+        vec.prewarm(128);
+        let guard = vec.recycle_same();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 128);
+    }
+
+    #[test]
+    fn shrinking_keeps_resettable_elements() {
+        let mut vec = ReusableVec::<Vec<u8>>::with_capacity(64);
+        vec.recycle_resettable().push_reset().push(1);
+        vec.shrink_to(8);
+        assert!((8..64).contains(&vec.capacity()));
+
+        vec.shrink_to_fit();
+        assert_eq!(vec.len(), 1);
+        assert_eq!(vec.capacity(), 1);
+    }
+
+    #[test]
+    fn outlier_batches_shrink_back_to_the_cap() {
+        let mut vec = ReusableVec::<u32>::default();
+        vec.set_max_retained_capacity(Some(64));
+        vec.recycle_same().extend(0..16);
+        let small = vec.capacity();
+        assert!(small <= 64);
+
+        vec.recycle_same().extend(0..10_000);
+        assert!(vec.capacity() <= 64);
+
+        vec.set_max_retained_capacity(None);
+        vec.recycle_same().extend(0..10_000);
+        assert!(vec.capacity() >= 10_000);
+    }
+
+    #[test]
+    fn deferred_clearing_depends_on_drop_glue() {
+        let mut names = ReusableVec::<&'static str>::default();
+        names.set_clear_policy(ClearPolicy::OnRecycle);
+        {
+            let owned = String::from("borrowed");
+            names.recycle::<&str>().push(&owned);
+        }
+        // The dangling `&str` is left in place, but never read.
+        assert_eq!(unsafe { &*names.inner.get() }.len(), 1);
+        assert!(names.is_empty());
+        assert!(names.recycle::<&str>().is_empty());
+
+        let mut strings = ReusableVec::<String>::default();
+        strings.set_clear_policy(ClearPolicy::OnRecycle);
+        strings.recycle::<String>().push("text".to_string());
+        assert!(unsafe { &*strings.inner.get() }.is_empty());
+
+        // Only elements without drop glue are left in place.
+        strings.set_clear_policy(ClearPolicy::OnRecycleIfTrivial);
+        strings.recycle_same().push("text".to_string());
+        assert!(unsafe { &*strings.inner.get() }.is_empty());
+        names.set_clear_policy(ClearPolicy::OnRecycleIfTrivial);
+        names.recycle_same().push("static");
+        assert_eq!(unsafe { &*names.inner.get() }.len(), 1);
+    }
+
+    #[test]
+    fn debug_shows_the_allocation_but_not_the_elements() {
+        let mut names = ReusableVec::<&'static str>::with_capacity(4);
+        names.set_clear_policy(ClearPolicy::OnRecycle);
+        names.recycle_same().push("secret");
+
+        let debug = format!("{:?}", names);
+        assert!(debug.starts_with("ReusableVec { capacity: 4, len: 0"));
+        assert!(!debug.contains("secret"));
+
+        // The elements don't need to implement `Debug` at all.
+        struct Opaque;
+        let opaque = ReusableVec::<Opaque>::default();
+        assert!(format!("{:?}", opaque).starts_with("ReusableVec { capacity: "));
+    }
+
+    #[test]
+    fn leaked_guards_never_drop_cast_elements() {
+        use std::cell::Cell;
+
+        struct Counted<'a>(&'a Cell<u32>);
+
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        // SAFETY: Only the lifetime changes.
+        unsafe impl<'a, 'b> ReuseCastInto<Counted<'b>> for Counted<'a> {}
+
+        let mut vec = ReusableVec::<Counted<'static>>::default();
+        {
+            let drops = Cell::new(0);
+            mem::forget(vec.recycle_collect([Counted(&drops), Counted(&drops)]));
+        }
+        // Dropping the elements now would read the `Cell` that is gone.
+        assert!(vec.is_empty());
+        assert!(vec.recycle::<Counted<'_>>().is_empty());
+
+        let drops = Cell::new(0);
+        mem::forget(vec.recycle_collect([Counted(&drops)]));
+        assert!(vec.into_inner().is_empty());
+        assert_eq!(drops.get(), 0);
+    }
+
+    #[test]
+    fn from_adopts_the_allocation() {
+        let startup: Vec<String> = (0..64).map(|i| i.to_string()).collect();
+        let ptr = startup.as_ptr() as usize;
+
+        let mut vec = ReusableVec::from(startup);
+        let guard = vec.recycle_same();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 64);
+        assert_eq!(guard.as_ptr() as usize, ptr);
+    }
+
+    #[test]
+    fn into_inner_returns_the_allocation() {
+        let mut vec = ReusableVec::<&'static str>::default();
+        {
+            let word = String::from("borrowed");
+            vec.recycle().push(word.as_str());
+        }
+        let inner = vec.into_inner();
+        assert!(inner.is_empty());
+        assert!(inner.capacity() >= 1);
+    }
+
+    #[test]
+    fn detached_contents_outlive_the_guard() {
+        let mut vec = ReusableVec::<u32>::default();
+        let mut guard = vec.recycle_same();
+        guard.extend(0..10);
+        let owned = guard.detach();
+
+        let sum = std::thread::spawn(move || owned.iter().sum::<u32>())
+            .join()
+            .unwrap();
+        assert_eq!(sum, 45);
+
+        let mut guard = vec.recycle_same();
+        assert_eq!(guard.capacity(), 0);
+        guard.push(1);
+        assert_eq!(guard, [1]);
+    }
+
+    #[test]
+    fn swap_exchanges_allocations() {
+        let mut vec = ReusableVec::<u8>::with_capacity(8);
+        let mut external = Vec::with_capacity(1024);
+        external.extend_from_slice(b"leftover");
+        let external_ptr = external.as_ptr() as usize;
+
+        vec.swap(&mut external);
+        assert!(external.is_empty());
+        assert!(external.capacity() >= 8);
+
+        let guard = vec.recycle_same();
+        assert!(guard.is_empty());
+        assert_eq!(guard.as_ptr() as usize, external_ptr);
+    }
+
+    #[test]
+    fn retained_elements_and_capacity_are_visible() {
+        let mut vec = ReusableVec::<Vec<u8>>::default();
+        assert_eq!(vec.capacity(), 0);
+        {
+            let mut guard = vec.recycle_resettable();
+            guard.push_reset().push(1);
+            guard.push_reset().push(2);
+        }
+        assert_eq!(vec.len(), 2);
+        assert!(vec.capacity() >= 2);
+
+        drop(vec.recycle_same());
+        assert!(vec.is_empty());
+    }
+
     #[test]
     fn reference_reuse_works() {
         let mut vec = ReusableVec::<&'static str>::default();
@@ -225,4 +1567,163 @@ mod tests {
         assert!(r_vec.is_empty());
         assert_eq!(r_vec.capacity(), last_capacity);
     }
+
+    #[test]
+    fn resettable_elements_keep_their_allocations() {
+        let mut vec = ReusableVec::<String>::default();
+        let ptrs: Vec<_> = {
+            let mut guard = vec.recycle_resettable();
+            guard.push_reset().push_str("first");
+            guard.push_reset().push_str("second");
+            guard.iter().map(|s| s.as_ptr()).collect()
+        };
+
+        let mut guard = vec.recycle_resettable();
+        assert!(guard.is_empty());
+        assert_eq!(guard.push_reset().as_ptr(), ptrs[0]);
+        assert_eq!(guard.push_reset().as_ptr(), ptrs[1]);
+        assert!(guard.iter().all(|s| s.is_empty()));
+        guard.truncate(1);
+        assert_eq!(guard.len(), 1);
+    }
+
+    #[test]
+    fn recycle_drops_retained_elements() {
+        let mut vec = ReusableVec::<Vec<u8>>::default();
+        vec.recycle_resettable().push_reset().push(1);
+        assert_eq!(unsafe { &*vec.inner.get() }.len(), 1);
+
+        assert!(vec.recycle::<Vec<u8>>().is_empty());
+        assert!(unsafe { &*vec.inner.get() }.is_empty());
+    }
+
+    #[test]
+    fn clear_budget_spreads_drops_over_later_calls() {
+        use std::rc::Rc;
+
+        let shared = Rc::new(());
+        let mut vec = ReusableVec::<Rc<()>>::default();
+        vec.set_clear_budget(Some(2));
+
+        vec.recycle_same()
+            .extend((0..5).map(|_| Rc::clone(&shared)));
+        assert_eq!(Rc::strong_count(&shared), 4);
+
+        let guard = vec.recycle_same();
+        assert!(guard.is_empty());
+        assert!(guard.capacity() >= 5);
+        assert_eq!(Rc::strong_count(&shared), 2);
+        drop(guard);
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn optional_references_can_be_recycled() {
+        let mut vec = ReusableVec::<Option<&'static str>>::default();
+        for round in 0..2 {
+            let owned = format!("round {}", round);
+            let mut r_vec = vec.recycle::<Option<&str>>();
+            r_vec.extend([Some(owned.as_str()), None]);
+            assert_eq!(r_vec.as_slice(), &[Some(owned.as_str()), None]);
+        }
+    }
+
+    #[test]
+    fn arrays_of_references_can_be_recycled() {
+        let mut vec = ReusableVec::<[&'static str; 2]>::default();
+        for round in 0..2 {
+            let owned = format!("key={}", round);
+            let (key, value) = owned.split_at(3);
+            let mut r_vec = vec.recycle::<[&str; 2]>();
+            r_vec.push([key, value]);
+            assert_eq!(r_vec[0], ["key", &owned[3..]]);
+        }
+    }
+
+    #[test]
+    fn results_of_references_can_be_recycled() {
+        let mut vec = ReusableVec::<Result<&'static str, u32>>::default();
+        for batch in ["1 a 2", "b"] {
+            let mut r_vec = vec.recycle::<Result<&str, u32>>();
+            r_vec.extend(batch.split(' ').map(|token| match token.parse() {
+                Ok(number) => Err(number),
+                Err(_) => Ok(token),
+            }));
+            assert!(r_vec.iter().any(Result::is_ok));
+        }
+    }
+
+    #[test]
+    fn mutable_references_can_be_recycled() {
+        let mut vec = ReusableVec::<&'static mut u32>::default();
+        let mut counters = [0u32; 3];
+        for _ in 0..2 {
+            let mut r_vec = vec.recycle::<&mut u32>();
+            r_vec.extend(counters.iter_mut());
+            for counter in r_vec.iter_mut() {
+                **counter += 1;
+            }
+        }
+        assert_eq!(counters, [2; 3]);
+    }
+
+    #[test]
+    fn pointer_tables_can_be_recycled() {
+        extern "C" fn double(x: i32) -> i32 {
+            x * 2
+        }
+
+        let mut pointers = ReusableVec::<*const std::ffi::c_void>::default();
+        let value = 5u8;
+        pointers
+            .recycle::<*const std::ffi::c_void>()
+            .push(&value as *const u8 as *const std::ffi::c_void);
+
+        let mut callbacks = ReusableVec::<extern "C" fn(i32) -> i32>::default();
+        let mut r_vec = callbacks.recycle::<extern "C" fn(i32) -> i32>();
+        r_vec.push(double);
+        assert_eq!(r_vec[0](21), 42);
+    }
+
+    #[test]
+    fn boxed_references_can_be_recycled() {
+        let mut vec = ReusableVec::<Box<&'static str>>::default();
+        for round in 0..2 {
+            let owned = format!("round {}", round);
+            let mut r_vec = vec.recycle::<Box<&str>>();
+            r_vec.push(Box::new(owned.as_str()));
+            assert_eq!(*r_vec[0], owned);
+        }
+    }
+
+    #[test]
+    fn std_value_types_can_be_recycled() {
+        use std::{net::SocketAddr, num::NonZeroU32, ops::RangeInclusive, time::Duration};
+
+        let mut timings = ReusableVec::<(SocketAddr, Duration)>::default();
+        timings
+            .recycle::<(SocketAddr, Duration)>()
+            .push(("127.0.0.1:80".parse().unwrap(), Duration::from_millis(5)));
+
+        let mut ids = ReusableVec::<Option<NonZeroU32>>::default();
+        ids.recycle::<Option<NonZeroU32>>().push(NonZeroU32::new(1));
+
+        let mut ranges = ReusableVec::<RangeInclusive<char>>::default();
+        ranges.recycle::<RangeInclusive<char>>().push('a'..='z');
+    }
+
+    #[test]
+    fn guards_can_be_filled_on_scoped_threads() {
+        let mut evens = ReusableVec::<u32>::default();
+        let mut odds = ReusableVec::<u32>::default();
+        let mut even_guard = evens.recycle_same();
+        let mut odd_guard = odds.recycle_same();
+        std::thread::scope(|scope| {
+            scope.spawn(move || even_guard.extend((0..100).step_by(2)));
+            scope.spawn(move || odd_guard.extend((1..100).step_by(2)));
+        });
+        assert!(evens.capacity() >= 50);
+        assert!(odds.capacity() >= 50);
+        assert!(evens.recycle_same().is_empty());
+    }
 }
@@ -0,0 +1,427 @@
+use crate::{poison::PoisonOnPanic, MemoryFootprint, PoisonError, PoisonPolicy, ReuseCastInto};
+use std::{
+    cell::UnsafeCell,
+    collections::{vec_deque::Drain, VecDeque},
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+/// A wrapper around [`VecDeque`] that allows for reusing its allocation.
+///
+/// Event queues and breadth-first search frontiers are typically rebuilt
+/// every frame or every query. `ReusableVecDeque` mirrors
+/// [`ReusableVec`](crate::ReusableVec) for them: [`recycle`](ReusableVecDeque::recycle)
+/// returns a [`ReusableVecDequeGuard`] with exclusive access to the queue,
+/// and when the guard is dropped the queue is cleared while its ring buffer
+/// is kept for the next cycle. The element type can be cast through
+/// [`ReuseCastInto`], so the queue can hold references that only live for
+/// one cycle.
+///
+/// # Examples
+///
+/// ```
+/// use triple_r::ReusableVecDeque;
+///
+/// let edges = [vec![1, 2], vec![3], vec![3], vec![]];
+/// let mut frontier = ReusableVecDeque::<usize>::default();
+///
+/// for start in 0..edges.len() {
+///     let mut queue = frontier.recycle::<usize>();
+///     let mut reachable = 0;
+///     queue.push_back(start);
+///     while let Some(node) = queue.pop_front() {
+///         reachable += 1;
+///         queue.extend(&edges[node]);
+///     }
+///     assert!(reachable >= 1);
+/// }
+///
+/// assert!(frontier.recycle::<usize>().capacity() >= 2);
+/// ```
+pub struct ReusableVecDeque<T: 'static> {
+    inner: UnsafeCell<VecDeque<T>>,
+    /// Set when a guard is dropped by a panic.
+    poisoned: bool,
+    poison_policy: PoisonPolicy,
+}
+
+// The `ReusableVecDeque` is safe to send across threads if `T` is `Send`.
+unsafe impl<T: Send> Send for ReusableVecDeque<T> {}
+
+// The `ReusableVecDeque` is safe to share across threads if `T` is `Send`,
+// because `recycle` requires `&mut self`.
+unsafe impl<T: Send> Sync for ReusableVecDeque<T> {}
+
+impl<T: 'static> fmt::Debug for ReusableVecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableVecDeque")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<T: 'static> Default for ReusableVecDeque<T> {
+    /// Creates a new, empty `ReusableVecDeque`.
+    fn default() -> Self {
+        Self {
+            inner: UnsafeCell::new(VecDeque::new()),
+            poisoned: false,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+/// A RAII guard that provides temporary, exclusive access to a `VecDeque`
+/// retrieved from a [`ReusableVecDeque`].
+///
+/// When the guard is dropped, it clears the `VecDeque`.
+pub struct ReusableVecDequeGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    inner: *mut VecDeque<T2>,
+    _poison: PoisonOnPanic<'parent>,
+    _parent: PhantomData<&'parent mut ReusableVecDeque<T1>>,
+}
+
+impl<'parent, T1, T2> Deref for ReusableVecDequeGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    type Target = VecDeque<T2>;
+
+    /// Provides immutable access to the underlying `VecDeque`.
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `self.inner` is a valid pointer for the lifetime `'parent`.
+        // This is enforced by `_parent` and the `recycle` method signature.
+        unsafe { &*self.inner }
+    }
+}
+
+impl<'parent, T1, T2> DerefMut for ReusableVecDequeGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Provides mutable access to the underlying `VecDeque`.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The same guarantees as `deref` apply. The parent is mutably
+        // borrowed for `'parent`, so no other access is possible.
+        unsafe { &mut *self.inner }
+    }
+}
+
+impl<'parent, T1, T2> ReusableVecDequeGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// The estimated number of heap bytes the `VecDeque` holds on to, counting
+    /// its unused capacity.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        (**self).memory_footprint()
+    }
+
+    /// Takes the contents out, leaving the parent with a new, empty
+    /// `VecDeque`.
+    ///
+    /// The allocation leaves with the contents, so the next guard starts
+    /// without one. Use this when a cycle's result has to outlive the guard,
+    /// for example to send it to another thread.
+    pub fn detach(mut self) -> VecDeque<T2> {
+        mem::take(&mut *self)
+    }
+}
+
+impl<T1> ReusableVecDeque<T1>
+where
+    T1: 'static,
+{
+    /// Reuses the `VecDeque`'s allocation, returning a guard cast to the
+    /// element type `T2`.
+    ///
+    /// The `&mut self` requirement ensures that only one guard can be active
+    /// at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the queue and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle<'parent, T2>(&'parent mut self) -> ReusableVecDequeGuard<'parent, T1, T2>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        // SAFETY: `&mut self` guarantees exclusive access, and the queue is
+        // always empty between guards, so no `T1` value is ever read as a
+        // `T2`.
+        let inner_ptr = self.inner.get() as *mut VecDeque<T2>;
+
+        ReusableVecDequeGuard {
+            inner: inner_ptr,
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the queue without casting it.
+    ///
+    /// This is [`recycle`](Self::recycle) into the stored types, spelled
+    /// without type annotations. No [`ReuseCastInto`] impl is needed, since
+    /// nothing changes type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a panic poisoned the queue and the poison policy is
+    /// [`PoisonPolicy::Error`]. See [`try_recycle`](Self::try_recycle).
+    pub fn recycle_same(&mut self) -> ReusableVecDequeGuard<'_, T1, T1> {
+        if let Err(err) = self.check_poison() {
+            panic!("{}", err);
+        }
+        self.forget_leaked();
+        ReusableVecDequeGuard {
+            inner: self.inner.get(),
+            _poison: PoisonOnPanic::new(&mut self.poisoned),
+            _parent: PhantomData,
+        }
+    }
+
+    /// Returns a guard like [`recycle`](Self::recycle), but returns an error
+    /// instead of panicking if a panic poisoned the queue and the
+    /// [poison policy](Self::set_poison_policy) is [`PoisonPolicy::Error`].
+    pub fn try_recycle<T2>(&mut self) -> Result<ReusableVecDequeGuard<'_, T1, T2>, PoisonError>
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        self.check_poison()?;
+        Ok(self.recycle())
+    }
+
+    /// Returns `true` if a guard was dropped by a panic since the queue was
+    /// last reset or [`clear_poison`](Self::clear_poison) was called.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Marks the queue as no longer poisoned.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Sets what the next `recycle` does after a guard was dropped by a
+    /// panic: reset the queue, or refuse to hand out a guard.
+    pub fn set_poison_policy(&mut self, policy: PoisonPolicy) {
+        self.poison_policy = policy;
+    }
+
+    /// Returns the current poison policy.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// Applies the poison policy before a guard is handed out.
+    fn check_poison(&mut self) -> Result<(), PoisonError> {
+        if self.poisoned {
+            if self.poison_policy == PoisonPolicy::Error {
+                return Err(PoisonError::new());
+            }
+            self.forget_leaked();
+            *self.inner.get_mut() = VecDeque::new();
+            self.poisoned = false;
+        }
+        Ok(())
+    }
+
+    /// Recycles the queue for the duration of `f`, clearing it when `f`
+    /// returns.
+    ///
+    /// The guard can't be held past the closure, and the cast-to element
+    /// types are inferred from how `f` uses it.
+    pub fn recycle_with<T2, R>(
+        &mut self,
+        f: impl FnOnce(&mut ReusableVecDequeGuard<'_, T1, T2>) -> R,
+    ) -> R
+    where
+        T1: ReuseCastInto<T2>,
+    {
+        f(&mut self.recycle())
+    }
+
+    /// Returns the retained `VecDeque`, which is always empty.
+    pub fn into_inner(mut self) -> VecDeque<T1> {
+        self.forget_leaked();
+        mem::take(self.inner.get_mut())
+    }
+
+    /// Leaks anything a leaked guard left in the queue.
+    ///
+    /// Guards leave the queue empty, so any elements in it come from a guard
+    /// that was never dropped. They may be cast ones that borrow data that
+    /// is gone, so dropping them isn't safe.
+    fn forget_leaked(&mut self) {
+        let queue = self.inner.get_mut();
+        if !queue.is_empty() {
+            mem::forget(mem::take(queue));
+        }
+    }
+
+    /// The number of elements the retained `VecDeque` can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).capacity() }
+    }
+
+    /// The estimated number of heap bytes the retained `VecDeque` holds on to.
+    ///
+    /// See [`MemoryFootprint`] for how the estimate is made.
+    pub fn memory_footprint(&self) -> usize {
+        // SAFETY: A guard mutably borrows `self`, so none can be alive while
+        // `&self` is held.
+        unsafe { (*self.inner.get()).memory_footprint() }
+    }
+}
+
+impl<T: 'static> Drop for ReusableVecDeque<T> {
+    /// Leaks anything a leaked guard left behind, rather than dropping it
+    /// with the queue.
+    fn drop(&mut self) {
+        self.forget_leaked();
+    }
+}
+
+impl<'parent, T1, T2> Drop for ReusableVecDequeGuard<'parent, T1, T2>
+where
+    T1: 'static,
+{
+    /// Clears the `VecDeque` when the guard is dropped, keeping its
+    /// allocation.
+    fn drop(&mut self) {
+        // SAFETY: The pointer is valid for the lifetime of the guard.
+        let queue = unsafe { &mut *self.inner };
+        queue.clear();
+    }
+}
+
+/// A draining iterator over the contents of a [`ReusableVecDequeGuard`].
+///
+/// Returned by the guard's `IntoIterator` impl. The elements are moved out by
+/// value, and the `VecDeque` keeps its allocation for the next guard.
+pub struct ReusableVecDequeIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    drain: Drain<'parent, T2>,
+    // Declared after `drain`, so the guard's cleanup runs once the drain has
+    // finished with the `VecDeque`.
+    _guard: ReusableVecDequeGuard<'parent, T1, T2>,
+}
+
+impl<'parent, T1, T2> IntoIterator for ReusableVecDequeGuard<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    type Item = T2;
+    type IntoIter = ReusableVecDequeIntoIter<'parent, T1, T2>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // SAFETY: `self.inner` is valid and exclusive for `'parent`. The
+        // guard is moved into the iterator and only touches the `VecDeque` again
+        // when it is dropped, after the drain.
+        let drain = unsafe { &mut *self.inner }.drain(..);
+        ReusableVecDequeIntoIter {
+            drain,
+            _guard: self,
+        }
+    }
+}
+
+impl<'parent, T1, T2> Iterator for ReusableVecDequeIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    type Item = T2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<'parent, T1, T2> DoubleEndedIterator for ReusableVecDequeIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<'parent, T1, T2> ExactSizeIterator for ReusableVecDequeIntoIter<'parent, T1, T2>
+where
+    T1: 'static,
+    T2: 'parent,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_is_retained_across_uses() {
+        let mut deque = ReusableVecDeque::<u32>::default();
+        let capacity = {
+            let mut guard = deque.recycle::<u32>();
+            guard.extend(0..10);
+            guard.rotate_left(3);
+            guard.push_front(42);
+            assert_eq!(guard.front(), Some(&42));
+            guard.capacity()
+        };
+
+        let guard = deque.recycle::<u32>();
+        assert!(guard.is_empty());
+        assert_eq!(guard.capacity(), capacity);
+    }
+
+    #[test]
+    fn borrowed_elements_can_be_recycled() {
+        let mut deque = ReusableVecDeque::<&'static str>::default();
+        for round in 0..2 {
+            let owned = format!("event {}", round);
+            let mut guard = deque.recycle::<&str>();
+            guard.push_back(owned.as_str());
+            assert_eq!(guard.pop_front(), Some(owned.as_str()));
+        }
+    }
+
+    #[test]
+    fn a_panic_poisons_the_queue_until_it_is_reset() {
+        let mut queue = ReusableVecDeque::<u32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = queue.recycle_same();
+            guard.extend(0..64);
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert!(queue.is_poisoned());
+
+        // The default policy starts over without an allocation.
+        assert_eq!(queue.recycle_same().capacity(), 0);
+        assert!(!queue.is_poisoned());
+    }
+}
@@ -0,0 +1,151 @@
+//! Secure clearing of recycled buffers with [zeroize](https://docs.rs/zeroize).
+//!
+//! A recycled buffer keeps its allocation between cycles, and clearing it only
+//! resets its length. Whatever the previous cycle wrote is still in memory,
+//! where a later cycle reading uninitialized capacity, a heap inspection, or a
+//! core dump can find it.
+//!
+//! With [`DropPolicy::Zeroize`], [`ReusableVec`](crate::ReusableVec) and
+//! [`ReusableString`](crate::ReusableString) overwrite their entire retained
+//! capacity with zeroes whenever a guard is dropped, after the elements
+//! themselves have been dropped. The allocation is still kept, so recycling
+//! costs a `memset` per cycle but no reallocation.
+//!
+//! Two things are outside of what a drop policy can reach:
+//!
+//! - Memory owned by the elements, such as the heap buffer of each `String`
+//!   in a `Vec<String>`. Use element types that wipe themselves on drop, like
+//!   [`Zeroizing`], for those.
+//! - Buffers that were freed while the guard was alive because the collection
+//!   had to grow. [`reserve`](Vec::reserve) enough room up front if that
+//!   matters.
+//!
+//! The table of a [`HashMap`](std::collections::HashMap) is not accessible
+//! through the standard library, so [`ReusableHashMap`](crate::ReusableHashMap)
+//! has no drop policy. Its entries are dropped in place when a guard is
+//! dropped, so maps of sensitive values should wrap them in [`Zeroizing`],
+//! which can be recycled like any other value type.
+//!
+//! This module is only available with the `zeroize` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use triple_r::{zeroize::DropPolicy, ReusableVec};
+//!
+//! let mut buffer = ReusableVec::<u8>::default();
+//! buffer.set_drop_policy(DropPolicy::Zeroize);
+//!
+//! {
+//!     let mut secret = buffer.recycle();
+//!     secret.extend_from_slice(b"hunter2");
+//!     // ... use the secret ...
+//! } // The secret is overwritten with zeroes here.
+//!
+//! let buffer = buffer.recycle::<u8>();
+//! assert!(buffer.is_empty());
+//! assert!(buffer.capacity() >= 7);
+//! ```
+use crate::ReuseCastInto;
+use zeroize::{Zeroize, Zeroizing};
+
+/// What a guard does with the retained memory when it is dropped.
+///
+/// With the `serde` feature, the policy deserializes from `"clear"` or
+/// `"zeroize"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DropPolicy {
+    /// Drop the elements and keep the allocation as is. This is the default,
+    /// and what guards do without the `zeroize` feature.
+    #[default]
+    Clear,
+    /// Drop the elements, then overwrite the whole allocation with zeroes.
+    Zeroize,
+}
+
+// SAFETY: `Zeroizing<T>` is a transparent wrapper, so this is the identity
+// cast.
+unsafe impl<T: Zeroize> ReuseCastInto<Zeroizing<T>> for Zeroizing<T> {}
+
+/// Clears `vec`, then zeroes its entire capacity.
+pub(crate) fn clear_and_zeroize<T>(vec: &mut Vec<T>) {
+    vec.clear();
+    vec.spare_capacity_mut().zeroize();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReusableHashMap, ReusableString, ReusableVec};
+
+    #[test]
+    fn vec_capacity_is_zeroed() {
+        let mut buffer = ReusableVec::<u8>::default();
+        buffer.set_drop_policy(DropPolicy::Zeroize);
+        assert_eq!(buffer.drop_policy(), DropPolicy::Zeroize);
+        {
+            let mut guard = buffer.recycle();
+            guard.extend_from_slice(b"secret");
+        }
+
+        let vec = buffer.recycle();
+        assert!(vec.is_empty());
+        // SAFETY: The whole capacity has just been initialized with zeroes.
+        let retained = unsafe { std::slice::from_raw_parts(vec.as_ptr(), vec.capacity()) };
+        assert!(retained.len() >= 6);
+        assert!(retained.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn string_capacity_is_zeroed() {
+        let mut buffer = ReusableString::default();
+        buffer.set_drop_policy(DropPolicy::Zeroize);
+        {
+            let mut guard = buffer.recycle();
+            guard.push_str("password");
+        }
+
+        let mut string = buffer.recycle();
+        assert!(string.is_empty());
+        // SAFETY: The bytes are only read, and the whole capacity has just
+        // been initialized with zeroes.
+        let retained = unsafe {
+            let bytes = string.as_mut_vec();
+            std::slice::from_raw_parts(bytes.as_ptr(), bytes.capacity())
+        };
+        assert!(retained.len() >= 8);
+        assert!(retained.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn clear_is_the_default() {
+        let mut buffer = ReusableVec::<u8>::default();
+        assert_eq!(buffer.drop_policy(), DropPolicy::Clear);
+        buffer.recycle().extend_from_slice(b"kept");
+
+        let vec = buffer.recycle();
+        // SAFETY: The first four bytes were initialized by the last cycle.
+        let retained = unsafe { std::slice::from_raw_parts(vec.as_ptr(), 4) };
+        assert_eq!(retained, b"kept");
+    }
+
+    #[test]
+    fn zeroizing_values_can_be_recycled() {
+        let mut map = ReusableHashMap::<u32, Zeroizing<[u8; 4]>>::default();
+        map.recycle().insert(1, Zeroizing::new(*b"key!"));
+        assert!(map.recycle::<u32, Zeroizing<[u8; 4]>>().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn policies_deserialize_from_snake_case() {
+        let policy: DropPolicy = serde_json::from_str(r#""zeroize""#).unwrap();
+        assert_eq!(policy, DropPolicy::Zeroize);
+        assert!(serde_json::from_str::<DropPolicy>(r#""Zeroize""#).is_err());
+    }
+}
@@ -18,3 +18,17 @@ fn ui_string() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/ui-string/*.rs");
 }
+
+#[test]
+#[cfg(all(not(miri), feature = "derive"))]
+fn ui_derive() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui-derive/*.rs");
+}
+
+#[test]
+#[cfg(not(miri))]
+fn ui_cast() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui-cast/*.rs");
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "derive")]
+
+use std::collections::HashMap;
+use triple_r::{Recycle, ReusableHashMap, ReusableString, ReusableVec, ReuseCast};
+
+#[derive(Default, Recycle)]
+struct Scratch {
+    ids: ReusableVec<u64>,
+    lines: ReusableVec<String>,
+    counts: ReusableHashMap<u64, usize>,
+    output: ReusableString,
+}
+
+#[derive(Default, Recycle)]
+struct Pair<T: 'static + Default + Copy>(ReusableVec<T>, ReusableVec<T>);
+
+#[test]
+fn all_fields_are_recycled_together() {
+    let mut scratch = Scratch::default();
+    for round in 0..3u64 {
+        let mut guard = scratch.recycle();
+        assert!(guard.ids.is_empty());
+        assert!(guard.lines.is_empty());
+        assert!(guard.counts.is_empty());
+        assert!(guard.output.is_empty());
+
+        guard.ids.extend(0..round + 10);
+        guard.lines.push(format!("round {}", round));
+        *guard.counts.entry(round).or_default() += 1;
+        guard.output.push_str("done");
+    }
+
+    let guard = scratch.recycle();
+    assert!(guard.ids.capacity() >= 12);
+    let counts: &HashMap<u64, usize> = &guard.counts;
+    assert!(counts.is_empty());
+}
+
+#[test]
+fn tuple_structs_with_generics_are_supported() {
+    let mut pair = Pair::<f32>::default();
+    {
+        let mut guard = pair.recycle();
+        guard.0.push(1.0);
+        guard.1.extend([2.0, 3.0]);
+    }
+    let guard = pair.recycle();
+    assert!(guard.0.is_empty() && guard.1.is_empty());
+}
+
+#[derive(Debug, PartialEq, ReuseCast)]
+#[repr(transparent)]
+struct UserId(u64);
+
+#[derive(Debug, PartialEq, ReuseCast)]
+#[repr(transparent)]
+struct Token<'a>(&'a str);
+
+#[derive(ReuseCast)]
+#[repr(transparent)]
+struct Tagged<T, Tag> {
+    value: T,
+    _tag: std::marker::PhantomData<Tag>,
+}
+
+#[test]
+fn transparent_newtypes_can_be_recycled() {
+    let mut ids = ReusableVec::<UserId>::default();
+    ids.recycle::<UserId>().push(UserId(7));
+
+    let mut tokens = ReusableVec::<Token<'static>>::default();
+    for line in [String::from("a b"), String::from("c")] {
+        let mut guard = tokens.recycle::<Token<'_>>();
+        guard.extend(line.split(' ').map(Token));
+        assert_eq!(guard[0], Token(&line[..1]));
+    }
+
+    let mut tagged = ReusableVec::<Tagged<&'static str, ()>>::default();
+    let owned = String::from("tagged");
+    tagged.recycle::<Tagged<&str, ()>>().push(Tagged {
+        value: owned.as_str(),
+        _tag: std::marker::PhantomData,
+    });
+}
+
+#[derive(Debug, PartialEq, ReuseCast)]
+struct Span<'a> {
+    text: &'a str,
+    line: u32,
+}
+
+#[derive(ReuseCast)]
+struct Labeled<'a, T> {
+    label: &'a str,
+    value: T,
+}
+
+#[test]
+fn covariant_structs_can_shorten_lifetimes() {
+    let mut spans = ReusableVec::<Span<'static>>::default();
+    for (line, text) in [String::from("first"), String::from("second")]
+        .iter()
+        .enumerate()
+    {
+        let mut guard = spans.recycle::<Span<'_>>();
+        guard.push(Span {
+            text: text.as_str(),
+            line: line as u32,
+        });
+        assert_eq!(guard[0].text, text);
+    }
+
+    let mut labeled = ReusableVec::<Labeled<'static, u64>>::default();
+    let label = String::from("count");
+    let mut guard = labeled.recycle::<Labeled<'_, u64>>();
+    guard.push(Labeled {
+        label: label.as_str(),
+        value: 1,
+    });
+    assert_eq!((guard[0].label, guard[0].value), ("count", 1));
+}
@@ -0,0 +1,6 @@
+struct Small(u8);
+struct Large(u64);
+
+triple_r::impl_reuse_cast!(Small => Large);
+
+fn main() {}
@@ -0,0 +1,5 @@
+struct Plain(std::ptr::NonNull<u8>, usize, usize);
+
+triple_r::impl_reuse_cast!(Plain => String);
+
+fn main() {}
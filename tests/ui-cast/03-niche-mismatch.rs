@@ -0,0 +1,6 @@
+struct Flag(bool);
+struct Byte(u8);
+
+triple_r::impl_reuse_cast!(Flag => Byte);
+
+fn main() {}
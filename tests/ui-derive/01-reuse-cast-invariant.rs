@@ -0,0 +1,9 @@
+use std::cell::Cell;
+use triple_r::ReuseCast;
+
+#[derive(ReuseCast)]
+struct Slot<'a> {
+    value: Cell<&'a str>,
+}
+
+fn main() {}
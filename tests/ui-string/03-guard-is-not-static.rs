@@ -5,11 +5,10 @@ fn main() {
     let mut s = ReusableString::default();
     let guard = s.recycle();
 
-    // This closure requires `guard` to be `Send`.
+    // The compiler should prevent this because `ReusableStringGuard` borrows
+    // `s`, and `thread::spawn` requires a closure that owns everything it
+    // uses. Use `thread::scope` to fill a guard on another thread.
     let handle = thread::spawn(move || {
-        // The compiler should prevent this because `ReusableStringGuard` is not `Send`.
-        // It holds a raw pointer to the string's data, which is not safe to move across
-        // threads.
         println!("Length: {}", guard.len());
     });
 
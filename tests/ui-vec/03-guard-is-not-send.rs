@@ -1,17 +1,15 @@
-use std::thread;
+use std::{rc::Rc, thread};
 use triple_r::vec::ReusableVec;
 
 fn main() {
-    let mut vec = ReusableVec::<String>::default();
-    let guard = vec.recycle::<String>();
+    let mut vec = ReusableVec::<Rc<String>>::default();
+    let guard = vec.recycle_same();
 
-    // This closure requires `guard` to be `Send`.
-    let handle = thread::spawn(move || {
-        // The compiler should prevent this because `ReusableVecGuard` is not `Send`.
-        // It holds a raw pointer to the vector's data, which is not safe to move across
-        // threads.
-        println!("Length: {}", guard.len());
+    thread::scope(|scope| {
+        // The compiler should prevent this because `ReusableVecGuard` is only
+        // `Send` when its elements are, and `Rc` is not.
+        scope.spawn(move || {
+            println!("Length: {}", guard.len());
+        });
     });
-
-    handle.join().unwrap();
 }
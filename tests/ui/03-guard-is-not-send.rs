@@ -1,17 +1,15 @@
-use std::thread;
+use std::{rc::Rc, thread};
 use triple_r::hashmap::ReusableHashMap;
 
 fn main() {
-    let mut map = ReusableHashMap::<String, String>::default();
-    let guard = map.recycle::<String, String>();
+    let mut map = ReusableHashMap::<Rc<String>, String>::default();
+    let guard = map.recycle_same();
 
-    // This closure requires `guard` to be `Send`.
-    let handle = thread::spawn(move || {
-        // The compiler should prevent this because `ReusableHashMapGuard` is not `Send`.
-        // It holds a mutable reference to `map`, which is not safe to share across
-        // threads without synchronization.
-        println!("Length: {}", guard.len());
+    thread::scope(|scope| {
+        // The compiler should prevent this because `ReusableHashMapGuard` is
+        // only `Send` when its keys and values are, and `Rc` is not.
+        scope.spawn(move || {
+            println!("Length: {}", guard.len());
+        });
     });
-
-    handle.join().unwrap();
 }
@@ -0,0 +1,47 @@
+//! Derive macros for [`triple-r`](https://docs.rs/triple-r).
+//!
+//! These are re-exported by `triple-r` when its `derive` feature is enabled,
+//! and should be used through it.
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, Error};
+
+mod recycle;
+mod reuse_cast;
+
+/// Implements `triple_r::Recycle` for a struct whose fields are all reusable
+/// containers.
+///
+/// The derive generates a `<Name>Guard<'recycle>` struct with the same
+/// fields, each holding the guard of the corresponding container, and a
+/// `Recycle` impl that recycles every field at once. Dropping the generated
+/// guard drops every field guard, so all containers are cleared together.
+#[proc_macro_derive(Recycle)]
+pub fn derive_recycle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    recycle::expand_recycle(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implements `triple_r::ReuseCastInto` for a struct, so it can be cast
+/// between lifetimes wherever its fields can.
+///
+/// A `Span<'a> { text: &'a str, line: u32 }` can then be cast between
+/// lifetimes, so a `ReusableVec<Span<'static>>` can hold the `Span<'a>`s of
+/// one iteration, and a struct without lifetimes, such as `UserId(u64)`, is
+/// cast to itself. Lifetimes never change a type's layout, but the struct
+/// must be covariant in all of its lifetime parameters, like `&'a T`, which
+/// is checked at compile time.
+///
+/// The type parameters of a `#[repr(transparent)]` struct are cast too,
+/// along with the fields that use them, since such a struct always has the
+/// layout of its one non-zero-sized field. Other structs keep their type
+/// parameters, because their layout could differ between two
+/// instantiations. `PhantomData` fields are skipped.
+#[proc_macro_derive(ReuseCast)]
+pub fn derive_reuse_cast(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    reuse_cast::expand_reuse_cast(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,103 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Lifetime};
+
+pub(crate) fn expand_recycle(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) if !data.fields.is_empty() => &data.fields,
+        Data::Struct(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`Recycle` needs at least one field to recycle",
+            ))
+        }
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`Recycle` can only be derived for structs",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let guard = format_ident!("{}Guard", name);
+    let lifetime = Lifetime::new("'recycle", Span::call_site());
+    let doc = format!("The guards of every field of a recycled [`{}`].", name);
+
+    let mut guard_generics = input.generics.clone();
+    guard_generics
+        .params
+        .insert(0, syn::parse_quote!(#lifetime));
+    let (guard_impl_generics, guard_ty_generics, _) = guard_generics.split_for_impl();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let field_types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let mut guard_where = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    for ty in &field_types {
+        guard_where
+            .predicates
+            .push(syn::parse_quote!(#ty: ::triple_r::Recycle + #lifetime));
+    }
+
+    let guard_fields = fields.iter().map(|field| {
+        let attrs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"));
+        let vis = &field.vis;
+        let ty = &field.ty;
+        let ty = quote!(<#ty as ::triple_r::Recycle>::Guard<#lifetime>);
+        match &field.ident {
+            Some(ident) => quote!(#(#attrs)* #vis #ident: #ty),
+            None => quote!(#(#attrs)* #vis #ty),
+        }
+    });
+    let recycled = fields.iter().enumerate().map(|(index, field)| {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index)
+            }
+        };
+        quote!(#member: ::triple_r::Recycle::recycle(&mut self.#member))
+    });
+
+    let guard_def = match fields {
+        Fields::Unnamed(_) => quote! {
+            #vis struct #guard #guard_impl_generics (#(#guard_fields,)*) #guard_where;
+        },
+        _ => quote! {
+            #vis struct #guard #guard_impl_generics #guard_where {
+                #(#guard_fields,)*
+            }
+        },
+    };
+
+    let mut impl_where = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    for ty in &field_types {
+        impl_where
+            .predicates
+            .push(syn::parse_quote!(#ty: ::triple_r::Recycle));
+    }
+
+    Ok(quote! {
+        #[doc = #doc]
+        #guard_def
+
+        impl #impl_generics ::triple_r::Recycle for #name #ty_generics #impl_where {
+            type Guard<#lifetime> = #guard #guard_ty_generics
+            where
+                Self: #lifetime;
+
+            fn recycle(&mut self) -> Self::Guard<'_> {
+                #guard { #(#recycled,)* }
+            }
+        }
+    })
+}
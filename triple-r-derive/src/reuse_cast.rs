@@ -0,0 +1,221 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    visit_mut::VisitMut, Data, DeriveInput, Error, GenericParam, Generics, Ident, Lifetime, Type,
+};
+
+pub(crate) fn expand_reuse_cast(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`ReuseCast` can only be derived for structs",
+            ))
+        }
+    };
+    let transparent = is_repr_transparent(&input)?;
+    let name = &input.ident;
+    let mut rename = Rename::new(&input.generics, transparent);
+    let mut target_generics = input.generics.clone();
+    rename.visit_generics_mut(&mut target_generics);
+
+    // The impl is generic over both the source and the target parameters.
+    // Parameters that are not renamed are shared, so only the source copy is
+    // kept.
+    let mut impl_generics = input.generics.clone();
+    impl_generics.params.extend(
+        target_generics
+            .params
+            .iter()
+            .filter(|param| rename.renames(param))
+            .cloned(),
+    );
+    let where_clause = impl_generics.make_where_clause();
+    if let Some(target_where) = &target_generics.where_clause {
+        where_clause
+            .predicates
+            .extend(target_where.predicates.iter().cloned());
+    }
+    for field in fields.iter().filter(|field| !is_phantom_data(&field.ty)) {
+        let source = &field.ty;
+        let mut target = field.ty.clone();
+        rename.visit_type_mut(&mut target);
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#source: ::triple_r::ReuseCastInto<#target>));
+    }
+
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+    let (_, source_ty_generics, _) = input.generics.split_for_impl();
+    let (_, target_ty_generics, _) = target_generics.split_for_impl();
+
+    let variance_check = variance_check(&input, &rename);
+
+    Ok(quote! {
+        #variance_check
+
+        // SAFETY: Either the struct is `#[repr(transparent)]`, so it has the
+        // layout of its one non-zero-sized field and that field can be cast,
+        // or only its lifetimes change, which never affects its layout.
+        unsafe impl #impl_generics ::triple_r::ReuseCastInto<#name #target_ty_generics>
+            for #name #source_ty_generics
+            #where_clause
+        {
+        }
+    })
+}
+
+/// Emits a function that only compiles if the struct is covariant in all of
+/// its lifetime parameters.
+fn variance_check(input: &DeriveInput, rename: &Rename) -> TokenStream {
+    if rename.lifetimes.is_empty() {
+        return TokenStream::new();
+    }
+
+    let name = &input.ident;
+    let mut long = Rename::lifetimes_only(&input.generics, "long");
+    let mut short = Rename::lifetimes_only(&input.generics, "short");
+    let mut long_generics = input.generics.clone();
+    long.visit_generics_mut(&mut long_generics);
+    let mut short_generics = input.generics.clone();
+    short.visit_generics_mut(&mut short_generics);
+
+    let mut generics = long_generics.clone();
+    generics.params.extend(
+        short_generics
+            .params
+            .iter()
+            .filter(|param| matches!(param, GenericParam::Lifetime(_)))
+            .cloned(),
+    );
+    let where_clause = generics.make_where_clause();
+    if let Some(short_where) = &short_generics.where_clause {
+        where_clause
+            .predicates
+            .extend(short_where.predicates.iter().cloned());
+    }
+    for ((_, long), (_, short)) in long.lifetimes.iter().zip(&short.lifetimes) {
+        let long = Lifetime {
+            apostrophe: Span::call_site(),
+            ident: long.clone(),
+        };
+        let short = Lifetime {
+            apostrophe: Span::call_site(),
+            ident: short.clone(),
+        };
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#long: #short));
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, long_ty_generics, _) = long_generics.split_for_impl();
+    let (_, short_ty_generics, _) = short_generics.split_for_impl();
+
+    quote! {
+        const _: () = {
+            #[allow(dead_code)]
+            fn reuse_cast_requires_covariant_lifetimes #impl_generics (
+                value: #name #long_ty_generics,
+            ) -> #name #short_ty_generics
+            #where_clause
+            {
+                value
+            }
+        };
+    }
+}
+
+fn is_repr_transparent(input: &DeriveInput) -> syn::Result<bool> {
+    let mut transparent = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("transparent") {
+                transparent = true;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(transparent)
+}
+
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "PhantomData"),
+        _ => false,
+    }
+}
+
+/// Renames the type and lifetime parameters of a struct to fresh names for
+/// the target side of the cast.
+struct Rename {
+    types: Vec<(Ident, Ident)>,
+    lifetimes: Vec<(Ident, Ident)>,
+}
+
+impl Rename {
+    /// Renames every lifetime, and the type parameters too if `types` is set.
+    fn new(generics: &Generics, types: bool) -> Self {
+        let mut rename = Self::lifetimes_only(generics, "reuse_cast");
+        if types {
+            for ty in generics.type_params() {
+                let target = Ident::new(&format!("__ReuseCast{}", ty.ident), Span::call_site());
+                rename.types.push((ty.ident.clone(), target));
+            }
+        }
+        rename
+    }
+
+    fn lifetimes_only(generics: &Generics, prefix: &str) -> Self {
+        let lifetimes = generics
+            .lifetimes()
+            .map(|lt| {
+                let ident = &lt.lifetime.ident;
+                let target = Ident::new(&format!("__{}_{}", prefix, ident), Span::call_site());
+                (ident.clone(), target)
+            })
+            .collect();
+        Self {
+            types: Vec::new(),
+            lifetimes,
+        }
+    }
+
+    fn renames(&self, param: &GenericParam) -> bool {
+        match param {
+            GenericParam::Type(ty) => self.types.iter().any(|(_, target)| *target == ty.ident),
+            GenericParam::Lifetime(lt) => self
+                .lifetimes
+                .iter()
+                .any(|(_, target)| *target == lt.lifetime.ident),
+            GenericParam::Const(_) => false,
+        }
+    }
+}
+
+impl VisitMut for Rename {
+    fn visit_ident_mut(&mut self, ident: &mut Ident) {
+        if let Some((_, target)) = self.types.iter().find(|(source, _)| source == ident) {
+            *ident = target.clone();
+        }
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if let Some((_, target)) = self
+            .lifetimes
+            .iter()
+            .find(|(source, _)| *source == lifetime.ident)
+        {
+            lifetime.ident = target.clone();
+        }
+    }
+}